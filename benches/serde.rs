@@ -0,0 +1,43 @@
+//! Times parsing and serializing every sample microcontroller.
+//!
+//! Run with `cargo bench --bench serde`.
+
+use std::time::{Duration, Instant};
+
+use sw_rs::microcontroller::Microcontroller;
+
+const ITERATIONS: u32 = 50;
+
+fn main() {
+    let mut samples: Vec<_> = std::fs::read_dir("samples/microcontroller")
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .filter(|p| p.extension().is_some_and(|e| e == "xml"))
+        .map(|p| std::fs::read_to_string(p).unwrap())
+        .collect();
+    samples.sort();
+    let bytes: usize = samples.iter().map(String::len).sum();
+
+    let mut parse = Duration::ZERO;
+    let mut serialize = Duration::ZERO;
+    for _ in 0..ITERATIONS {
+        for src in &samples {
+            let start = Instant::now();
+            let mc = Microcontroller::from_xml_str(src).unwrap();
+            parse += start.elapsed();
+
+            let start = Instant::now();
+            std::hint::black_box(mc.to_xml_string().unwrap());
+            serialize += start.elapsed();
+        }
+    }
+
+    println!(
+        "{} samples ({} KiB), {ITERATIONS} iterations",
+        samples.len(),
+        bytes / 1024
+    );
+    for (name, total) in [("parse", parse), ("serialize", serialize)] {
+        println!("{name:>9}: {:.2?} per pass", total / ITERATIONS);
+    }
+}
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 use sw_rs::microcontroller::{
-    components::{ComponentConnection, ComponentType, TypedInputConnection},
+    components::{ComponentConnection, ComponentType, CompositeChannel, TypedInputConnection},
     Microcontroller,
 };
 
@@ -17,7 +17,7 @@ fn main() {
                 node_index: 0,
             }),
             variable_channel: TypedInputConnection::empty(),
-            channel: 0,
+            channel: CompositeChannel::Fixed(0),
             out: Default::default(),
         },
     };
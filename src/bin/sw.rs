@@ -0,0 +1,699 @@
+//! `sw`, a command line tool for inspecting and tidying Stormworks files.
+//!
+//! Run `sw help` for the commands. Every command takes `--json` to print machine readable output
+//! instead of text. The exit code is 0 on success, 1 if a file was invalid, couldn't be read or
+//! (with `mc fmt --check`) isn't formatted, and 2 for bad arguments.
+
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use serde_json::{json, Value};
+use sw_rs::{
+    component::definition::ComponentDefinition,
+    mesh::{Material, Mesh},
+    microcontroller::{
+        lint::{Lint, Severity},
+        mc_serde::microcontroller::IONodeType,
+        properties::{PropertyKind, PropertyValue},
+        types::Type,
+        Microcontroller,
+    },
+    util::{discovery::Discovery, serde_utils::Vector3F, AnyComponentRef},
+    vehicle::Vehicle,
+};
+
+const USAGE: &str = "\
+Usage: sw <command> [options]
+
+Commands:
+  mc validate <file>...     Check microcontrollers for errors and lints
+  mc fmt [--check] <file>...
+                            Re-save microcontrollers in canonical form
+  mc info <file>            Show a microcontroller's IO, components and properties
+  vehicle info <file>       Show a vehicle's bodies and components
+  def show [--defs <dir>] <name>
+                            Show a component definition, by file name or display name
+  mesh info <file>          Show a mesh's vertices, triangles and submeshes
+  help                      Show this message
+
+Options:
+  --json                    Print JSON instead of text
+  --check                   (mc fmt) Don't write anything, fail if a file would change
+  --defs <dir>              (def show) Definitions folder, found from the game install if
+                            not given
+
+Exit codes: 0 success, 1 invalid or unreadable input, 2 bad arguments.
+";
+
+enum CliError {
+    /// Bad arguments, exits with 2.
+    Usage(String),
+    /// The command couldn't do its job, exits with 1.
+    Failed(String),
+}
+
+fn usage(msg: impl Into<String>) -> CliError {
+    CliError::Usage(msg.into())
+}
+
+/// Parsed options and positional arguments of a command.
+#[derive(Default)]
+struct Options {
+    json: bool,
+    check: bool,
+    defs: Option<PathBuf>,
+    args: Vec<String>,
+}
+
+impl Options {
+    /// Parses `args`, allowing `--json` and the options in `accepted`.
+    fn parse(args: &[String], accepted: &[&str]) -> Result<Self, CliError> {
+        let mut opts = Self::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--" => {
+                    opts.args.extend(args.by_ref().cloned());
+                },
+                "--json" => opts.json = true,
+                "--check" if accepted.contains(&"--check") => opts.check = true,
+                "--defs" if accepted.contains(&"--defs") => {
+                    let dir = args
+                        .next()
+                        .ok_or_else(|| usage("`--defs` needs a folder"))?;
+                    opts.defs = Some(dir.into());
+                },
+                a if a.starts_with('-') && a.len() > 1 => {
+                    return Err(usage(format!("unknown option `{a}`")));
+                },
+                _ => opts.args.push(arg.clone()),
+            }
+        }
+        Ok(opts)
+    }
+
+    /// Gets the only positional argument.
+    fn one(&self, what: &str) -> Result<&str, CliError> {
+        match self.args.as_slice() {
+            [arg] => Ok(arg),
+            [] => Err(usage(format!("missing {what}"))),
+            _ => Err(usage(format!("expected a single {what}"))),
+        }
+    }
+
+    /// Gets the positional arguments, requiring at least one.
+    fn files(&self) -> Result<&[String], CliError> {
+        if self.args.is_empty() {
+            Err(usage("missing file"))
+        } else {
+            Ok(&self.args)
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let json = args
+        .iter()
+        .take_while(|a| *a != "--")
+        .any(|a| a == "--json");
+    match run(&args) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(CliError::Failed(msg)) if json => {
+            print_json(&json!({ "error": msg }));
+            ExitCode::from(1)
+        },
+        Err(CliError::Failed(msg)) => {
+            eprintln!("error: {msg}");
+            ExitCode::from(1)
+        },
+        Err(CliError::Usage(msg)) => {
+            eprintln!("error: {msg}\nRun `sw help` for usage.");
+            ExitCode::from(2)
+        },
+    }
+}
+
+/// Runs a command, returning whether it succeeded.
+fn run(args: &[String]) -> Result<bool, CliError> {
+    let Some((group, rest)) = args.split_first() else {
+        eprint!("{USAGE}");
+        return Err(usage("missing command"));
+    };
+    match group.as_str() {
+        "help" | "-h" | "--help" => {
+            print!("{USAGE}");
+            return Ok(true);
+        },
+        "-V" | "--version" => {
+            println!("sw {}", env!("CARGO_PKG_VERSION"));
+            return Ok(true);
+        },
+        _ => {},
+    }
+
+    let Some((cmd, rest)) = rest.split_first() else {
+        return Err(usage(format!("missing `{group}` command")));
+    };
+    match (group.as_str(), cmd.as_str()) {
+        ("mc", "validate") => mc_validate(&Options::parse(rest, &[])?),
+        ("mc", "fmt") => mc_fmt(&Options::parse(rest, &["--check"])?),
+        ("mc", "info") => mc_info(&Options::parse(rest, &[])?),
+        ("vehicle", "info") => vehicle_info(&Options::parse(rest, &[])?),
+        ("def", "show") => def_show(&Options::parse(rest, &["--defs"])?),
+        ("mesh", "info") => mesh_info(&Options::parse(rest, &[])?),
+        _ => Err(usage(format!("unknown command `{group} {cmd}`"))),
+    }
+}
+
+fn print_json(value: &Value) {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(value).expect("JSON values always serialize")
+    );
+}
+
+fn read(path: &str) -> Result<String, String> {
+    std::fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))
+}
+
+fn load_mc(path: &str) -> Result<Microcontroller, CliError> {
+    let src = read(path).map_err(CliError::Failed)?;
+    Microcontroller::from_xml_str(&src).map_err(|e| CliError::Failed(format!("{path}: {e}")))
+}
+
+fn vector_json(v: &Vector3F) -> Value {
+    json!({ "x": v.x, "y": v.y, "z": v.z })
+}
+
+// ---- mc validate ----
+
+/// Result of validating one microcontroller file.
+struct Checked {
+    /// Read, parse and validation errors.
+    errors: Vec<String>,
+    lints: Vec<Lint>,
+}
+
+/// The errors don't include the path, it's added when they're reported.
+fn check_mc(path: &str) -> Checked {
+    let mc = std::fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|src| Microcontroller::from_xml_str_unvalidated(&src).map_err(|e| e.to_string()));
+    match mc {
+        // report every problem, not just the first
+        Ok(mc) => Checked {
+            errors: mc.validate_all().iter().map(ToString::to_string).collect(),
+            lints: mc.lint(),
+        },
+        Err(e) => Checked { errors: vec![e], lints: vec![] },
+    }
+}
+
+fn mc_validate(opts: &Options) -> Result<bool, CliError> {
+    let mut ok = true;
+    let mut reports = vec![];
+    for path in opts.files()? {
+        let checked = check_mc(path);
+        let valid = checked.errors.is_empty()
+            && !checked.lints.iter().any(|l| l.severity == Severity::Error);
+        ok &= valid;
+
+        if opts.json {
+            let lints: Vec<_> = checked
+                .lints
+                .iter()
+                .map(|l| {
+                    json!({
+                        "rule": l.rule.id(),
+                        "severity": l.severity.to_string(),
+                        "component_ids": l.component_ids,
+                        "message": l.message,
+                    })
+                })
+                .collect();
+            reports.push(json!({
+                "file": path,
+                "valid": valid,
+                "errors": checked.errors,
+                "lints": lints,
+            }));
+        } else {
+            for e in &checked.errors {
+                println!("{path}: error: {e}");
+            }
+            for l in &checked.lints {
+                println!("{path}: {l}");
+            }
+            if valid {
+                println!("{path}: ok");
+            }
+        }
+    }
+    if opts.json {
+        print_json(&Value::Array(reports));
+    }
+    Ok(ok)
+}
+
+// ---- mc fmt ----
+
+fn mc_fmt(opts: &Options) -> Result<bool, CliError> {
+    let mut ok = true;
+    let mut reports = vec![];
+    for path in opts.files()? {
+        let formatted = read(path).and_then(|src| {
+            let mut mc = Microcontroller::from_xml_str(&src).map_err(|e| format!("{path}: {e}"))?;
+            mc.canonicalize();
+            let out = mc.to_xml_string().map_err(|e| format!("{path}: {e}"))?;
+            Ok((src != out).then_some(out))
+        });
+
+        let (changed, error) = match formatted {
+            Ok(Some(out)) if !opts.check => match std::fs::write(path, out) {
+                Ok(()) => (true, None),
+                Err(e) => (true, Some(format!("{path}: {e}"))),
+            },
+            Ok(out) => (out.is_some(), None),
+            Err(e) => (false, Some(e)),
+        };
+        ok &= error.is_none() && !(opts.check && changed);
+
+        if opts.json {
+            reports.push(json!({ "file": path, "changed": changed, "error": error }));
+        } else if let Some(e) = error {
+            eprintln!("error: {e}");
+        } else if changed && opts.check {
+            println!("{path}: would reformat");
+        } else if changed {
+            println!("{path}: reformatted");
+        } else {
+            println!("{path}: unchanged");
+        }
+    }
+    if opts.json {
+        print_json(&Value::Array(reports));
+    }
+    Ok(ok)
+}
+
+// ---- mc info ----
+
+fn property_kind_json(kind: &PropertyKind) -> Value {
+    match kind {
+        PropertyKind::Slider { min, max, rounding } => {
+            json!({ "kind": "slider", "min": min, "max": max, "rounding": rounding })
+        },
+        PropertyKind::Dropdown { options } => {
+            let options: Vec<_> = options
+                .iter()
+                .map(|(label, value)| json!({ "label": label, "value": value }))
+                .collect();
+            json!({ "kind": "dropdown", "options": options })
+        },
+        PropertyKind::Toggle { on, off } => json!({ "kind": "toggle", "on": on, "off": off }),
+        PropertyKind::Number => json!({ "kind": "number" }),
+        PropertyKind::Text => json!({ "kind": "text" }),
+    }
+}
+
+fn property_value_json(value: &PropertyValue) -> Value {
+    match value {
+        PropertyValue::Number(n) => json!(n),
+        PropertyValue::Bool(b) => json!(b),
+        PropertyValue::Text(s) => json!(s),
+    }
+}
+
+fn mc_info(opts: &Options) -> Result<bool, CliError> {
+    let mc = load_mc(opts.one("file")?)?;
+
+    // components by type, including the ones in nested groups but not IO nodes
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for c in mc.components() {
+        if let AnyComponentRef::Component(c) = c {
+            *counts.entry(c.component.name()).or_default() += 1;
+        }
+    }
+    let mut groups = mc.groups().iter().collect::<Vec<_>>();
+    let mut n_groups = 0;
+    while let Some(g) = groups.pop() {
+        n_groups += 1;
+        for c in &g.components {
+            *counts.entry(c.component.name()).or_default() += 1;
+        }
+        groups.extend(&g.groups);
+    }
+    let n_components: usize = counts.values().sum();
+    let properties = mc.properties();
+
+    if opts.json {
+        let io: Vec<_> = mc
+            .io_nodes()
+            .iter()
+            .map(|ion| {
+                let d = &ion.design;
+                json!({
+                    "label": d.label,
+                    "description": d.description,
+                    "type": d.typ.to_string(),
+                    "mode": match d.mode {
+                        IONodeType::Input => "input",
+                        IONodeType::Output => "output",
+                    },
+                    "position": { "x": d.position.x, "y": d.position.y },
+                })
+            })
+            .collect();
+        let properties: Vec<_> = properties
+            .iter()
+            .map(|p| {
+                let mut v = property_kind_json(&p.kind);
+                v["component_id"] = json!(p.component_id);
+                v["name"] = json!(p.name);
+                v["value"] = property_value_json(&p.value);
+                v
+            })
+            .collect();
+        print_json(&json!({
+            "name": mc.name,
+            "description": mc.description,
+            "width": mc.width,
+            "length": mc.length,
+            "io": io,
+            "components": n_components,
+            "component_types": counts,
+            "groups": n_groups,
+            "properties": properties,
+        }));
+        return Ok(true);
+    }
+
+    println!("{} ({}x{})", mc.name, mc.width, mc.length);
+    println!("{}", mc.description);
+    for mode in [IONodeType::Input, IONodeType::Output] {
+        let nodes: Vec<_> = mc
+            .io_nodes()
+            .iter()
+            .filter(|ion| ion.design.mode == mode)
+            .collect();
+        println!();
+        match mode {
+            IONodeType::Input => println!("Inputs ({}):", nodes.len()),
+            IONodeType::Output => println!("Outputs ({}):", nodes.len()),
+        }
+        for ion in nodes {
+            let d = &ion.design;
+            println!(
+                "  {} ({}) at {},{}",
+                d.label, d.typ, d.position.x, d.position.y
+            );
+        }
+    }
+
+    println!();
+    println!("Components ({n_components}, {n_groups} groups):");
+    let width = counts.keys().map(|k| k.len()).max().unwrap_or(0);
+    for (name, count) in &counts {
+        println!("  {name:width$}  {count}");
+    }
+
+    if !properties.is_empty() {
+        println!();
+        println!("Properties ({}):", properties.len());
+        for p in &properties {
+            let value = match &p.value {
+                PropertyValue::Number(n) => n.to_string(),
+                PropertyValue::Bool(b) => b.to_string(),
+                PropertyValue::Text(s) => format!("{s:?}"),
+            };
+            let kind = match &p.kind {
+                PropertyKind::Slider { min, max, .. } => format!("slider {min}..={max}"),
+                PropertyKind::Dropdown { options } => {
+                    format!("dropdown, {} options", options.len())
+                },
+                PropertyKind::Toggle { .. } => "toggle".into(),
+                PropertyKind::Number => "number".into(),
+                PropertyKind::Text => "text".into(),
+            };
+            println!("  {} = {value} ({kind})", p.name);
+        }
+    }
+    Ok(true)
+}
+
+// ---- vehicle info ----
+
+fn vehicle_info(opts: &Options) -> Result<bool, CliError> {
+    let path = opts.one("file")?;
+    let src = read(path).map_err(CliError::Failed)?;
+    let failed = |e: &dyn std::fmt::Display| CliError::Failed(format!("{path}: {e}"));
+    let src = Vehicle::<()>::prettify_xml(&src).map_err(|e| failed(&e))?;
+    let vehicle: Vehicle = Vehicle::from_xml_str(&src).map_err(|e| failed(&e))?;
+
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for body in &vehicle.bodies.nodes {
+        for c in &body.components.nodes {
+            *counts.entry(&c.definition).or_default() += 1;
+        }
+    }
+    let n_components: usize = counts.values().sum();
+
+    if opts.json {
+        let bodies: Vec<_> = vehicle
+            .bodies
+            .nodes
+            .iter()
+            .map(|b| json!({ "id": b.unique_id, "components": b.components.nodes.len() }))
+            .collect();
+        print_json(&json!({
+            "data_version": vehicle.data_version,
+            "bodies": bodies,
+            "components": n_components,
+            "definitions": counts,
+        }));
+        return Ok(true);
+    }
+
+    println!("Data version {}", vehicle.data_version);
+    println!();
+    println!("Bodies ({}):", vehicle.bodies.nodes.len());
+    for b in &vehicle.bodies.nodes {
+        println!("  {}: {} components", b.unique_id, b.components.nodes.len());
+    }
+    println!();
+    println!("Components ({n_components}):");
+    let width = counts.keys().map(|k| k.len()).max().unwrap_or(0);
+    for (definition, count) in &counts {
+        println!("  {definition:width$}  {count}");
+    }
+    Ok(true)
+}
+
+// ---- def show ----
+
+/// Finds a definition by file name, or failing that by its display name (ignoring case).
+fn find_definition(dir: &Path, name: &str) -> Result<(PathBuf, ComponentDefinition), CliError> {
+    let load = |path: &Path| {
+        let src = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        ComponentDefinition::from_xml_str(&src).map_err(|e| e.to_string())
+    };
+
+    let stem = name.strip_suffix(".xml").unwrap_or(name);
+    let path = dir.join(format!("{stem}.xml"));
+    if path.is_file() {
+        return load(&path)
+            .map(|def| (path.clone(), def))
+            .map_err(|e| CliError::Failed(format!("{}: {e}", path.display())));
+    }
+
+    let entries =
+        std::fs::read_dir(dir).map_err(|e| CliError::Failed(format!("{}: {e}", dir.display())))?;
+    let mut paths: Vec<_> = entries
+        .filter_map(|e| Some(e.ok()?.path()))
+        .filter(|p| p.extension().is_some_and(|ext| ext == "xml"))
+        .collect();
+    paths.sort();
+    paths
+        .into_iter()
+        .find_map(|path| {
+            let def = load(&path).ok()?;
+            def.name.eq_ignore_ascii_case(name).then_some((path, def))
+        })
+        .ok_or_else(|| {
+            CliError::Failed(format!("no definition named `{name}` in {}", dir.display()))
+        })
+}
+
+fn logic_type_name(typ: u32) -> String {
+    Type::try_from(typ).map_or_else(|_| typ.to_string(), |t| t.to_string())
+}
+
+fn def_show(opts: &Options) -> Result<bool, CliError> {
+    let name = opts.one("definition name")?;
+    let dir = match &opts.defs {
+        Some(dir) => dir.clone(),
+        None => Discovery::from_env().find().definitions().ok_or_else(|| {
+            CliError::Failed("couldn't find the game install, pass `--defs <dir>`".into())
+        })?,
+    };
+    let (path, def) = find_definition(&dir, name)?;
+    let file = path.file_name().unwrap_or_default().to_string_lossy();
+    let flags: Vec<_> = def.flags.iter_names().map(|(name, _)| name).collect();
+
+    if opts.json {
+        let logic_nodes: Vec<_> = def
+            .logic_nodes
+            .nodes
+            .iter()
+            .map(|n| {
+                json!({
+                    "label": n.label,
+                    "description": n.description,
+                    "mode": n.mode,
+                    "type": logic_type_name(n.typ),
+                    "position": { "x": n.position.x, "y": n.position.y, "z": n.position.z },
+                })
+            })
+            .collect();
+        let (min, max) = (&def.voxel_min, &def.voxel_max);
+        print_json(&json!({
+            "file": file,
+            "name": def.name,
+            "category": format!("{:?}", def.category),
+            "type": format!("{:?}", def.typ),
+            "mass": def.mass,
+            "value": def.value,
+            "flags": flags,
+            "tags": def.tags,
+            "description": def.tooltip_properties.description,
+            "short_description": def.tooltip_properties.short_description,
+            "mesh": def.mesh_data_name,
+            "voxel_min": { "x": min.x, "y": min.y, "z": min.z },
+            "voxel_max": { "x": max.x, "y": max.y, "z": max.z },
+            "logic_nodes": logic_nodes,
+        }));
+        return Ok(true);
+    }
+
+    println!("{} ({file})", def.name);
+    if let Some(d) = &def.tooltip_properties.short_description {
+        println!("{d}");
+    }
+    if let Some(d) = &def.tooltip_properties.description {
+        println!("{d}");
+    }
+    println!();
+    println!("Category: {:?}", def.category);
+    println!("Type:     {:?}", def.typ);
+    println!("Mass:     {}", def.mass);
+    println!("Value:    ${}", def.value);
+    println!(
+        "Voxels:   {},{},{} to {},{},{}",
+        def.voxel_min.x,
+        def.voxel_min.y,
+        def.voxel_min.z,
+        def.voxel_max.x,
+        def.voxel_max.y,
+        def.voxel_max.z
+    );
+    if let Some(mesh) = &def.mesh_data_name {
+        println!("Mesh:     {mesh}");
+    }
+    if !def.tags.is_empty() {
+        println!("Tags:     {}", def.tags.join(", "));
+    }
+    if !flags.is_empty() {
+        println!("Flags:    {}", flags.join(", "));
+    }
+    if !def.logic_nodes.nodes.is_empty() {
+        println!();
+        println!("Logic nodes ({}):", def.logic_nodes.nodes.len());
+        for n in &def.logic_nodes.nodes {
+            println!(
+                "  {} ({}, mode {}): {}",
+                n.label,
+                logic_type_name(n.typ),
+                n.mode,
+                n.description
+            );
+        }
+    }
+    Ok(true)
+}
+
+// ---- mesh info ----
+
+fn material_name(material: Material) -> &'static str {
+    match material {
+        Material::Normal => "normal",
+        Material::Glass => "glass",
+        Material::Emissive => "emissive",
+        Material::_Unknown => "unknown",
+    }
+}
+
+fn mesh_info(opts: &Options) -> Result<bool, CliError> {
+    let path = opts.one("file")?;
+    let failed = |e: &dyn std::fmt::Display| CliError::Failed(format!("{path}: {e}"));
+    let file = File::open(path).map_err(|e| failed(&e))?;
+    let mesh = Mesh::load_file(file).map_err(|e| failed(&e))?;
+
+    let bounds = mesh.vertices.iter().map(|v| &v.position).fold(
+        None,
+        |acc: Option<(Vector3F, Vector3F)>, p| {
+            let (mut min, mut max) = acc.unwrap_or((p.clone(), p.clone()));
+            (min.x, min.y, min.z) = (min.x.min(p.x), min.y.min(p.y), min.z.min(p.z));
+            (max.x, max.y, max.z) = (max.x.max(p.x), max.y.max(p.y), max.z.max(p.z));
+            Some((min, max))
+        },
+    );
+
+    if opts.json {
+        let submeshes: Vec<_> = mesh
+            .submeshes
+            .iter()
+            .map(|s| {
+                json!({
+                    "material": material_name(s.material),
+                    "triangles": s.tris.len(),
+                    "cull_min": vector_json(&s.cull_min),
+                    "cull_max": vector_json(&s.cull_max),
+                })
+            })
+            .collect();
+        print_json(&json!({
+            "vertices": mesh.vertices.len(),
+            "triangles": mesh.faces.len(),
+            "bounds": bounds.map(|(min, max)| {
+                json!({ "min": vector_json(&min), "max": vector_json(&max) })
+            }),
+            "submeshes": submeshes,
+        }));
+        return Ok(true);
+    }
+
+    println!("Vertices:  {}", mesh.vertices.len());
+    println!("Triangles: {}", mesh.faces.len());
+    if let Some((min, max)) = bounds {
+        println!(
+            "Bounds:    {},{},{} to {},{},{}",
+            min.x, min.y, min.z, max.x, max.y, max.z
+        );
+    }
+    println!();
+    println!("Submeshes ({}):", mesh.submeshes.len());
+    for s in &mesh.submeshes {
+        println!(
+            "  {}: {} triangles",
+            material_name(s.material),
+            s.tris.len()
+        );
+    }
+    Ok(true)
+}
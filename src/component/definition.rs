@@ -3,7 +3,9 @@ use thiserror::Error;
 
 use crate::{
     microcontroller::mc_serde::is_default,
-    util::serde_utils::{RecursiveStringMap, Vector3F, Vector3I},
+    util::serde_utils::{
+        locate_de_error, ElementContext, RecursiveStringMap, Vector3F, Vector3I, XmlElementError,
+    },
 };
 
 /// Note: Deserializing and re-serializing is not guaranteed to result in the exact same result, since the built-in definitions' formatting is wildly inconsistent
@@ -454,7 +456,10 @@ fn de_flags<'de, D>(de: D) -> Result<Flags, D::Error>
 where
     D: serde::Deserializer<'de>,
 {
-    u32::deserialize(de).map(|n| Flags::from_bits(n).unwrap())
+    let s = String::deserialize(de)?;
+    let err = |msg: &str| ElementContext::attribute("definition", None, "flags", &s, msg).into_de();
+    let n: u32 = s.parse().map_err(|_| err("not a number"))?;
+    Flags::from_bits(n).ok_or_else(|| err("unknown flag bits"))
 }
 
 #[derive(
@@ -840,6 +845,8 @@ pub struct RewardProperties {
 pub enum ComponentDefSerDeError {
     #[error(transparent)]
     SerDeError(#[from] quick_xml::DeError),
+    #[error(transparent)]
+    ElementError(#[from] XmlElementError),
 }
 
 impl ComponentDefinition {
@@ -865,7 +872,8 @@ impl ComponentDefinition {
         {
             string = xml.replacen(r#"<particle_bounds x="0.2" y="0.2" z="0.2"/>"#, "", 1);
         }
-        let mc: Self = quick_xml::de::from_str(&string)?;
+        let mc: Self = quick_xml::de::from_str(&string)
+            .map_err(|e| locate_de_error::<ComponentDefSerDeError>(e, &string))?;
         Ok(mc)
     }
 }
@@ -3,7 +3,7 @@ use std::num::ParseIntError;
 use crate::microcontroller::mc_serde::is_default;
 use serde::{Deserialize, Serialize};
 
-use crate::util::serde_utils::Vector3I;
+use crate::util::serde_utils::{ElementContext, Vector3I};
 
 fn default_definition() -> String {
     "01_block".into()
@@ -70,7 +70,10 @@ fn de_flip<'de, D>(de: D) -> Result<Flip, D::Error>
 where
     D: serde::Deserializer<'de>,
 {
-    u8::deserialize(de).map(|n| Flip::from_bits(n).unwrap())
+    let s = String::deserialize(de)?;
+    let err = |msg: &str| ElementContext::attribute("c", None, "t", &s, msg).into_de();
+    let n: u8 = s.parse().map_err(|_| err("not a number"))?;
+    Flip::from_bits(n).ok_or_else(|| err("unknown flip bits"))
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -155,10 +158,12 @@ fn de_rotation_matrix<'de, D>(de: D) -> Result<[i8; 9], D::Error>
 where
     D: serde::Deserializer<'de>,
 {
-    let str = String::deserialize(de)?;
-    let s = str
+    let text = String::deserialize(de)?;
+    let err = |msg: &str| ElementContext::attribute("o", None, "r", &text, msg).into_de();
+    let s = text
         .split(',')
-        .map(|s| s.parse().unwrap())
-        .collect::<Vec<_>>();
-    Ok(s.try_into().unwrap())
+        .map(str::parse)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| err("not a list of numbers"))?;
+    s.try_into().map_err(|_| err("expected 9 numbers"))
 }
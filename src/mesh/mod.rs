@@ -1,6 +1,6 @@
 use std::{
     fs::File,
-    io::{BufReader, Read},
+    io::{BufReader, ErrorKind, Read, Seek},
 };
 
 use byteorder::{LittleEndian, ReadBytesExt};
@@ -32,14 +32,29 @@ pub enum MeshParseError {
     WrongSubmeshPadding { actual: [u8; 2] },
     #[error("Invalid submesh material, expected 0, 1, or 2, but got {actual:?}")]
     InvalidSubmeshMaterial { actual: u16 },
+    #[error("Submesh triangles {start}..{end} are out of range, there are only {faces} faces")]
+    SubmeshOutOfRange { start: u32, end: u32, faces: usize },
+    #[error("Unexpected end of file at byte {offset}")]
+    UnexpectedEof { offset: u64 },
 }
 
 impl Mesh {
     pub fn load_file(file: File) -> Result<Self, MeshParseError> {
         let mut br = BufReader::new(file);
+        Self::read(&mut br).map_err(|e| match e {
+            MeshParseError::IOError(e) if e.kind() == ErrorKind::UnexpectedEof => {
+                match br.stream_position() {
+                    Ok(offset) => MeshParseError::UnexpectedEof { offset },
+                    Err(_) => MeshParseError::IOError(e),
+                }
+            },
+            e => e,
+        })
+    }
 
+    fn read(br: &mut BufReader<File>) -> Result<Self, MeshParseError> {
         const HEADER: [u8; 8] = [0x6D, 0x65, 0x73, 0x68, 0x07, 0x00, 0x01, 0x00];
-        let header = br.read_bytes();
+        let header = br.read_bytes()?;
         if header != HEADER {
             Err(MeshParseError::InvalidHeader { expected: HEADER, actual: header })?
         }
@@ -49,7 +64,7 @@ impl Mesh {
         let n_vertices = br.read_u16::<LittleEndian>()?;
 
         const BLOCK_HEADER: [u8; 4] = [0x13, 0x00, 0x00, 0x00];
-        let block_header = br.read_bytes();
+        let block_header = br.read_bytes()?;
         if block_header != BLOCK_HEADER {
             Err(MeshParseError::InvalidBlockHeader {
                 expected: BLOCK_HEADER,
@@ -82,8 +97,6 @@ impl Mesh {
 
         // Faces
 
-        println!("{}", vertices.len());
-
         let n_faces = br.read_u32::<LittleEndian>()?;
         if n_faces % 3 != 0 {
             Err(MeshParseError::InvalidFaceCount { actual: n_faces })?
@@ -114,15 +127,12 @@ impl Mesh {
                 }
                 let pos = pos / 3;
 
-                let n_tris = br.read_u32::<LittleEndian>()?;
-                if n_tris % 3 != 0 {
-                    println!("non 3 {n_tris}");
-                    // Err(MeshParseError::InvalidTriangleCount { actual: n_tris })?
-                }
-                let n_tris = n_tris / 3;
+                // some of the game's meshes have counts that aren't a multiple of 3, the extra
+                // indices are ignored
+                let n_tris = br.read_u32::<LittleEndian>()? / 3;
 
                 const PADDING: [u8; 2] = [0x00, 0x00];
-                let padding = br.read_bytes();
+                let padding = br.read_bytes()?;
                 if padding != PADDING {
                     Err(MeshParseError::WrongSubmeshPadding { actual: padding })?
                 }
@@ -156,9 +166,14 @@ impl Mesh {
 
                 br.seek_relative(14)?;
 
-                let tris = (0..n_tris)
-                    .map(|i| Ok(faces[(pos + i) as usize]))
-                    .collect::<Result<Vec<_>, MeshParseError>>()?;
+                let tris = faces
+                    .get(pos as usize..(pos + n_tris) as usize)
+                    .ok_or(MeshParseError::SubmeshOutOfRange {
+                        start: pos,
+                        end: pos + n_tris,
+                        faces: faces.len(),
+                    })?
+                    .to_vec();
 
                 Ok(Submesh { material, cull_min, cull_max, tris })
             })
@@ -206,13 +221,13 @@ pub enum Material {
 }
 
 trait ReadBytes {
-    fn read_bytes<const N: usize>(&mut self) -> [u8; N];
+    fn read_bytes<const N: usize>(&mut self) -> std::io::Result<[u8; N]>;
 }
 
 impl<R: Read> ReadBytes for R {
-    fn read_bytes<const N: usize>(&mut self) -> [u8; N] {
+    fn read_bytes<const N: usize>(&mut self) -> std::io::Result<[u8; N]> {
         let mut buf = [0; N];
-        self.read_exact(&mut buf).unwrap();
-        buf
+        self.read_exact(&mut buf)?;
+        Ok(buf)
     }
 }
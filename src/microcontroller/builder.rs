@@ -0,0 +1,448 @@
+//! Module containing a typed builder for wiring up [`Microcontroller`] logic from code.
+//!
+//! Every output is returned as an [`Out<T>`] handle where `T` is one of the [`CompileType`] markers,
+//! so mismatched wires are caught by the compiler instead of ingame.
+//!
+//! ```
+//! use sw_rs::microcontroller::{builder::Builder, Microcontroller};
+//! use sw_rs::microcontroller::types::{TNumber, TOnOff};
+//!
+//! let mut mc = Microcontroller::default();
+//! let mut b = Builder::new(&mut mc);
+//!
+//! let x = b.input::<TNumber>("X");
+//! let y = b.input::<TNumber>("Y");
+//! let sum = b.add(x, y);
+//! let big = b.greater_than(sum, x);
+//! b.output::<TOnOff>("Big", big);
+//! ```
+
+use std::marker::PhantomData;
+
+use super::{
+    components::{
+        ComponentConnection, ComponentType, CompositeChannel, TextValue, TypedInputConnection,
+        TypedOutputConnection,
+    },
+    mc_serde::microcontroller::IONodeType,
+    types::{CompileType, TComposite, TNumber, TOnOff},
+    Microcontroller,
+};
+
+/// Handle to a component output of type `T`.
+///
+/// Can be passed anywhere an input of the same [`CompileType`] is expected.
+pub struct Out<T: CompileType> {
+    component_id: u32,
+    node_index: u8,
+    _phantom: PhantomData<T>,
+}
+
+// manual impls so `T` doesn't need to be Copy
+impl<T: CompileType> Clone for Out<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: CompileType> Copy for Out<T> {}
+
+impl<T: CompileType> core::fmt::Debug for Out<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Out")
+            .field("type", &T::get_type())
+            .field("component_id", &self.component_id)
+            .field("node_index", &self.node_index)
+            .finish()
+    }
+}
+
+impl<T: CompileType> Out<T> {
+    fn new(component_id: u32, node_index: u8) -> Self {
+        Self { component_id, node_index, _phantom: PhantomData }
+    }
+
+    /// Gets the id of the component this output belongs to.
+    #[allow(clippy::must_use_candidate)]
+    pub fn component_id(&self) -> u32 {
+        self.component_id
+    }
+
+    /// Gets the [`ComponentConnection`] pointing at this output.
+    #[allow(clippy::must_use_candidate)]
+    pub fn connection(&self) -> ComponentConnection {
+        ComponentConnection {
+            component_id: self.component_id,
+            node_index: self.node_index,
+        }
+    }
+}
+
+impl<T: CompileType, const S: bool> From<Out<T>> for TypedInputConnection<T, S> {
+    fn from(out: Out<T>) -> Self {
+        Self::new(out.connection())
+    }
+}
+
+fn opt<T: CompileType, const S: bool>(out: Option<Out<T>>) -> TypedInputConnection<T, S> {
+    out.map(|o| o.connection()).into()
+}
+
+#[track_caller]
+fn fixed_channel(channel: u8) -> CompositeChannel {
+    CompositeChannel::from_number(channel)
+        .unwrap_or_else(|| panic!("composite channel {channel} isn't in 1..=32"))
+}
+
+/// Typed builder on top of [`Microcontroller::add_component`] and [`Microcontroller::add_io`].
+///
+/// See the [module docs][self] for an example.
+pub struct Builder<'a> {
+    mc: &'a mut Microcontroller,
+}
+
+impl<'a> Builder<'a> {
+    /// Creates a [`Builder`] that adds to the given [`Microcontroller`].
+    pub fn new(mc: &'a mut Microcontroller) -> Self {
+        Self { mc }
+    }
+
+    /// Access the underlying [`Microcontroller`].
+    pub fn mc(&mut self) -> &mut Microcontroller {
+        self.mc
+    }
+
+    /// Adds an input [`IONode`][super::IONode] with the given label.
+    pub fn input<T: CompileType>(&mut self, label: &str) -> Out<T> {
+        let id = self
+            .mc
+            .add_io(Some(label.into()), None, T::get_type(), IONodeType::Input)
+            .logic
+            .id();
+        Out::new(id, 0)
+    }
+
+    /// Adds an output [`IONode`][super::IONode] with the given label, driven by `src`.
+    ///
+    /// Returns the id of the node's logic component.
+    pub fn output<T: CompileType>(&mut self, label: &str, src: Out<T>) -> u32 {
+        let ion = self
+            .mc
+            .add_io(Some(label.into()), None, T::get_type(), IONodeType::Output);
+        for input in ion.logic.component.inputs_mut() {
+            *input = Some(src.connection());
+        }
+        ion.logic.id()
+    }
+
+    /// Adds an arbitrary [`ComponentType`] and returns its id.
+    ///
+    /// Use [`Builder::out`] to get typed handles to its outputs.
+    pub fn component(&mut self, component: ComponentType) -> u32 {
+        self.mc.add_component(component).id()
+    }
+
+    /// Gets a typed handle to output `index` of the given component.
+    ///
+    /// Returns [`None`] if the component or output doesn't exist, or isn't of type `T`.
+    #[allow(clippy::must_use_candidate)]
+    pub fn out<T: CompileType>(&self, component_id: u32, index: u8) -> Option<Out<T>> {
+        let outputs = self.mc.get_component(component_id)?.io_def().outputs;
+        (outputs.get(index as usize) == Some(&T::get_type())).then(|| Out::new(component_id, index))
+    }
+
+    fn push<T: CompileType>(&mut self, component: ComponentType) -> Out<T> {
+        Out::new(self.component(component), 0)
+    }
+
+    /// Adds a constant number.
+    pub fn constant(&mut self, value: impl Into<f64>) -> Out<TNumber> {
+        self.push(ComponentType::ConstantNum {
+            out: TypedOutputConnection::default(),
+            n: TextValue::from_value(value),
+        })
+    }
+
+    /// Adds a constant on signal.
+    pub fn constant_on(&mut self) -> Out<TOnOff> {
+        self.push(ComponentType::ConstantOn { out: TypedOutputConnection::default() })
+    }
+
+    /// Adds a NOT gate.
+    pub fn not(&mut self, input: Out<TOnOff>) -> Out<TOnOff> {
+        self.push(ComponentType::NOT {
+            input: input.into(),
+            out: TypedOutputConnection::default(),
+        })
+    }
+
+    /// Adds an AND gate.
+    pub fn and(&mut self, a: Out<TOnOff>, b: Out<TOnOff>) -> Out<TOnOff> {
+        self.push(ComponentType::AND {
+            input_a: a.into(),
+            input_b: b.into(),
+            out: TypedOutputConnection::default(),
+        })
+    }
+
+    /// Adds an OR gate.
+    pub fn or(&mut self, a: Out<TOnOff>, b: Out<TOnOff>) -> Out<TOnOff> {
+        self.push(ComponentType::OR {
+            input_a: a.into(),
+            input_b: b.into(),
+            out: TypedOutputConnection::default(),
+        })
+    }
+
+    /// Adds an XOR gate.
+    pub fn xor(&mut self, a: Out<TOnOff>, b: Out<TOnOff>) -> Out<TOnOff> {
+        self.push(ComponentType::XOR {
+            input_a: a.into(),
+            input_b: b.into(),
+            out: TypedOutputConnection::default(),
+        })
+    }
+
+    /// Adds a NAND gate.
+    pub fn nand(&mut self, a: Out<TOnOff>, b: Out<TOnOff>) -> Out<TOnOff> {
+        self.push(ComponentType::NAND {
+            input_a: a.into(),
+            input_b: b.into(),
+            out: TypedOutputConnection::default(),
+        })
+    }
+
+    /// Adds a NOR gate.
+    pub fn nor(&mut self, a: Out<TOnOff>, b: Out<TOnOff>) -> Out<TOnOff> {
+        self.push(ComponentType::NOR {
+            input_a: a.into(),
+            input_b: b.into(),
+            out: TypedOutputConnection::default(),
+        })
+    }
+
+    /// Adds an Add component (`a + b`).
+    pub fn add(&mut self, a: Out<TNumber>, b: Out<TNumber>) -> Out<TNumber> {
+        self.push(ComponentType::Add {
+            input_a: a.into(),
+            input_b: b.into(),
+            out: TypedOutputConnection::default(),
+        })
+    }
+
+    /// Adds a Subtract component (`a - b`).
+    pub fn sub(&mut self, a: Out<TNumber>, b: Out<TNumber>) -> Out<TNumber> {
+        self.push(ComponentType::Subtract {
+            input_a: a.into(),
+            input_b: b.into(),
+            out: TypedOutputConnection::default(),
+        })
+    }
+
+    /// Adds a Multiply component (`a * b`).
+    pub fn mul(&mut self, a: Out<TNumber>, b: Out<TNumber>) -> Out<TNumber> {
+        self.push(ComponentType::Multiply {
+            input_a: a.into(),
+            input_b: b.into(),
+            out: TypedOutputConnection::default(),
+        })
+    }
+
+    /// Adds a Divide component (`a / b`).
+    ///
+    /// Returns the result and the divide-by-zero flag.
+    pub fn div(&mut self, a: Out<TNumber>, b: Out<TNumber>) -> (Out<TNumber>, Out<TOnOff>) {
+        let id = self.component(ComponentType::Divide {
+            input_a: a.into(),
+            input_b: b.into(),
+            out: TypedOutputConnection::default(),
+            div_by_zero: TypedOutputConnection::default(),
+        });
+        (Out::new(id, 0), Out::new(id, 1))
+    }
+
+    /// Adds a Modulo component (`a % b`).
+    pub fn modulo(&mut self, a: Out<TNumber>, b: Out<TNumber>) -> Out<TNumber> {
+        self.push(ComponentType::Modulo {
+            input_a: a.into(),
+            input_b: b.into(),
+            out: TypedOutputConnection::default(),
+        })
+    }
+
+    /// Adds an Abs component.
+    pub fn abs(&mut self, input: Out<TNumber>) -> Out<TNumber> {
+        self.push(ComponentType::Abs {
+            input: input.into(),
+            out: TypedOutputConnection::default(),
+        })
+    }
+
+    /// Adds a Clamp component.
+    pub fn clamp(
+        &mut self,
+        input: Out<TNumber>,
+        min: impl Into<f64>,
+        max: impl Into<f64>,
+    ) -> Out<TNumber> {
+        self.push(ComponentType::Clamp {
+            input: input.into(),
+            out: TypedOutputConnection::default(),
+            min: TextValue::from_value(min),
+            max: TextValue::from_value(max),
+        })
+    }
+
+    /// Adds a Threshold component (on while `min <= input <= max`).
+    pub fn threshold(
+        &mut self,
+        input: Out<TNumber>,
+        min: impl Into<f64>,
+        max: impl Into<f64>,
+    ) -> Out<TOnOff> {
+        self.push(ComponentType::Threshold {
+            input: input.into(),
+            out: TypedOutputConnection::default(),
+            min: TextValue::from_value(min),
+            max: TextValue::from_value(max),
+        })
+    }
+
+    /// Adds a Greater Than component (`a > b`).
+    pub fn greater_than(&mut self, a: Out<TNumber>, b: Out<TNumber>) -> Out<TOnOff> {
+        self.push(ComponentType::GreaterThan {
+            input_a: a.into(),
+            input_b: b.into(),
+            out: TypedOutputConnection::default(),
+        })
+    }
+
+    /// Adds a Less Than component (`a < b`).
+    pub fn less_than(&mut self, a: Out<TNumber>, b: Out<TNumber>) -> Out<TOnOff> {
+        self.push(ComponentType::LessThan {
+            input_a: a.into(),
+            input_b: b.into(),
+            out: TypedOutputConnection::default(),
+        })
+    }
+
+    /// Adds an Equal component (`|a - b| <= epsilon`).
+    pub fn equal(
+        &mut self,
+        a: Out<TNumber>,
+        b: Out<TNumber>,
+        epsilon: impl Into<f64>,
+    ) -> Out<TOnOff> {
+        self.push(ComponentType::Equal {
+            input_a: a.into(),
+            input_b: b.into(),
+            out: TypedOutputConnection::default(),
+            epsilon: TextValue::from_value(epsilon),
+        })
+    }
+
+    /// Adds a Numerical Switchbox (`on` while `switch` is on, otherwise `off`).
+    pub fn switchbox(
+        &mut self,
+        on: Out<TNumber>,
+        off: Out<TNumber>,
+        switch: Out<TOnOff>,
+    ) -> Out<TNumber> {
+        self.push(ComponentType::NumericalSwitchbox {
+            on: on.into(),
+            off: off.into(),
+            switch: switch.into(),
+            out: TypedOutputConnection::default(),
+        })
+    }
+
+    /// Adds a Composite Switchbox (`on` while `switch` is on, otherwise `off`).
+    pub fn composite_switchbox(
+        &mut self,
+        on: Out<TComposite>,
+        off: Out<TComposite>,
+        switch: Out<TOnOff>,
+    ) -> Out<TComposite> {
+        self.push(ComponentType::CompositeSwitchbox {
+            on: on.into(),
+            off: off.into(),
+            switch: switch.into(),
+            out: TypedOutputConnection::default(),
+        })
+    }
+
+    /// Adds a Memory Register.
+    pub fn memory_register(
+        &mut self,
+        set: Out<TOnOff>,
+        reset: Option<Out<TOnOff>>,
+        number: Out<TNumber>,
+        reset_value: impl Into<f64>,
+    ) -> Out<TNumber> {
+        self.push(ComponentType::MemoryRegister {
+            set: set.into(),
+            reset: opt(reset),
+            number: number.into(),
+            out: TypedOutputConnection::default(),
+            reset_value: TextValue::from_value(reset_value),
+            __memory: None,
+        })
+    }
+
+    /// Adds a Delta component.
+    pub fn delta(&mut self, input: Out<TNumber>) -> Out<TNumber> {
+        self.push(ComponentType::Delta {
+            input: input.into(),
+            out: TypedOutputConnection::default(),
+            __vp: None,
+            __ip: None,
+        })
+    }
+
+    /// Adds a single variable function node with the given expression (in terms of `x`).
+    ///
+    /// The expression isn't checked here, see [`Microcontroller::validate`].
+    pub fn func(&mut self, input: Out<TNumber>, expr: &str) -> Out<TNumber> {
+        self.push(ComponentType::Func1n {
+            input: input.into(),
+            out: TypedOutputConnection::default(),
+            expr: expr.into(),
+        })
+    }
+
+    /// Adds a Composite Read (Number) reading the given channel (1-32).
+    ///
+    /// # Panics
+    /// Panics if `channel` isn't in `1..=32`.
+    #[track_caller]
+    pub fn composite_read_number(
+        &mut self,
+        composite: Out<TComposite>,
+        channel: u8,
+    ) -> Out<TNumber> {
+        self.push(ComponentType::CompositeReadNum {
+            composite: composite.into(),
+            variable_channel: TypedInputConnection::empty(),
+            out: TypedOutputConnection::default(),
+            channel: fixed_channel(channel),
+        })
+    }
+
+    /// Adds a Composite Read (On/Off) reading the given channel (1-32).
+    ///
+    /// # Panics
+    /// Panics if `channel` isn't in `1..=32`.
+    #[track_caller]
+    pub fn composite_read_on_off(
+        &mut self,
+        composite: Out<TComposite>,
+        channel: u8,
+    ) -> Out<TOnOff> {
+        self.push(ComponentType::CompositeReadOnOff {
+            composite: composite.into(),
+            variable_channel: TypedInputConnection::empty(),
+            out: TypedOutputConnection::default(),
+            channel: fixed_channel(channel),
+        })
+    }
+}
@@ -0,0 +1,188 @@
+//! Module containing id compaction and canonical ordering for [`Microcontroller`]s.
+
+use std::{cmp::Ordering, collections::HashMap};
+
+use crate::util::{serde_utils::PositionXY, AnyComponentMut, AnyComponentRef};
+
+use super::{group::Group, mc_serde::microcontroller::IONodeType, Microcontroller};
+
+/// Sort key for picking the next component, see [`Microcontroller::canonicalize`].
+struct Key {
+    pos: PositionXY,
+    name: &'static str,
+    settings: Vec<(&'static str, String)>,
+    /// New ids of the components connected to each input, [`u32::MAX`] if not assigned yet.
+    inputs: Vec<(u32, u8)>,
+    /// Index of the [`IONode`][super::IONode] for bridge components.
+    io_index: Option<usize>,
+}
+
+impl Key {
+    fn cmp(&self, o: &Key) -> Ordering {
+        self.pos
+            .x
+            .total_cmp(&o.pos.x)
+            .then(self.pos.y.total_cmp(&o.pos.y))
+            .then(self.name.cmp(o.name))
+            .then_with(|| self.settings.cmp(&o.settings))
+            .then_with(|| self.inputs.cmp(&o.inputs))
+            .then(self.io_index.cmp(&o.io_index))
+    }
+}
+
+impl Microcontroller {
+    /// Renumbers all node and component ids densely in a deterministic order and resets the id counters.
+    ///
+    /// IO nodes are ordered by their position on the microcontroller (inputs first if they overlap),
+    /// components are ordered topologically (inputs before what they feed) with ties broken by
+    /// position, then type and settings.
+    /// Two logically identical microcontrollers will serialize to the same XML after this.
+    ///
+    /// Components in nested [`groups`][`Self::groups`] are numbered after the top level ones, in the
+    /// order they're stored.
+    ///
+    /// Connections to components that don't exist are removed.
+    pub fn canonicalize(&mut self) {
+        self.io.sort_by(|a, b| {
+            let (a, b) = (&a.design, &b.design);
+            a.position
+                .x
+                .total_cmp(&b.position.x)
+                .then(a.position.y.total_cmp(&b.position.y))
+                .then((a.mode == IONodeType::Output).cmp(&(b.mode == IONodeType::Output)))
+                .then_with(|| a.label.cmp(&b.label))
+        });
+        for (i, ion) in self.io.iter_mut().enumerate() {
+            #[allow(clippy::cast_possible_truncation)]
+            let node_id = i as u32 + 1;
+            ion.design.node_id = node_id;
+        }
+        if self.id_counter_node.is_some() || !self.io.is_empty() {
+            #[allow(clippy::cast_possible_truncation)]
+            let n = self.io.len() as u32;
+            self.id_counter_node = Some(n);
+        }
+
+        let order = self.canonical_order();
+        #[allow(clippy::cast_possible_truncation)]
+        let new_ids: HashMap<u32, u32> = order
+            .iter()
+            .enumerate()
+            .map(|(i, &id)| (id, i as u32 + 1))
+            .collect();
+
+        // nested groups get their own map, so their ids can't clash with the old top level ones
+        let nested: Vec<u32> = self.groups.iter().flat_map(Group::ids).collect();
+        #[allow(clippy::cast_possible_truncation)]
+        let new_nested_ids: HashMap<u32, u32> = nested
+            .iter()
+            .enumerate()
+            .map(|(i, &id)| (id, (order.len() + i) as u32 + 1))
+            .collect();
+
+        let remap = |c: AnyComponentMut, own: &HashMap<u32, u32>, other: &HashMap<u32, u32>| {
+            for input in c.into_inputs_mut() {
+                *input = input.take().and_then(|mut conn| {
+                    let id = conn.component_id;
+                    conn.component_id = *own.get(&id).or_else(|| other.get(&id))?;
+                    Some(conn)
+                });
+            }
+        };
+        for c in self.components_mut() {
+            remap(c, &new_ids, &new_nested_ids);
+        }
+        for c in self.groups.iter_mut().flat_map(Group::all_components_mut) {
+            remap(c, &new_nested_ids, &new_ids);
+        }
+
+        for c in &mut self.components {
+            c.id = new_ids[&c.id];
+        }
+        for ion in &mut self.io {
+            ion.logic.id = new_ids[&ion.logic.id];
+        }
+        for c in self.groups.iter_mut().flat_map(Group::all_components_mut) {
+            match c {
+                AnyComponentMut::Component(c) => c.id = new_nested_ids[&c.id],
+                AnyComponentMut::BridgeComponent(bc) => bc.id = new_nested_ids[&bc.id],
+            }
+        }
+
+        self.components.sort_by_key(|c| c.id);
+        self.components_bridge_order = self.io.iter().map(|ion| ion.logic.id).collect();
+        self.components_bridge_order.sort_unstable();
+        #[allow(clippy::cast_possible_truncation)]
+        let n = (order.len() + nested.len()) as u32;
+        self.id_counter = n;
+    }
+
+    /// Orders the current component ids topologically, picking the smallest [`Key`] whenever there's a choice.
+    fn canonical_order(&self) -> Vec<u32> {
+        let g = self.graph();
+        let io_index: HashMap<u32, usize> = self
+            .io
+            .iter()
+            .enumerate()
+            .map(|(i, ion)| (ion.logic.id, i))
+            .collect();
+
+        // nested groups are numbered separately, so they don't hold anything up
+        let mut remaining: Vec<u32> = g.ids().into_iter().filter(|&id| !g.is_nested(id)).collect();
+        let mut new_ids: HashMap<u32, u32> = HashMap::new();
+        let mut order = vec![];
+
+        let key = |c: &AnyComponentRef, new_ids: &HashMap<u32, u32>| Key {
+            pos: c.pos().clone(),
+            name: match c {
+                AnyComponentRef::Component(c) => c.component.name(),
+                AnyComponentRef::BridgeComponent(bc) => bc.component.name(),
+            },
+            settings: match c {
+                AnyComponentRef::Component(c) => c.component.settings(),
+                AnyComponentRef::BridgeComponent(bc) => bc.component.settings(),
+            },
+            inputs: c
+                .inputs()
+                .iter()
+                .map(|conn| {
+                    conn.as_ref().map_or((0, 0), |conn| {
+                        (
+                            new_ids.get(&conn.component_id).copied().unwrap_or(u32::MAX),
+                            conn.node_index,
+                        )
+                    })
+                })
+                .collect(),
+            io_index: io_index.get(&c.id()).copied(),
+        };
+
+        while !remaining.is_empty() {
+            let ready: Vec<u32> = remaining
+                .iter()
+                .copied()
+                .filter(|&id| {
+                    g.predecessors(id)
+                        .iter()
+                        .all(|&p| new_ids.contains_key(&p) || g.is_nested(p))
+                })
+                .collect();
+            // in a loop nothing is ready, so just take the smallest of what's left
+            let candidates = if ready.is_empty() { &remaining } else { &ready };
+
+            let next = candidates
+                .iter()
+                .filter_map(|&id| Some((id, key(g.get(id)?, &new_ids))))
+                .min_by(|(_, a), (_, b)| a.cmp(b))
+                .map(|(id, _)| id);
+            let Some(next) = next else { break };
+
+            remaining.retain(|&id| id != next);
+            #[allow(clippy::cast_possible_truncation)]
+            new_ids.insert(next, order.len() as u32 + 1);
+            order.push(next);
+        }
+
+        order
+    }
+}
@@ -5,14 +5,16 @@ use std::num::ParseFloatError;
 use std::str::FromStr;
 
 use super::types::CompileType;
-use crate::util::fakemap_hack::FakeMapExt;
 use crate::util::serde_utils::PositionXY;
-use crate::util::serde_utils::RecursiveStringMap;
-use fakemap::FakeMap;
 use paste::paste;
 use serde::{Deserialize, Serialize};
+use serde_repr::Serialize_repr;
+use thiserror::Error;
 
-use super::{mc_serde::is_default, types::Type};
+use super::{
+    mc_serde::{is_default, microcontroller::IONodeType},
+    types::Type,
+};
 
 /// List of IO types for a component.
 pub struct ComponentIODef {
@@ -20,6 +22,10 @@ pub struct ComponentIODef {
     pub inputs: Vec<Type>,
     /// List of output Types.
     pub outputs: Vec<Type>,
+    /// List of input field names.
+    pub input_names: Vec<&'static str>,
+    /// List of output field names.
+    pub output_names: Vec<&'static str>,
 }
 
 fn skip_connection<T: CompileType, const S: bool>(v: &Option<ConnectionV>) -> bool {
@@ -51,19 +57,11 @@ pub struct ComponentConnection {
 pub(crate) fn de_from_str<'de, D, T: FromStr>(de: D) -> Result<T, D::Error>
 where
     D: serde::Deserializer<'de>,
-    <T as FromStr>::Err: std::fmt::Debug,
+    <T as FromStr>::Err: std::fmt::Display,
 {
     let s = String::deserialize(de)?;
-    Ok(s.parse().unwrap())
-}
-
-pub(crate) fn de_from_str_opt<'de, D, T: FromStr>(de: D) -> Result<Option<T>, D::Error>
-where
-    D: serde::Deserializer<'de>,
-    <T as FromStr>::Err: std::fmt::Debug,
-{
-    let s = Option::<String>::deserialize(de)?;
-    Ok(s.map(|s| s.parse().unwrap()))
+    s.parse()
+        .map_err(|e| serde::de::Error::custom(format!("can't parse {s:?}: {e}")))
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
@@ -237,217 +235,65 @@ impl<T: CompileType> core::fmt::Debug for TypedOutputConnection<T> {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct _ComponentTypeDe {
-    #[serde(flatten)]
-    inner: FakeMap<String, RecursiveStringMap>,
+/// XML details of an input or output slot that aren't part of the logic: whether an unconnected
+/// slot is written at all, and the values the game saved on it.
+#[cfg(feature = "json")]
+pub(crate) trait Slot {
+    /// Whether the slot is left out of the XML.
+    fn hidden(&self) -> bool;
+    /// Sets whether an unconnected slot with nothing saved is left out of the XML.
+    fn set_hidden(&mut self, hidden: bool);
+    /// Gets the saved `v` attribute and `v` element.
+    fn saved(&self) -> (Option<&String>, Option<&ConnectionV>);
+    /// Sets the saved `v` attribute and `v` element.
+    fn set_saved(&mut self, v_attr: Option<String>, v: Option<ConnectionV>);
 }
 
-impl From<_ComponentTypeDe> for Component {
-    fn from(de: _ComponentTypeDe) -> Self {
-        #[derive(Serialize, Deserialize, Debug)]
-        struct W {
-            object: _ComponentTypeDe,
-        }
-
-        let db = format!("{de:?}");
-
-        let mut se = quick_xml::se::Serializer::new(String::new());
-        se.escape(quick_xml::se::QuoteLevel::Partial);
-        let ser = W { object: de }.serialize(se).unwrap();
-        let ser = ser.trim_start_matches("<W>").trim_end_matches("</W>");
-
-        let de: Component = quick_xml::de::from_str(ser)
-            .expect(&format!("Deserializing component:\n{db}\n{ser}\n"));
-
-        de
+#[cfg(feature = "json")]
+impl<T: CompileType + Default + Eq, const S: bool> Slot for TypedInputConnection<T, S> {
+    fn hidden(&self) -> bool {
+        skip_typedinputconnection(self)
     }
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-struct _BridgeComponentTypeDe {
-    #[serde(flatten)]
-    inner: FakeMap<String, RecursiveStringMap>,
-}
-
-impl From<_BridgeComponentTypeDe> for BridgeComponent {
-    fn from(de: _BridgeComponentTypeDe) -> Self {
-        #[derive(Serialize, Deserialize, Debug)]
-        struct W {
-            object: _BridgeComponentTypeDe,
-        }
-
-        let db = format!("{de:?}");
 
-        let mut se = quick_xml::se::Serializer::new(String::new());
-        se.escape(quick_xml::se::QuoteLevel::Partial);
-        let ser = W { object: de }.serialize(se).unwrap();
-        let ser = ser.trim_start_matches("<W>").trim_end_matches("</W>");
-
-        let de: BridgeComponent = quick_xml::de::from_str(ser)
-            .expect(&format!("Deserializing bridge component:\n{db}\n{ser}\n"));
-
-        de
+    fn set_hidden(&mut self, hidden: bool) {
+        self.force_visible = !hidden;
     }
-}
 
-#[allow(dead_code)]
-pub(crate) fn component_deserialize<'de, D>(de: D) -> Result<Component, D::Error>
-where
-    D: serde::Deserializer<'de>,
-{
-    let mut cde = _ComponentTypeDe::deserialize(de)?;
-
-    if cde.inner.get("@type").is_none() {
-        cde.inner
-            .insert("@type".into(), RecursiveStringMap::String("0".into()));
+    fn saved(&self) -> (Option<&String>, Option<&ConnectionV>) {
+        (self.v_attr.as_ref(), self.v.as_ref())
     }
-    if let Some(RecursiveStringMap::String(s)) = cde.inner.get("@type") {
-        // see note on NumericalJunction
-        if s == "21" {
-            // FakeMap has no get_mut
-            if let Some(RecursiveStringMap::Map(mut o)) = cde.inner.remove("object") {
-                o.remove("out1");
-                o.insert("out2".into(), RecursiveStringMap::default());
-                cde.inner
-                    .insert("object".into(), RecursiveStringMap::Map(o));
-            }
-        } else if s == "40" || s == "41" {
-            if let Some(RecursiveStringMap::Map(mut o)) = cde.inner.remove("object") {
-                for (k, _) in o.iter_mut() {
-                    if *k == "inc" {
-                        *k = "in1".into();
-                    } else if *k == "inoff" {
-                        *k = "in34".into();
-                    } else if k.starts_with("in") {
-                        if let Ok(n) = k.trim_start_matches("in").parse::<u8>() {
-                            *k = format!("in{}", n + 1);
-                        }
-                    }
-                }
-                cde.inner
-                    .insert("object".into(), RecursiveStringMap::Map(o));
-            }
-        }
-    }
-
-    Ok(cde.into())
-}
-
-pub(crate) fn components_deserialize<'de, D>(de: D) -> Result<Vec<Component>, D::Error>
-where
-    D: serde::Deserializer<'de>,
-{
-    let de = Vec::<_ComponentTypeDe>::deserialize(de)?;
-    // println!("{de:?}");
-    let cs = de
-        .into_iter()
-        .map(|mut cde| {
-            if cde.inner.get("@type").is_none() {
-                cde.inner
-                    .insert("@type".into(), RecursiveStringMap::String("0".into()));
-            }
-            if let Some(RecursiveStringMap::String(s)) = cde.inner.get("@type") {
-                // see note on NumericalJunction
-                if s == "21" {
-                    // FakeMap has no get_mut
-                    if let Some(RecursiveStringMap::Map(mut o)) = cde.inner.remove("object") {
-                        o.remove("out1");
-                        o.insert("out2".into(), RecursiveStringMap::default());
-                        cde.inner
-                            .insert("object".into(), RecursiveStringMap::Map(o));
-                    }
-                } else if s == "40" || s == "41" {
-                    if let Some(RecursiveStringMap::Map(mut o)) = cde.inner.remove("object") {
-                        for (k, _) in o.iter_mut() {
-                            if *k == "inc" {
-                                *k = "in1".into();
-                            } else if *k == "inoff" {
-                                *k = "in34".into();
-                            } else if k.starts_with("in") {
-                                if let Ok(n) = k.trim_start_matches("in").parse::<u8>() {
-                                    *k = format!("in{}", n + 1);
-                                }
-                            }
-                        }
-                        cde.inner
-                            .insert("object".into(), RecursiveStringMap::Map(o));
-                    }
-                }
-            }
 
-            cde.into()
-        })
-        .collect();
-
-    Ok(cs)
+    fn set_saved(&mut self, v_attr: Option<String>, v: Option<ConnectionV>) {
+        self.v_attr = v_attr;
+        self.v = v;
+    }
 }
 
-#[allow(dead_code)]
-pub(crate) fn component_serialize<S>(
-    component: &Component,
-    serializer: S,
-) -> Result<S::Ok, S::Error>
-where
-    S: serde::Serializer,
-{
-    #[derive(Serialize, Debug)]
-    struct W<'a> {
-        object: &'a Component,
+#[cfg(feature = "json")]
+impl<T: CompileType + Default + Eq> Slot for TypedOutputConnection<T> {
+    fn hidden(&self) -> bool {
+        skip_typedoutputconnection(self)
     }
 
-    let mut se = quick_xml::se::Serializer::new(String::new());
-    se.escape(quick_xml::se::QuoteLevel::Partial);
-    let ser = W { object: component }.serialize(se).unwrap();
-    let ser = ser.trim_start_matches("<W>").trim_end_matches("</W>");
-
-    let mut cde: _ComponentTypeDe = quick_xml::de::from_str(ser).unwrap();
-    if let Some(RecursiveStringMap::String(s)) = cde.inner.get("@type").cloned() {
-        if s == "0" {
-            cde.inner.remove("@type");
-        }
+    fn set_hidden(&mut self, hidden: bool) {
+        self.force_visible = !hidden;
     }
 
-    cde.serialize(serializer)
-}
-
-pub(crate) fn components_serialize<S>(components: &[Component], ser: S) -> Result<S::Ok, S::Error>
-where
-    S: serde::Serializer,
-{
-    let cdes = components
-        .iter()
-        .map(|c| {
-            #[derive(Serialize, Debug)]
-            struct W<'a> {
-                object: &'a Component,
-            }
-
-            let mut se = quick_xml::se::Serializer::new(String::new());
-            se.escape(quick_xml::se::QuoteLevel::Partial);
-            let ser = W { object: c }.serialize(se).unwrap();
-            let ser = ser.trim_start_matches("<W>").trim_end_matches("</W>");
-
-            let mut cde: _ComponentTypeDe = quick_xml::de::from_str(ser).unwrap();
-            if let Some(RecursiveStringMap::String(s)) = cde.inner.get("@type").cloned() {
-                if s == "0" {
-                    cde.inner.remove("@type");
-                }
-            }
-
-            cde
-        })
-        .collect::<Vec<_>>();
+    fn saved(&self) -> (Option<&String>, Option<&ConnectionV>) {
+        (self.v_attr.as_ref(), self.v.as_ref())
+    }
 
-    ser.collect_seq(cdes.iter())
+    fn set_saved(&mut self, v_attr: Option<String>, v: Option<ConnectionV>) {
+        self.v_attr = v_attr;
+        self.v = v;
+    }
 }
 
 macro_rules! components {
     (   $type:ident,
         $(
-            $id:literal = $x:ident [$($in_id:ident($idx_i:literal): $in:expr),*] [$($out_id:ident($idx_o:literal): $out:expr),*] {$($b:tt)*}
-        ),*,
-        {$($ser_to_map:tt)*}
+            $id:literal = $x:ident [$($in_id:ident($idx_i:literal): $in:expr),*] [$($out_id:ident($idx_o:literal): $out:expr),*] {$($(#[$m:meta])* $f:ident: $t:ty),* $(,)?}
+        ),* $(,)?
     ) => {
         paste! {
             #[allow(missing_docs)]
@@ -465,7 +311,10 @@ macro_rules! components {
                             #[serde(rename = "" [<out $idx_o>] "", default, skip_serializing_if = "skip_typedoutputconnection")]
                             $out_id: TypedOutputConnection<super::types::[<T $out>]>,
                         )*
-                        $($b)*
+                        $(
+                            $(#[$m])*
+                            $f: $t,
+                        )*
                     },
                 )*
             }
@@ -479,11 +328,36 @@ macro_rules! components {
                             Self::$x { .. } => ComponentIODef {
                                 inputs: vec![$(Type::$in,)*],
                                 outputs: vec![$(Type::$out,)*],
+                                input_names: vec![$(stringify!($in_id),)*],
+                                output_names: vec![$(stringify!($out_id),)*],
                             },
                         )*
                     }
                 }
 
+                /// Gets the name of this [`ComponentType`]'s variant.
+                #[must_use]
+                pub fn name(&self) -> &'static str {
+                    match self {
+                        $(
+                            Self::$x { .. } => stringify!($x),
+                        )*
+                    }
+                }
+
+                /// Returns the name and [`Debug`] representation of every non-IO field of this [`ComponentType`].
+                #[must_use]
+                pub fn settings(&self) -> Vec<(&'static str, String)> {
+                    match self {
+                        $(
+                            #[allow(unused_variables)]
+                            Self::$x { $( $f, )* .. } => vec![
+                                $( (stringify!($f), format!("{:?}", $f)), )*
+                            ],
+                        )*
+                    }
+                }
+
                 /// Returns an immutable list of the input connections for this [`ComponentType`].
                 #[must_use]
                 pub fn inputs(&self) -> Vec<&Option<ComponentConnection>> {
@@ -510,21 +384,92 @@ macro_rules! components {
                     }
                 }
 
-                #[allow(dead_code)]
-                #[must_use]
-                fn ser_to_map(&self) -> FakeMap<String, RecursiveStringMap> {
-                    let mut se = quick_xml::se::Serializer::new(String::new());
-                    se.escape(quick_xml::se::QuoteLevel::Partial);
-                    let ser = self.serialize(se).unwrap();
+                /// Gets the value of the `type` attribute for this [`ComponentType`].
+                pub(crate) fn xml_type(&self) -> &'static str {
+                    match self {
+                        $(
+                            Self::$x { .. } => stringify!($id),
+                        )*
+                    }
+                }
 
-                    let mut de: FakeMap<String, RecursiveStringMap> = quick_xml::de::from_str(&ser).unwrap();
+            }
 
-                    #[allow(clippy::redundant_closure_call)]
-                    ($($ser_to_map)*)(&self, &mut de);
+            #[cfg(feature = "json")]
+            impl crate::microcontroller::json::JsonComponent for $type {
+                fn type_name(&self) -> &'static str {
+                    self.name()
+                }
 
-                    de
+                fn settings_json(&self) -> Vec<(&'static str, Option<serde_json::Value>)> {
+                    match self {
+                        $(
+                            #[allow(unused_variables)]
+                            Self::$x { $( $f, )* .. } => vec![
+                                $( (stringify!($f), crate::microcontroller::json::JsonSetting::to_json($f)), )*
+                            ],
+                        )*
+                    }
                 }
 
+                fn from_settings_json(
+                    name: &str,
+                    mut setting: impl FnMut(&'static str) -> Option<serde_json::Value>,
+                ) -> Option<Result<Self, (&'static str, String)>> {
+                    $(
+                        // one function per variant, so debug builds don't reserve stack space
+                        // for every variant at once
+                        #[allow(non_snake_case, unused_variables)]
+                        fn $x(
+                            setting: &mut dyn FnMut(&'static str) -> Option<serde_json::Value>,
+                        ) -> Result<Box<$type>, (&'static str, String)> {
+                            Ok(Box::new($type::$x {
+                                $( $in_id: TypedInputConnection::empty(), )*
+                                $( $out_id: TypedOutputConnection::default(), )*
+                                $(
+                                    $f: crate::microcontroller::json::JsonSetting::from_json(setting(stringify!($f)))
+                                        .map_err(|e| (stringify!($f), e))?,
+                                )*
+                            }))
+                        }
+                    )*
+
+                    let component = match name {
+                        $( stringify!($x) => $x(&mut setting), )*
+                        _ => return None,
+                    };
+                    Some(component.map(|c| *c))
+                }
+
+                fn slots(&self) -> Vec<(&'static str, &dyn Slot)> {
+                    match self {
+                        $(
+                            Self::$x { $( $in_id, )* $( $out_id, )* .. } => vec![
+                                $( (stringify!($in_id), $in_id as &dyn Slot), )*
+                                $( (stringify!($out_id), $out_id as &dyn Slot), )*
+                            ],
+                        )*
+                    }
+                }
+
+                fn slots_mut(&mut self) -> Vec<(&'static str, &mut dyn Slot)> {
+                    match self {
+                        $(
+                            Self::$x { $( $in_id, )* $( $out_id, )* .. } => vec![
+                                $( (stringify!($in_id), $in_id as &mut dyn Slot), )*
+                                $( (stringify!($out_id), $out_id as &mut dyn Slot), )*
+                            ],
+                        )*
+                    }
+                }
+
+                fn inputs_json_mut(&mut self) -> Vec<&mut Option<ComponentConnection>> {
+                    self.inputs_mut()
+                }
+
+                fn inputs_json(&self) -> Vec<&Option<ComponentConnection>> {
+                    self.inputs()
+                }
             }
         }
     };
@@ -559,6 +504,24 @@ impl TextValue {
         let val = val.into();
         Self { text: val.to_string(), value: val }
     }
+
+    /// Creates a [`TextValue`] from text and a value that aren't checked against each other.
+    #[cfg(feature = "json")]
+    pub(crate) fn from_parts(text: String, value: f64) -> Self {
+        Self { text, value }
+    }
+
+    /// Gets the text as entered ingame.
+    #[allow(clippy::must_use_candidate)]
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Gets the value the text parses to.
+    #[allow(clippy::must_use_candidate)]
+    pub fn value(&self) -> f64 {
+        self.value
+    }
 }
 
 /// Struct representing a dropdown item with a label and value.
@@ -576,6 +539,11 @@ pub struct DropdownItem {
     pub value: TextValue,
 }
 
+pub mod state;
+mod xml;
+
+pub(crate) use xml::serialize_states;
+
 mod dropdown_items {
     use fakemap::FakeMap;
     use serde::{Deserialize, Serialize};
@@ -605,6 +573,164 @@ mod dropdown_items {
     }
 }
 
+/// Error for a component setting that has a value the game doesn't know about.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("unknown {setting} value {value:?}")]
+pub struct UnknownSettingValue {
+    /// Name of the setting's type.
+    pub setting: &'static str,
+    /// The value as it appeared in the XML.
+    pub value: String,
+}
+
+macro_rules! setting_enum {
+    ($(#[$m:meta])* $name:ident { $($(#[$vm:meta])* $v:ident = $n:literal),* $(,)? }) => {
+        $(#[$m])*
+        #[derive(Serialize_repr, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+        #[repr(u8)]
+        pub enum $name {
+            $($(#[$vm])* $v = $n),*
+        }
+
+        impl FromStr for $name {
+            type Err = UnknownSettingValue;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                match s.parse::<u8>() {
+                    $(Ok($n) => Ok(Self::$v),)*
+                    _ => Err(UnknownSettingValue { setting: stringify!($name), value: s.into() }),
+                }
+            }
+        }
+    };
+}
+
+setting_enum! {
+    /// When a tooltip is shown, used in [`ComponentType::TooltipNum`] and [`ComponentType::TooltipOnOff`].
+    TooltipMode {
+        /// Always show the tooltip.
+        #[default]
+        Always = 0,
+        /// Only show the tooltip while the error input is on.
+        IfError = 1,
+        /// Only show the tooltip while the error input is off.
+        IfNoError = 2,
+    }
+}
+
+setting_enum! {
+    /// Units of a timer's duration, used in [`ComponentType::TimerTON`] and the other timers.
+    TimerUnits {
+        /// Duration is in seconds.
+        #[default]
+        Seconds = 0,
+        /// Duration is in ticks.
+        Ticks = 1,
+    }
+}
+
+setting_enum! {
+    /// Whether an [`ComponentType::UpDownCounter`] is clamped to its min and max.
+    UpDownCounterMode {
+        /// The counter isn't limited.
+        #[default]
+        Unclamped = 0,
+        /// The counter stays between min and max.
+        Clamped = 1,
+    }
+}
+
+/// When a [`ComponentType::Pulse`] outputs a pulse.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum PulseMode {
+    /// When the input turns on. Stored as no value at all.
+    #[default]
+    OffToOn,
+    /// When the input turns off. Stored as `0`.
+    OnToOff,
+    /// Whenever the input changes. Stored as `2`.
+    Always,
+}
+
+impl Serialize for PulseMode {
+    fn serialize<S: serde::Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+        match self {
+            PulseMode::OffToOn => ser.serialize_none(),
+            PulseMode::OnToOff => ser.serialize_u8(0),
+            PulseMode::Always => ser.serialize_u8(2),
+        }
+    }
+}
+
+impl FromStr for PulseMode {
+    type Err = UnknownSettingValue;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.parse::<u8>() {
+            Ok(0) => Ok(PulseMode::OnToOff),
+            Ok(2) => Ok(PulseMode::Always),
+            _ => Err(UnknownSettingValue { setting: "PulseMode", value: s.into() }),
+        }
+    }
+}
+
+/// Which channel of a composite signal a composite read/write uses.
+///
+/// Stored as the 0-based channel index, or `-1` for [`Variable`][CompositeChannel::Variable].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CompositeChannel {
+    /// The channel comes from the component's variable channel/start input.
+    Variable,
+    /// A fixed 0-based channel index (channel 1 ingame is `Fixed(0)`).
+    Fixed(u8),
+}
+
+impl Default for CompositeChannel {
+    fn default() -> Self {
+        CompositeChannel::Fixed(0)
+    }
+}
+
+impl CompositeChannel {
+    /// Creates a fixed channel from the 1-based number shown ingame, if it's in `1..=32`.
+    #[must_use]
+    pub fn from_number(number: u8) -> Option<Self> {
+        (1..=32)
+            .contains(&number)
+            .then(|| CompositeChannel::Fixed(number - 1))
+    }
+
+    /// Gets the 1-based number shown ingame, or [`None`] if the channel is variable.
+    #[must_use]
+    pub fn number(self) -> Option<u8> {
+        match self {
+            CompositeChannel::Variable => None,
+            CompositeChannel::Fixed(i) => Some(i + 1),
+        }
+    }
+}
+
+impl Serialize for CompositeChannel {
+    fn serialize<S: serde::Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+        match self {
+            CompositeChannel::Variable => ser.serialize_i8(-1),
+            CompositeChannel::Fixed(i) => ser.serialize_u8(*i),
+        }
+    }
+}
+
+impl FromStr for CompositeChannel {
+    type Err = UnknownSettingValue;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.parse::<i8>() {
+            Ok(-1) => Ok(CompositeChannel::Variable),
+            Ok(i @ 0..=31) => Ok(CompositeChannel::Fixed(i.unsigned_abs())),
+            _ => Err(UnknownSettingValue { setting: "CompositeChannel", value: s.into() }),
+        }
+    }
+}
+
 fn one() -> f32 {
     1.0
 }
@@ -684,7 +810,7 @@ components! { ComponentType,
         reset_value: TextValue,
 
         #[serde(rename = "@memory", default, skip_serializing_if = "is_default")]
-        __memory: Option<String>, // ??
+        __memory: Option<String>, // see state::MemoryRegisterState
     },
     14 = Abs[input(1): Number][out(1): Number]{},
     15 = ConstantNum[][out(1): Number]{
@@ -705,6 +831,9 @@ components! { ComponentType,
     20 = PropertyDropdown[][out(1): Number]{
         #[serde(rename = "@name", default = "str_value", skip_serializing_if = "is_str_value")]
         name: String,
+        // index of the selected item
+        #[serde(rename = "@i", default, skip_serializing_if = "is_default", deserialize_with = "de_from_str")]
+        selected: u32,
 
         #[serde(with = "dropdown_items")]
         items: Vec<DropdownItem>,
@@ -714,13 +843,13 @@ components! { ComponentType,
     22 = NumericalSwitchbox[on(1): Number, off(2): Number, switch(3): OnOff][out(1): Number]{},
     23 = PIDController[setpoint(1): Number, process_var(2): Number, active(3): OnOff][out(1): Number]{
         #[serde(rename = "@te", default, skip_serializing_if = "is_default")]
-        __te: Option<String>, // ??
+        __te: Option<String>, // see state::PIDState
         #[serde(rename = "@p2", default, skip_serializing_if = "is_default")]
-        __p2: Option<String>, // ??
+        __p2: Option<String>, // see state::PIDState
         #[serde(rename = "@pe", default, skip_serializing_if = "is_default")]
-        __pe: Option<String>, // ??
+        __pe: Option<String>, // see state::PIDState
         #[serde(rename = "@pes", default, skip_serializing_if = "is_default")]
-        __pes: Option<String>, // ??
+        __pes: Option<String>, // see state::PIDState
 
         kp: TextValue,
         ki: TextValue,
@@ -728,7 +857,7 @@ components! { ComponentType,
     },
     24 = SRLatch[set(1): OnOff, reset(2): OnOff][out(1): OnOff, not_out(2): OnOff]{
         #[serde(rename = "@p1", default, skip_serializing_if = "is_default")]
-        __p1: Option<String>, // ??
+        __p1: Option<String>, // see state::SRLatchState
     },
     25 = JKFlipFlop[set(1): OnOff, reset(2): OnOff][out(1): OnOff, not_out(2): OnOff]{},
     26 = Capacitor[charge(1): OnOff][stored(1): OnOff]{
@@ -738,11 +867,11 @@ components! { ComponentType,
         dt: f32,
 
         #[serde(rename = "@c1", default, skip_serializing_if = "is_default")]
-        __c1: Option<String>, // ??
+        __c1: Option<String>, // see state::CapacitorState
         #[serde(rename = "@c2", default, skip_serializing_if = "is_default")]
-        __c2: Option<String>, // ??
+        __c2: Option<String>, // see state::CapacitorState
         #[serde(rename = "@p", default, skip_serializing_if = "is_default")]
-        __p: Option<String>, // ??
+        __p: Option<String>, // see state::CapacitorState
     },
     27 = Blinker[control(1): OnOff][out(1): OnOff]{
         #[serde(rename = "@on", default = "one", skip_serializing_if="is_one", deserialize_with = "de_from_str")]
@@ -751,20 +880,20 @@ components! { ComponentType,
         off: f32,
 
         #[serde(rename = "@c", default, skip_serializing_if = "is_default")]
-        __c: Option<String>, // ??
+        __c: Option<String>, // see state::BlinkerState
     },
     28 = PushToToggle[toggle(1): OnOff][state(1): OnOff]{},
     29 = CompositeReadOnOff[composite(1): Composite, variable_channel(2): Number][out(1): OnOff]{
-        #[serde(rename = "@i", default, skip_serializing_if = "is_default", deserialize_with = "de_from_str")] // TODO
-        channel: i8, // TODO: "variable (from node)" is -1, make this an enum
+        #[serde(rename = "@i", default, skip_serializing_if = "is_default", deserialize_with = "de_from_str")]
+        channel: CompositeChannel,
     },
     30 = _OldCompositeWriteOnOff[composite(1): Composite, val(2): OnOff][out(1): Composite]{
         #[serde(rename = "@i", default, skip_serializing_if = "is_default", deserialize_with = "de_from_str")] // TODO
         channel: u8, // no option for "variable (from node)"
     },
     31 = CompositeReadNum[composite(1): Composite, variable_channel(2): Number][out(1): Number]{
-        #[serde(rename = "@i", default, skip_serializing_if = "is_default", deserialize_with = "de_from_str")] // TODO
-        channel: i8, // TODO: "variable (from node)" is -1, make this an enum
+        #[serde(rename = "@i", default, skip_serializing_if = "is_default", deserialize_with = "de_from_str")]
+        channel: CompositeChannel,
     },
     32 = _OldCompositeWriteNum[composite(1): Composite, val(2): Number][out(1): Composite]{
         #[serde(rename = "@i", default, skip_serializing_if = "is_default", deserialize_with = "de_from_str")] // TODO
@@ -789,9 +918,9 @@ components! { ComponentType,
     },
     35 = Delta[input(1): Number][out(1): Number]{
         #[serde(rename = "@vp", default, skip_serializing_if = "is_default")]
-        __vp: Option<String>, // ??
+        __vp: Option<String>, // see state::DeltaState
         #[serde(rename = "@ip", default, skip_serializing_if = "is_default")]
-        __ip: Option<String>, // ??
+        __ip: Option<String>, // see state::DeltaState
     },
     36 = Func8n[x(1): Number, y(2): Number, z(3): Number, w(4): Number, a(5): Number, b(6): Number, c(7): Number, d(8): Number][out(1): Number]{
         #[serde(rename = "@e", default, skip_serializing_if = "is_default")]
@@ -799,7 +928,7 @@ components! { ComponentType,
     },
     37 = UpDownCounter[up(1): OnOff, down(2): OnOff, reset(3): OnOff][out(1): Number]{
         #[serde(rename = "@m", default, skip_serializing_if = "is_default", deserialize_with = "de_from_str")]
-        mode: u8, // 1 for clamp, 0 for disabled
+        mode: UpDownCounterMode,
 
         #[serde(rename = "@is", default, skip_serializing_if = "is_default")]
         __is: Option<String>, // ??
@@ -816,27 +945,27 @@ components! { ComponentType,
     38 = Modulo[input_a(1): Number, input_b(2): Number][out(1): Number]{},
     39 = PIDControllerAdvanced[setpoint(1): Number, process_var(2): Number, p(3): Number, i(4): Number, d(5): Number, active(6): OnOff][out(1): Number]{
         #[serde(rename = "@te", default, skip_serializing_if = "is_default")]
-        __te: Option<String>, // ??
+        __te: Option<String>, // see state::PIDState
         #[serde(rename = "@p2", default, skip_serializing_if = "is_default")]
-        __p2: Option<String>, // ??
+        __p2: Option<String>, // see state::PIDState
         #[serde(rename = "@pe", default, skip_serializing_if = "is_default")]
-        __pe: Option<String>, // ??
+        __pe: Option<String>, // see state::PIDState
         #[serde(rename = "@pes", default, skip_serializing_if = "is_default")]
-        __pes: Option<String>, // ??
+        __pes: Option<String>, // see state::PIDState
     },
     // NOTE: CompositeWriteNum uses tags inc, in1, in2, etc.
     40 = CompositeWriteNum[composite(1): Composite, in1(2): Number, in2(3): Number, in3(4): Number, in4(5): Number, in5(6): Number, in6(7): Number, in7(8): Number, in8(9): Number, in9(10): Number, in10(11): Number, in11(12): Number, in12(13): Number, in13(14): Number, in14(15): Number, in15(16): Number, in16(17): Number, in17(18): Number, in18(19): Number, in19(20): Number, in20(21): Number, in21(22): Number, in22(23): Number, in23(24): Number, in24(25): Number, in25(26): Number, in26(27): Number, in27(28): Number, in28(29): Number, in29(30): Number, in30(31): Number, in31(32): Number, in32(33): Number, start(34): Number][out(1): Composite]{
         #[serde(rename = "@count", deserialize_with = "de_from_str")]
         count: u8,
         #[serde(rename = "@offset", default, skip_serializing_if = "is_default", deserialize_with = "de_from_str")]
-        offset: i8, // TODO: "variable (from node)" is -1, make this an enum
+        offset: CompositeChannel,
     },
     // NOTE: CompositeWriteOnOff uses tags inc, in1, in2, etc.
     41 = CompositeWriteOnOff[composite(1): Composite, in1(2): OnOff, in2(3): OnOff, in3(4): OnOff, in4(5): OnOff, in5(6): OnOff, in6(7): OnOff, in7(8): OnOff, in8(9): OnOff, in9(10): OnOff, in10(11): OnOff, in11(12): OnOff, in12(13): OnOff, in13(14): OnOff, in14(15): OnOff, in15(16): OnOff, in16(17): OnOff, in17(18): OnOff, in18(19): OnOff, in19(20): OnOff, in20(21): OnOff, in21(22): OnOff, in22(23): OnOff, in23(24): OnOff, in24(25): OnOff, in25(26): OnOff, in26(27): OnOff, in27(28): OnOff, in28(29): OnOff, in29(30): OnOff, in30(31): OnOff, in31(32): OnOff, in32(33): OnOff, start(34): Number][out(1): Composite]{
         #[serde(rename = "@count", deserialize_with = "de_from_str")]
         count: u8,
         #[serde(rename = "@offset", default, skip_serializing_if = "is_default", deserialize_with = "de_from_str")]
-        offset: i8, // TODO: "variable (from node)" is -1, make this an enum
+        offset: CompositeChannel,
     },
     42 = Equal[input_a(1): Number, input_b(2): Number][out(1): OnOff]{
         #[serde(rename = "e")]
@@ -846,7 +975,7 @@ components! { ComponentType,
         #[serde(rename = "@l")]
         label: String,
         #[serde(rename = "@m", default, skip_serializing_if = "is_default", deserialize_with = "de_from_str")]
-        mode: u8, // TODO: enum: 0 for Always, 1 for If Error, 2 for If No Error
+        mode: TooltipMode,
     },
     44 = TooltipOnOff[display(1): OnOff][]{
         #[serde(rename = "@l")]
@@ -856,7 +985,7 @@ components! { ComponentType,
         #[serde(rename = "@off")]
         off: String,
         #[serde(rename = "@m", default, skip_serializing_if = "is_default", deserialize_with = "de_from_str")]
-        mode: u8, // TODO: enum: 0 for Always, 1 for If Error, 2 for If No Error
+        mode: TooltipMode,
     },
     45 = Func1n[input(1): Number][out(1): Number]{
         #[serde(rename = "@e", default, skip_serializing_if="is_default")]
@@ -871,39 +1000,39 @@ components! { ComponentType,
         expr: String,
     },
     48 = Pulse[input(1): OnOff][out(1): OnOff]{
-        #[serde(rename = "@m", default, skip_serializing_if="is_default", deserialize_with = "de_from_str_opt")]
-        mode: Option<u8>, // None for Off->On, 0 for On->Off, 2 for Always
+        #[serde(rename = "@m", default, skip_serializing_if="is_default", deserialize_with = "de_from_str")]
+        mode: PulseMode,
 
         #[serde(rename = "@p", default, skip_serializing_if = "is_default")]
-        __p: Option<String>, // ??
+        __p: Option<String>, // see state::PulseState
     },
     49 = TimerTON[enable(1): OnOff, duration(2): Number][complete(1): OnOff]{
         #[serde(rename = "@u", default, skip_serializing_if="is_default", deserialize_with = "de_from_str")]
-        units: u8, // TODO: enum: 0 for secs, 1 for ticks
+        units: TimerUnits,
 
         #[serde(rename = "@t", default, skip_serializing_if = "is_default")]
-        __t: Option<String>, // ??
+        __t: Option<String>, // see state::TimerState
     },
     50 = TimerTOF[enable(1): OnOff, duration(2): Number][timing(1): OnOff]{
         #[serde(rename = "@u", default, skip_serializing_if="is_default", deserialize_with = "de_from_str")]
-        units: u8, // TODO: enum: 0 for secs, 1 for ticks
+        units: TimerUnits,
 
         #[serde(rename = "@t", default, skip_serializing_if = "is_default")]
-        __t: Option<String>, // ??
+        __t: Option<String>, // see state::TimerState
     },
     51 = TimerRTO[enable(1): OnOff, duration(2): Number, reset(3): OnOff][complete(1): OnOff]{
         #[serde(rename = "@u", default, skip_serializing_if="is_default", deserialize_with = "de_from_str")]
-        units: u8, // TODO: enum: 0 for secs, 1 for ticks
+        units: TimerUnits,
 
         #[serde(rename = "@t", default, skip_serializing_if = "is_default")]
-        __t: Option<String>, // ??
+        __t: Option<String>, // see state::TimerState
     },
     52 = TimerRTF[enable(1): OnOff, duration(2): Number, reset(3): OnOff][timing(1): OnOff]{
         #[serde(rename = "@u", default, skip_serializing_if="is_default", deserialize_with = "de_from_str")]
-        units: u8, // TODO: enum: 0 for secs, 1 for ticks
+        units: TimerUnits,
 
         #[serde(rename = "@t", default, skip_serializing_if = "is_default")]
-        __t: Option<String>, // ??
+        __t: Option<String>, // see state::TimerState
     },
     53 = CompositeSwitchbox[on(1): Composite, off(2): Composite, switch(3): OnOff][out(1): Composite]{},
     54 = NumToCompositeBin[input(1): Number][out(1): Composite]{},
@@ -920,66 +1049,6 @@ components! { ComponentType,
         val: String,
     },
     59 = AudioSwitchbox[on(1): Audio, off(2): Audio, switch(3): OnOff][out(1): Audio]{},
-    {
-        |c: &ComponentType, de: &mut FakeMap<String, RecursiveStringMap>| {
-            // see note on NumericalJunction
-            if matches!(c, ComponentType::NumericalJunction { .. }) {
-                // FakeMap has no get_mut
-                if let Some(RecursiveStringMap::Map(mut o)) = de.remove("object") {
-                    o.remove("out2");
-                    o.duplicate_by_key("out1".into(), "__reserved".into());
-                    de.insert("object".into(), RecursiveStringMap::Map(o));
-                }
-            }
-
-
-            // map in1,in2,in3,etc. to inc,in1,in2,etc.
-            // see note on CompositeWriteNum/CompositeWriteOnOff
-            if let ComponentType::CompositeWriteNum { count, offset, .. } | ComponentType::CompositeWriteOnOff { count, offset, .. } = c {
-                if let Some(RecursiveStringMap::Map(mut o)) = de.remove("object") {
-                    for (k, _) in o.iter_mut() {
-                        if *k == "in1" {
-                            *k = "inc".into();
-                        } else if *k == "in34" {
-                            *k = "inoff".into();
-                        } else if k.starts_with("in") {
-                            if let Ok(n) = k.trim_start_matches("in").parse::<u8>() {
-                                *k = format!("in{}", n - 1);
-                            }
-                        }
-                    }
-
-                    if *offset != -1 {
-                        o.remove("inoff");
-                    }
-
-                    for i in 1..=32 {
-                        if i > *count {
-                            let l = format!("in{i}");
-                            o.remove(&l);
-                        }
-                    }
-
-                    de.insert("object".into(), RecursiveStringMap::Map(o));
-                }
-            }
-
-            // remove in2 if channel is constant
-            if let ComponentType::CompositeReadNum { channel, .. } | ComponentType::CompositeReadOnOff { channel, .. } = c {
-                if let Some(RecursiveStringMap::Map(mut o)) = de.remove("object") {
-                    if *channel == -1 {
-                        // for some reason, in these nodes in2 is supposed to go after out1
-                        let in2 = o.remove("in2").unwrap();
-                        o.insert("in2".into(), in2);
-                    } else {
-                        o.remove("in2");
-                    }
-
-                    de.insert("object".into(), RecursiveStringMap::Map(o));
-                }
-            }
-        }
-    }
 }
 
 components! { BridgeComponentType,
@@ -993,80 +1062,18 @@ components! { BridgeComponentType,
     7 = VideoOut[input(1): Video][unused_output(1): Video]{},
     8 = AudioIn[unused_input(1): Audio][output(1): Audio]{},
     9 = AudioOut[input(1): Audio][unused_output(1): Audio]{},
-    {
-        |_c: &BridgeComponentType, _de: &mut FakeMap<String, RecursiveStringMap>| {}
-    }
-}
-
-pub(crate) fn bridge_components_deserialize<'de, D>(de: D) -> Result<Vec<BridgeComponent>, D::Error>
-where
-    D: serde::Deserializer<'de>,
-{
-    let de = Vec::<_BridgeComponentTypeDe>::deserialize(de)?;
-    // println!("{de:?}");
-    let cs = de
-        .into_iter()
-        .map(|mut cde| {
-            if cde.inner.get("@type").is_none() {
-                cde.inner
-                    .insert("@type".into(), RecursiveStringMap::String("0".into()));
-            }
-
-            cde.into()
-        })
-        .collect();
-
-    Ok(cs)
-}
-
-pub(crate) fn bridge_components_serialize<S>(
-    components: &[BridgeComponent],
-    ser: S,
-) -> Result<S::Ok, S::Error>
-where
-    S: serde::Serializer,
-{
-    let cdes = components
-        .iter()
-        .map(|c| {
-            #[derive(Serialize, Debug)]
-            struct W<'a> {
-                object: &'a BridgeComponent,
-            }
-
-            let mut se = quick_xml::se::Serializer::new(String::new());
-            se.escape(quick_xml::se::QuoteLevel::Partial);
-            let ser = W { object: c }.serialize(se).unwrap();
-            let ser = ser.trim_start_matches("<W>").trim_end_matches("</W>");
-
-            let mut cde: _ComponentTypeDe = quick_xml::de::from_str(ser).unwrap();
-            if let Some(RecursiveStringMap::String(s)) = cde.inner.get("@type").cloned() {
-                if s == "0" {
-                    cde.inner.remove("@type");
-                }
-            }
-
-            cde
-        })
-        .collect::<Vec<_>>();
-
-    ser.collect_seq(cdes.iter())
 }
 
 /// [`ComponentType`] with an id.
-#[derive(Serialize, Deserialize, Clone, Debug)]
-#[serde(from = "_ComponentDe", into = "_ComponentDe")]
+#[derive(Clone, Debug)]
 pub struct Component {
-    #[serde(rename = "@id")]
     pub(crate) id: u32,
     /// The position of the component.
     ///
     /// Each grid square is 0.25 units.
-    #[serde(default, skip_serializing_if = "is_default")]
     pub pos: PositionXY,
 
     /// The [`ComponentType`].
-    #[serde(flatten)]
     pub component: ComponentType,
 }
 
@@ -1078,112 +1085,50 @@ impl Component {
     pub fn id(&self) -> u32 {
         self.id
     }
-
-    #[allow(dead_code)]
-    #[must_use]
-    pub(crate) fn ser_to_map(&self) -> FakeMap<String, RecursiveStringMap> {
-        let mut m = self.component.ser_to_map();
-
-        m.insert_idx(
-            0,
-            "@id".into(),
-            RecursiveStringMap::String(self.id.to_string()),
-        );
-        if !is_default(&self.pos) {
-            m.insert_idx(1, "pos".into(), self.pos.clone().into());
-        }
-
-        m
-    }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct _ComponentDe {
-    #[serde(flatten)]
-    inner: FakeMap<String, RecursiveStringMap>,
+impl Serialize for Component {
+    fn serialize<S: serde::Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+        xml::serialize_component(self, ser)
+    }
 }
 
-impl From<_ComponentDe> for Component {
-    fn from(mut de: _ComponentDe) -> Self {
-        #[derive(Serialize, Deserialize, Debug)]
-        struct W {
-            c: _ComponentDe,
-        }
-
-        #[derive(Serialize, Deserialize, Clone, Debug)]
-        #[serde(rename = "c")]
-        struct _RawComponent {
-            #[serde(rename = "@id")]
-            pub id: u32,
-            #[serde(default, skip_serializing_if = "is_default")]
-            pub pos: PositionXY,
-            #[serde(flatten)]
-            pub component: Box<ComponentType>,
-        }
-
-        if let Some(RecursiveStringMap::Map(mut o)) = de.inner.remove("object") {
-            // println!("{o:?}");
-            de.inner
-                .insert_idx(0, "@id".into(), o.remove("@id").unwrap());
-
-            if let Some(pos) = o.remove("pos") {
-                de.inner.insert_idx(1, "pos".into(), pos);
-            }
-
-            de.inner.insert("object".into(), RecursiveStringMap::Map(o));
-        }
-
-        let db = format!("{de:?}");
-
-        // println!("pre {de:?}");
-        let mut se = quick_xml::se::Serializer::new(String::new());
-        se.escape(quick_xml::se::QuoteLevel::Partial);
-        let ser = W { c: de }.serialize(se).unwrap();
-        let ser = ser.trim_start_matches("<W>").trim_end_matches("</W>");
-
-        // println!("se {ser} {}", size_of::<_RawComponentWithId>());
-        let de: _RawComponent = quick_xml::de::from_str(ser)
-            .expect(&format!("Deserializing component:\n{db}\n{ser}\n"));
-
-        // println!("don {de:?}");
-
-        Component { id: de.id, pos: de.pos, component: *de.component }
+impl<'de> Deserialize<'de> for Component {
+    fn deserialize<D: serde::Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
+        let (id, pos, component) = xml::deserialize_component(de)?;
+        Ok(Component { id, pos, component })
     }
 }
 
-impl From<Component> for _ComponentDe {
-    fn from(c: Component) -> Self {
-        let mut m = c.ser_to_map();
-
-        if let Some(RecursiveStringMap::Map(mut o)) = m.remove("object") {
-            o.insert_idx(0, "@id".into(), m.remove("@id").unwrap());
-            if let Some(pos) = m.remove("pos") {
-                o.insert_idx(1, "pos".into(), pos);
-            }
-
-            m.insert("object".into(), RecursiveStringMap::Map(o));
+impl BridgeComponentType {
+    /// Whether this bridges an input or an output node.
+    #[must_use]
+    pub fn mode(&self) -> IONodeType {
+        match self {
+            BridgeComponentType::OnOffIn { .. }
+            | BridgeComponentType::NumberIn { .. }
+            | BridgeComponentType::CompositeIn { .. }
+            | BridgeComponentType::VideoIn { .. }
+            | BridgeComponentType::AudioIn { .. } => IONodeType::Input,
+            BridgeComponentType::OnOffOut { .. }
+            | BridgeComponentType::NumberOut { .. }
+            | BridgeComponentType::CompositeOut { .. }
+            | BridgeComponentType::VideoOut { .. }
+            | BridgeComponentType::AudioOut { .. } => IONodeType::Output,
         }
-
-        let de: _ComponentDe = _ComponentDe { inner: m };
-
-        de
     }
 }
 
 /// [`BridgeComponentType`] with an id.
-#[derive(Serialize, Deserialize, Clone, Debug)]
-#[serde(from = "_BridgeComponentDe", into = "_BridgeComponentDe")]
+#[derive(Clone, Debug)]
 pub struct BridgeComponent {
-    #[serde(rename = "@id")]
     pub(crate) id: u32,
     /// The position of the component.
     ///
     /// Each grid square is 0.25 units.
-    #[serde(default, skip_serializing_if = "is_default")]
     pub pos: PositionXY,
 
     /// The [`BridgeComponentType`].
-    #[serde(flatten)]
     pub component: BridgeComponentType,
 }
 
@@ -1195,88 +1140,17 @@ impl BridgeComponent {
     pub fn id(&self) -> u32 {
         self.id
     }
-
-    #[allow(dead_code)]
-    #[must_use]
-    pub(crate) fn ser_to_map(&self) -> FakeMap<String, RecursiveStringMap> {
-        let mut m = self.component.ser_to_map();
-
-        m.insert_idx(
-            0,
-            "@id".into(),
-            RecursiveStringMap::String(self.id.to_string()),
-        );
-        if !is_default(&self.pos) {
-            m.insert_idx(1, "pos".into(), self.pos.clone().into());
-        }
-
-        m
-    }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct _BridgeComponentDe {
-    #[serde(flatten)]
-    inner: FakeMap<String, RecursiveStringMap>,
-}
-
-impl From<_BridgeComponentDe> for BridgeComponent {
-    fn from(mut de: _BridgeComponentDe) -> Self {
-        #[derive(Serialize, Deserialize, Debug)]
-        struct W {
-            object: _BridgeComponentDe,
-        }
-
-        #[derive(Serialize, Deserialize, Clone, Debug)]
-        struct _RawBridgeComponent {
-            #[serde(rename = "@id")]
-            pub id: u32,
-            #[serde(default, skip_serializing_if = "is_default")]
-            pub pos: PositionXY,
-            #[serde(flatten)]
-            pub component: BridgeComponentType,
-        }
-
-        if let Some(RecursiveStringMap::Map(mut o)) = de.inner.remove("object") {
-            de.inner
-                .insert_idx(0, "@id".into(), o.remove("@id").unwrap());
-
-            if let Some(pos) = o.remove("pos") {
-                de.inner.insert_idx(1, "pos".into(), pos);
-            }
-
-            de.inner.insert("object".into(), RecursiveStringMap::Map(o));
-        }
-
-        let db = format!("{de:?}");
-
-        let mut se = quick_xml::se::Serializer::new(String::new());
-        se.escape(quick_xml::se::QuoteLevel::Partial);
-        let ser = W { object: de }.serialize(se).unwrap();
-        let ser = ser.trim_start_matches("<W>").trim_end_matches("</W>");
-
-        let de: _RawBridgeComponent = quick_xml::de::from_str(ser)
-            .expect(&format!("Deserializing component:\n{db}\n{ser}\n"));
-
-        BridgeComponent { id: de.id, pos: de.pos, component: de.component }
+impl Serialize for BridgeComponent {
+    fn serialize<S: serde::Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+        xml::serialize_component(self, ser)
     }
 }
 
-impl From<BridgeComponent> for _BridgeComponentDe {
-    fn from(c: BridgeComponent) -> Self {
-        let mut m = c.ser_to_map();
-
-        if let Some(RecursiveStringMap::Map(mut o)) = m.remove("object") {
-            o.insert_idx(0, "@id".into(), m.remove("@id").unwrap());
-            if let Some(pos) = m.remove("pos") {
-                o.insert_idx(1, "pos".into(), pos);
-            }
-
-            m.insert("object".into(), RecursiveStringMap::Map(o));
-        }
-
-        let de: _BridgeComponentDe = _BridgeComponentDe { inner: m };
-
-        de
+impl<'de> Deserialize<'de> for BridgeComponent {
+    fn deserialize<D: serde::Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
+        let (id, pos, component) = xml::deserialize_component(de)?;
+        Ok(BridgeComponent { id, pos, component })
     }
 }
@@ -0,0 +1,316 @@
+//! Module containing typed access to the runtime state the game saves on stateful components.
+//!
+//! The state is stored as the original attribute text so unchanged microcontrollers serialize
+//! byte-for-byte, [`ComponentType::state`] decodes it and [`ComponentType::set_state`] encodes it.
+
+use std::str::FromStr;
+
+use thiserror::Error;
+
+use crate::{
+    microcontroller::{group::Group, Microcontroller},
+    util::AnyComponentMut,
+};
+
+use super::ComponentType;
+
+/// Error when reading or writing a component's state.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum StateError {
+    /// A saved state attribute doesn't parse.
+    #[error("Invalid value {value:?} for state attribute {attribute:?}")]
+    InvalidValue {
+        /// The XML attribute.
+        attribute: &'static str,
+        /// The value as it appeared in the XML.
+        value: String,
+    },
+    /// The state is for a different kind of component.
+    #[error("Can't set {state} state on a {component} component")]
+    WrongComponent {
+        /// Name of the component type.
+        component: &'static str,
+        /// Name of the [`ComponentState`] variant.
+        state: &'static str,
+    },
+}
+
+/// State of a [`ComponentType::MemoryRegister`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MemoryRegisterState {
+    /// The stored value (`memory`).
+    pub value: f32,
+}
+
+/// State of a [`ComponentType::Capacitor`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CapacitorState {
+    /// Time spent charging (`c1`).
+    pub charge: f32,
+    /// Time spent discharging (`c2`).
+    pub discharge: f32,
+    /// Whether the capacitor was charged (`p`).
+    pub charged: bool,
+}
+
+/// State of a [`ComponentType::Blinker`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BlinkerState {
+    /// Time into the current blink (`c`).
+    pub elapsed: f32,
+}
+
+/// State of a [`ComponentType::Pulse`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PulseState {
+    /// The input on the previous tick (`p`).
+    pub prev_input: bool,
+}
+
+/// State of one of the timer components ([`ComponentType::TimerTON`], [`ComponentType::TimerTOF`],
+/// [`ComponentType::TimerRTO`] and [`ComponentType::TimerRTF`]).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TimerState {
+    /// Time counted so far, in the timer's units (`t`).
+    pub elapsed: f32,
+}
+
+/// State of a [`ComponentType::Delta`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DeltaState {
+    /// The value on the previous tick (`vp`).
+    pub prev_value: f32,
+    /// The input on the previous tick (`ip`).
+    pub prev_input: f32,
+}
+
+/// State of a [`ComponentType::SRLatch`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SRLatchState {
+    /// Whether the latch is set (`p1`).
+    pub set: bool,
+}
+
+/// State of a [`ComponentType::PIDController`] or [`ComponentType::PIDControllerAdvanced`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PIDState {
+    /// Accumulated error for the integral term (`te`).
+    pub total_error: f32,
+    /// Error on the previous tick, for the derivative term (`pe`).
+    pub prev_error: f32,
+    /// Whether `prev_error` has been set yet (`pes`).
+    pub prev_error_set: bool,
+    /// Process variable on the previous tick (`p2`).
+    pub prev_process_var: f32,
+}
+
+/// Saved runtime state of a stateful component, see [`ComponentType::state`].
+#[allow(missing_docs)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ComponentState {
+    MemoryRegister(MemoryRegisterState),
+    Capacitor(CapacitorState),
+    Blinker(BlinkerState),
+    Pulse(PulseState),
+    Timer(TimerState),
+    Delta(DeltaState),
+    SRLatch(SRLatchState),
+    PID(PIDState),
+}
+
+impl ComponentState {
+    fn name(&self) -> &'static str {
+        match self {
+            ComponentState::MemoryRegister(_) => "MemoryRegister",
+            ComponentState::Capacitor(_) => "Capacitor",
+            ComponentState::Blinker(_) => "Blinker",
+            ComponentState::Pulse(_) => "Pulse",
+            ComponentState::Timer(_) => "Timer",
+            ComponentState::Delta(_) => "Delta",
+            ComponentState::SRLatch(_) => "SRLatch",
+            ComponentState::PID(_) => "PID",
+        }
+    }
+}
+
+/// A value that can be stored in a state attribute.
+trait StateValue: FromStr + Default + PartialEq {
+    fn to_xml(&self) -> String;
+}
+
+impl StateValue for f32 {
+    /// Formats like the game does (`%f` without trailing zeros).
+    fn to_xml(&self) -> String {
+        let s = format!("{self:.6}");
+        s.trim_end_matches('0').trim_end_matches('.').to_string()
+    }
+}
+
+impl StateValue for bool {
+    fn to_xml(&self) -> String {
+        self.to_string()
+    }
+}
+
+fn get<T: StateValue>(attribute: &'static str, slot: Option<&String>) -> Result<T, StateError> {
+    slot.map_or(Ok(T::default()), |s| {
+        s.parse()
+            .map_err(|_| StateError::InvalidValue { attribute, value: s.clone() })
+    })
+}
+
+/// Stores `value`, keeping the existing text if it already decodes to the same value and
+/// removing the attribute for the default value.
+fn put<T: StateValue>(slot: &mut Option<String>, value: &T) {
+    if slot.as_ref().and_then(|s| s.parse::<T>().ok()).as_ref() == Some(value) {
+        return;
+    }
+    *slot = (*value != T::default()).then(|| value.to_xml());
+}
+
+impl ComponentType {
+    /// Decodes the runtime state the game saved on this component.
+    ///
+    /// Returns [`None`] for components without state. Missing attributes decode to the default,
+    /// which is the state of a freshly placed component.
+    ///
+    /// # Errors
+    /// Returns a [`StateError::InvalidValue`] if a saved attribute doesn't parse.
+    pub fn state(&self) -> Result<Option<ComponentState>, StateError> {
+        Ok(Some(match self {
+            ComponentType::MemoryRegister { __memory, .. } => {
+                ComponentState::MemoryRegister(MemoryRegisterState {
+                    value: get("memory", __memory.as_ref())?,
+                })
+            },
+            ComponentType::Capacitor { __c1, __c2, __p, .. } => {
+                ComponentState::Capacitor(CapacitorState {
+                    charge: get("c1", __c1.as_ref())?,
+                    discharge: get("c2", __c2.as_ref())?,
+                    charged: get("p", __p.as_ref())?,
+                })
+            },
+            ComponentType::Blinker { __c, .. } => {
+                ComponentState::Blinker(BlinkerState { elapsed: get("c", __c.as_ref())? })
+            },
+            ComponentType::Pulse { __p, .. } => {
+                ComponentState::Pulse(PulseState { prev_input: get("p", __p.as_ref())? })
+            },
+            ComponentType::TimerTON { __t, .. }
+            | ComponentType::TimerTOF { __t, .. }
+            | ComponentType::TimerRTO { __t, .. }
+            | ComponentType::TimerRTF { __t, .. } => {
+                ComponentState::Timer(TimerState { elapsed: get("t", __t.as_ref())? })
+            },
+            ComponentType::Delta { __vp, __ip, .. } => ComponentState::Delta(DeltaState {
+                prev_value: get("vp", __vp.as_ref())?,
+                prev_input: get("ip", __ip.as_ref())?,
+            }),
+            ComponentType::SRLatch { __p1, .. } => {
+                ComponentState::SRLatch(SRLatchState { set: get("p1", __p1.as_ref())? })
+            },
+            ComponentType::PIDController { __te, __pe, __pes, __p2, .. }
+            | ComponentType::PIDControllerAdvanced { __te, __pe, __pes, __p2, .. } => {
+                ComponentState::PID(PIDState {
+                    total_error: get("te", __te.as_ref())?,
+                    prev_error: get("pe", __pe.as_ref())?,
+                    prev_error_set: get("pes", __pes.as_ref())?,
+                    prev_process_var: get("p2", __p2.as_ref())?,
+                })
+            },
+            _ => return Ok(None),
+        }))
+    }
+
+    /// Overwrites the runtime state saved on this component.
+    ///
+    /// # Errors
+    /// Returns a [`StateError::WrongComponent`] if `state` is for a different kind of component.
+    pub fn set_state(&mut self, state: &ComponentState) -> Result<(), StateError> {
+        match (&mut *self, state) {
+            (ComponentType::MemoryRegister { __memory, .. }, ComponentState::MemoryRegister(s)) => {
+                put(__memory, &s.value);
+            },
+            (ComponentType::Capacitor { __c1, __c2, __p, .. }, ComponentState::Capacitor(s)) => {
+                put(__c1, &s.charge);
+                put(__c2, &s.discharge);
+                put(__p, &s.charged);
+            },
+            (ComponentType::Blinker { __c, .. }, ComponentState::Blinker(s)) => {
+                put(__c, &s.elapsed);
+            },
+            (ComponentType::Pulse { __p, .. }, ComponentState::Pulse(s)) => put(__p, &s.prev_input),
+            (
+                ComponentType::TimerTON { __t, .. }
+                | ComponentType::TimerTOF { __t, .. }
+                | ComponentType::TimerRTO { __t, .. }
+                | ComponentType::TimerRTF { __t, .. },
+                ComponentState::Timer(s),
+            ) => put(__t, &s.elapsed),
+            (ComponentType::Delta { __vp, __ip, .. }, ComponentState::Delta(s)) => {
+                put(__vp, &s.prev_value);
+                put(__ip, &s.prev_input);
+            },
+            (ComponentType::SRLatch { __p1, .. }, ComponentState::SRLatch(s)) => put(__p1, &s.set),
+            (
+                ComponentType::PIDController { __te, __pe, __pes, __p2, .. }
+                | ComponentType::PIDControllerAdvanced { __te, __pe, __pes, __p2, .. },
+                ComponentState::PID(s),
+            ) => {
+                put(__te, &s.total_error);
+                put(__pe, &s.prev_error);
+                put(__pes, &s.prev_error_set);
+                put(__p2, &s.prev_process_var);
+            },
+            (c, state) => {
+                return Err(StateError::WrongComponent { component: c.name(), state: state.name() })
+            },
+        }
+        Ok(())
+    }
+
+    /// Clears the runtime state saved on this component, as if it was freshly placed.
+    ///
+    /// Returns `false` if this component has no state.
+    pub fn reset_state(&mut self) -> bool {
+        // this removes the attributes entirely, even ones that don't parse
+        match self {
+            ComponentType::MemoryRegister { __memory, .. } => *__memory = None,
+            ComponentType::Capacitor { __c1, __c2, __p, .. } => {
+                (*__c1, *__c2, *__p) = (None, None, None);
+            },
+            ComponentType::Blinker { __c, .. } => *__c = None,
+            ComponentType::Pulse { __p, .. } => *__p = None,
+            ComponentType::TimerTON { __t, .. }
+            | ComponentType::TimerTOF { __t, .. }
+            | ComponentType::TimerRTO { __t, .. }
+            | ComponentType::TimerRTF { __t, .. } => *__t = None,
+            ComponentType::Delta { __vp, __ip, .. } => (*__vp, *__ip) = (None, None),
+            ComponentType::SRLatch { __p1, .. } => *__p1 = None,
+            ComponentType::PIDController { __te, __pe, __pes, __p2, .. }
+            | ComponentType::PIDControllerAdvanced { __te, __pe, __pes, __p2, .. } => {
+                (*__te, *__pe, *__pes, *__p2) = (None, None, None, None);
+            },
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl Microcontroller {
+    /// Clears the runtime state saved on every component, including the ones in nested
+    /// [`groups`][`Self::groups`], see [`ComponentType::reset_state`].
+    ///
+    /// Returns the ids of the components that have state.
+    pub fn reset_states(&mut self) -> Vec<u32> {
+        let nested = self.groups.iter_mut().flat_map(Group::all_components_mut);
+        self.components
+            .iter_mut()
+            .chain(nested.filter_map(|c| match c {
+                AnyComponentMut::Component(c) => Some(c),
+                AnyComponentMut::BridgeComponent(_) => None,
+            }))
+            .filter_map(|c| c.component.reset_state().then_some(c.id))
+            .collect()
+    }
+}
@@ -0,0 +1,563 @@
+//! Single-pass (de)serialization between components and the game's XML tag layout.
+//!
+//! Components are stored as `<c type=".."><object id=".." ..><pos/>..</object></c>`. The derived
+//! (adjacently tagged) serde impls of [`ComponentType`] and [`BridgeComponentType`] handle the
+//! fields inside `object`, this module wraps the serializer/deserializer they're given to move the
+//! type to the `c` tag, add the id and position, and rename the few tags the game names differently.
+
+use std::{borrow::Cow, marker::PhantomData};
+
+use serde::{
+    de::{self, value::StrDeserializer, DeserializeSeed, IgnoredAny, MapAccess, Visitor},
+    ser::{self, Impossible, SerializeMap, SerializeStruct},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::{
+    microcontroller::mc_serde::is_default,
+    util::serde_utils::{ElementContext, PositionXY},
+};
+
+use super::{
+    skip_typedinputconnection, BridgeComponent, BridgeComponentType, Component, ComponentType,
+    CompositeChannel,
+};
+
+/// Differences between a component type's fields and the tags the game uses.
+pub(crate) trait TagLayout: Serialize + for<'de> Deserialize<'de> {
+    /// Value of the `type` attribute.
+    fn xml_type(&self) -> &'static str;
+
+    /// Gets the tag a field is written as, or [`None`] to leave it out.
+    fn ser_tag(&self, tag: &'static str) -> Option<Cow<'static, str>> {
+        Some(Cow::Borrowed(tag))
+    }
+
+    /// Writes any extra tags that go after the field with the given tag.
+    fn ser_after<M: SerializeMap>(&self, _tag: &str, _map: &mut M) -> Result<(), M::Error> {
+        Ok(())
+    }
+
+    /// Gets the field a tag is read into, `prev` is the tag before it.
+    fn de_tag<'t>(_xml_type: &str, tag: &'t str, _prev: Option<&str>) -> Cow<'t, str> {
+        Cow::Borrowed(tag)
+    }
+}
+
+impl TagLayout for ComponentType {
+    fn xml_type(&self) -> &'static str {
+        ComponentType::xml_type(self)
+    }
+
+    fn ser_tag(&self, tag: &'static str) -> Option<Cow<'static, str>> {
+        match self {
+            // see note on NumericalJunction
+            ComponentType::NumericalJunction { .. } if tag == "out2" => Some("out1".into()),
+            // map in1,in2,in3,etc. to inc,in1,in2,etc.
+            // see note on CompositeWriteNum/CompositeWriteOnOff
+            ComponentType::CompositeWriteNum { count, offset, .. }
+            | ComponentType::CompositeWriteOnOff { count, offset, .. } => match tag {
+                "in1" => Some("inc".into()),
+                "in34" => (*offset == CompositeChannel::Variable).then_some("inoff".into()),
+                _ => match tag.strip_prefix("in").and_then(|n| n.parse::<u8>().ok()) {
+                    Some(n @ 2..=33) => (n - 1 <= *count).then(|| format!("in{}", n - 1).into()),
+                    _ => Some(tag.into()),
+                },
+            },
+            // written by ser_after
+            ComponentType::CompositeReadNum { .. } | ComponentType::CompositeReadOnOff { .. }
+                if tag == "in2" =>
+            {
+                None
+            },
+            _ => Some(tag.into()),
+        }
+    }
+
+    fn ser_after<M: SerializeMap>(&self, tag: &str, map: &mut M) -> Result<(), M::Error> {
+        if tag != "out1" {
+            return Ok(());
+        }
+
+        // for some reason, in these nodes in2 is supposed to go after out1
+        // and it's left out if the channel is constant
+        match self {
+            ComponentType::CompositeReadNum { variable_channel, channel, .. }
+                if *channel == CompositeChannel::Variable
+                    && !skip_typedinputconnection(variable_channel) =>
+            {
+                map.serialize_entry("in2", variable_channel)
+            },
+            ComponentType::CompositeReadOnOff { variable_channel, channel, .. }
+                if *channel == CompositeChannel::Variable
+                    && !skip_typedinputconnection(variable_channel) =>
+            {
+                map.serialize_entry("in2", variable_channel)
+            },
+            _ => Ok(()),
+        }
+    }
+
+    fn de_tag<'t>(xml_type: &str, tag: &'t str, prev: Option<&str>) -> Cow<'t, str> {
+        match xml_type {
+            // see note on NumericalJunction
+            "21" if tag == "out1" && prev == Some("out1") => "out2".into(),
+            // see note on CompositeWriteNum/CompositeWriteOnOff
+            "40" | "41" => match tag {
+                "inc" => "in1".into(),
+                "inoff" => "in34".into(),
+                _ => match tag.strip_prefix("in").and_then(|n| n.parse::<u8>().ok()) {
+                    Some(n @ 1..=32) => format!("in{}", n + 1).into(),
+                    _ => tag.into(),
+                },
+            },
+            _ => tag.into(),
+        }
+    }
+}
+
+impl TagLayout for BridgeComponentType {
+    fn xml_type(&self) -> &'static str {
+        BridgeComponentType::xml_type(self)
+    }
+}
+
+/// A component with an id and position.
+pub(crate) trait XmlObject {
+    /// The component type.
+    type Layout: TagLayout;
+
+    /// Gets the id, position and type.
+    fn parts(&self) -> (u32, &PositionXY, &Self::Layout);
+}
+
+impl XmlObject for Component {
+    type Layout = ComponentType;
+
+    fn parts(&self) -> (u32, &PositionXY, &ComponentType) {
+        (self.id, &self.pos, &self.component)
+    }
+}
+
+impl XmlObject for BridgeComponent {
+    type Layout = BridgeComponentType;
+
+    fn parts(&self) -> (u32, &PositionXY, &BridgeComponentType) {
+        (self.id, &self.pos, &self.component)
+    }
+}
+
+/// Serializes a component as `<c type=".."><object id="..">..</object></c>`.
+pub(crate) fn serialize_component<S: Serializer, T: XmlObject>(
+    c: &T,
+    ser: S,
+) -> Result<S::Ok, S::Error> {
+    let mut map = ser.serialize_map(None)?;
+    let xml_type = c.parts().2.xml_type();
+    if xml_type != "0" {
+        map.serialize_entry("@type", xml_type)?;
+    }
+    map.serialize_entry("object", &Object { c, state: false })?;
+    map.end()
+}
+
+/// Serializes components as the `c0`, `c1`, etc. tags in `component_states`.
+pub(crate) fn serialize_states<S: Serializer, T: XmlObject>(
+    states: &[T],
+    ser: S,
+) -> Result<S::Ok, S::Error> {
+    ser.collect_map(
+        states
+            .iter()
+            .enumerate()
+            .map(|(i, c)| (format!("c{i}"), Object { c, state: true })),
+    )
+}
+
+/// The contents of an `object` tag, or of a `cN` tag in the component states.
+struct Object<'a, T> {
+    c: &'a T,
+    /// The states leave out an id of 0.
+    state: bool,
+}
+
+impl<T: XmlObject> Serialize for Object<'_, T> {
+    fn serialize<S: Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+        let (id, pos, component) = self.c.parts();
+
+        let mut map = ser.serialize_map(None)?;
+        if !(self.state && id == 0) {
+            map.serialize_entry("@id", &id)?;
+        }
+        if !is_default(pos) {
+            map.serialize_entry("pos", pos)?;
+        }
+        component.serialize(FieldSerializer { map: &mut map, component, outer: true })?;
+        map.end()
+    }
+}
+
+/// Serializes the derived form of a component type into the `object` map.
+///
+/// The derived form is a struct with the type and a struct of fields, the type is skipped
+/// (it goes on the `c` tag) and the fields are renamed with [`TagLayout::ser_tag`].
+struct FieldSerializer<'a, M, C> {
+    map: &'a mut M,
+    component: &'a C,
+    /// Whether this is the struct with the type, rather than the fields.
+    outer: bool,
+}
+
+macro_rules! unsupported {
+    ($($f:ident($($t:ty),*)),* $(,)?) => {
+        $(
+            fn $f(self, $(_: $t),*) -> Result<(), M::Error> {
+                Err(ser::Error::custom("expected a component"))
+            }
+        )*
+    };
+}
+
+impl<M: SerializeMap, C: TagLayout> Serializer for FieldSerializer<'_, M, C> {
+    type Ok = ();
+    type Error = M::Error;
+    type SerializeSeq = Impossible<(), M::Error>;
+    type SerializeTuple = Impossible<(), M::Error>;
+    type SerializeTupleStruct = Impossible<(), M::Error>;
+    type SerializeTupleVariant = Impossible<(), M::Error>;
+    type SerializeMap = Impossible<(), M::Error>;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Impossible<(), M::Error>;
+
+    unsupported! {
+        serialize_bool(bool), serialize_i8(i8), serialize_i16(i16), serialize_i32(i32),
+        serialize_i64(i64), serialize_u8(u8), serialize_u16(u16), serialize_u32(u32),
+        serialize_u64(u64), serialize_f32(f32), serialize_f64(f64), serialize_char(char),
+        serialize_str(&str), serialize_bytes(&[u8]), serialize_none(), serialize_unit(),
+        serialize_unit_struct(&'static str),
+        serialize_unit_variant(&'static str, u32, &'static str),
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, _: &T) -> Result<(), M::Error> {
+        Err(ser::Error::custom("expected a component"))
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        _: &T,
+    ) -> Result<(), M::Error> {
+        Err(ser::Error::custom("expected a component"))
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: &T,
+    ) -> Result<(), M::Error> {
+        Err(ser::Error::custom("expected a component"))
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self::SerializeSeq, M::Error> {
+        Err(ser::Error::custom("expected a component"))
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<Self::SerializeTuple, M::Error> {
+        Err(ser::Error::custom("expected a component"))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleStruct, M::Error> {
+        Err(ser::Error::custom("expected a component"))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleVariant, M::Error> {
+        Err(ser::Error::custom("expected a component"))
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap, M::Error> {
+        Err(ser::Error::custom("expected a component"))
+    }
+
+    fn serialize_struct(self, _: &'static str, _: usize) -> Result<Self, M::Error> {
+        Ok(self)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStructVariant, M::Error> {
+        Err(ser::Error::custom("expected a component"))
+    }
+}
+
+impl<M: SerializeMap, C: TagLayout> SerializeStruct for FieldSerializer<'_, M, C> {
+    type Ok = ();
+    type Error = M::Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), M::Error> {
+        if self.outer {
+            if key == "object" {
+                value.serialize(FieldSerializer {
+                    map: &mut *self.map,
+                    component: self.component,
+                    outer: false,
+                })?;
+            }
+            return Ok(());
+        }
+
+        if let Some(tag) = self.component.ser_tag(key) {
+            self.map.serialize_entry(tag.as_ref(), value)?;
+        }
+        self.component.ser_after(key, self.map)
+    }
+
+    fn end(self) -> Result<(), M::Error> {
+        Ok(())
+    }
+}
+
+/// Deserializes a component from `<c type=".."><object id="..">..</object></c>`.
+pub(crate) fn deserialize_component<'de, D: Deserializer<'de>, C: TagLayout>(
+    de: D,
+) -> Result<(u32, PositionXY, C), D::Error> {
+    de.deserialize_map(ComponentVisitor(PhantomData))
+}
+
+struct ComponentVisitor<C>(PhantomData<C>);
+
+impl<'de, C: TagLayout> Visitor<'de> for ComponentVisitor<C> {
+    type Value = (u32, PositionXY, C);
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("a component")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut xml_type = None;
+        let mut object = None;
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "@type" => xml_type = Some(map.next_value::<String>()?),
+                "object" => {
+                    object = Some(map.next_value_seed(ObjectSeed::<C> {
+                        xml_type: xml_type.as_deref().unwrap_or("0"),
+                        marker: PhantomData,
+                    })?);
+                },
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                },
+            }
+        }
+        object.ok_or_else(|| de::Error::missing_field("object"))
+    }
+}
+
+/// Id and position taken out of an `object` while its fields are deserialized.
+#[derive(Default)]
+struct Found {
+    id: Option<u32>,
+    pos: PositionXY,
+}
+
+struct ObjectSeed<'a, C> {
+    xml_type: &'a str,
+    marker: PhantomData<C>,
+}
+
+impl<'de, C: TagLayout> DeserializeSeed<'de> for ObjectSeed<'_, C> {
+    type Value = (u32, PositionXY, C);
+
+    fn deserialize<D: Deserializer<'de>>(self, de: D) -> Result<Self::Value, D::Error> {
+        let mut found = Found::default();
+        let component = C::deserialize(de::value::MapAccessDeserializer::new(Tagged {
+            xml_type: self.xml_type,
+            fields: Some(Fields::<D, C> {
+                de,
+                found: &mut found,
+                xml_type: self.xml_type,
+                marker: PhantomData,
+            }),
+            read: 0,
+        }));
+
+        let id = found.id;
+        component
+            .and_then(|c| {
+                Ok((
+                    id.ok_or_else(|| de::Error::missing_field("@id"))?,
+                    found.pos,
+                    c,
+                ))
+            })
+            .map_err(|e| ElementContext::component("object", id, e).into_de())
+    }
+}
+
+/// Presents an `object` in the derived form: a map with the type, then the fields.
+struct Tagged<'a, D, C> {
+    xml_type: &'a str,
+    fields: Option<Fields<'a, D, C>>,
+    /// Number of entries read so far.
+    read: u8,
+}
+
+impl<'de, D: Deserializer<'de>, C: TagLayout> MapAccess<'de> for Tagged<'_, D, C> {
+    type Error = D::Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, D::Error> {
+        let key = match self.read {
+            0 => "@type",
+            1 => "object",
+            _ => return Ok(None),
+        };
+        seed.deserialize(StrDeserializer::new(key)).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, D::Error> {
+        self.read += 1;
+        match self.fields.take() {
+            Some(fields) if self.read == 2 => seed.deserialize(fields),
+            fields => {
+                self.fields = fields;
+                seed.deserialize(StrDeserializer::new(self.xml_type))
+            },
+        }
+    }
+}
+
+/// Deserializer for the fields in an `object`, see [`FieldsMap`].
+struct Fields<'a, D, C> {
+    de: D,
+    found: &'a mut Found,
+    xml_type: &'a str,
+    marker: PhantomData<C>,
+}
+
+impl<'de, D: Deserializer<'de>, C: TagLayout> Deserializer<'de> for Fields<'_, D, C> {
+    type Error = D::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, D::Error> {
+        self.de.deserialize_map(FieldsVisitor::<V, C> {
+            visitor,
+            found: self.found,
+            xml_type: self.xml_type,
+            marker: PhantomData,
+        })
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, D::Error> {
+        self.de.deserialize_struct(
+            name,
+            fields,
+            FieldsVisitor::<V, C> {
+                visitor,
+                found: self.found,
+                xml_type: self.xml_type,
+                marker: PhantomData,
+            },
+        )
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map enum identifier ignored_any
+    }
+}
+
+struct FieldsVisitor<'a, V, C> {
+    visitor: V,
+    found: &'a mut Found,
+    xml_type: &'a str,
+    marker: PhantomData<C>,
+}
+
+impl<'de, V: Visitor<'de>, C: TagLayout> Visitor<'de> for FieldsVisitor<'_, V, C> {
+    type Value = V::Value;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        self.visitor.expecting(f)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<V::Value, A::Error> {
+        self.visitor.visit_map(FieldsMap::<A, C> {
+            map,
+            found: self.found,
+            xml_type: self.xml_type,
+            prev: None,
+            marker: PhantomData,
+        })
+    }
+}
+
+/// The entries of an `object`, without the id and position and with tags renamed by
+/// [`TagLayout::de_tag`].
+struct FieldsMap<'a, A, C> {
+    map: A,
+    found: &'a mut Found,
+    xml_type: &'a str,
+    prev: Option<String>,
+    marker: PhantomData<C>,
+}
+
+impl<'de, A: MapAccess<'de>, C: TagLayout> MapAccess<'de> for FieldsMap<'_, A, C> {
+    type Error = A::Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, A::Error> {
+        while let Some(key) = self.map.next_key::<String>()? {
+            match key.as_str() {
+                "@id" => {
+                    let id = self.map.next_value::<String>()?;
+                    self.found.id = Some(
+                        id.parse()
+                            .map_err(|e| de::Error::custom(format!("can't parse {id:?}: {e}")))?,
+                    );
+                },
+                "pos" => self.found.pos = self.map.next_value()?,
+                _ => {
+                    let tag = C::de_tag(self.xml_type, &key, self.prev.as_deref());
+                    let field = seed.deserialize(StrDeserializer::new(&tag))?;
+                    self.prev = Some(key);
+                    return Ok(Some(field));
+                },
+            }
+        }
+        Ok(None)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, A::Error> {
+        self.map.next_value_seed(seed)
+    }
+}
@@ -0,0 +1,345 @@
+//! Module containing an analysis of which composite channels a [`Microcontroller`] writes and reads.
+//!
+//! Composite writes and reads are followed along the composite wires between them, channels set
+//! from a node ([`CompositeChannel::Variable`]) are treated as "could be any channel".
+
+use std::collections::{HashMap, HashSet};
+
+use crate::util::AnyComponentRef;
+
+use super::{
+    components::{BridgeComponentType, ComponentConnection, ComponentType, CompositeChannel},
+    graph::Graph,
+    types::Type,
+    Microcontroller,
+};
+
+/// A composite channel written or read by a component.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChannelAccess {
+    /// Id of the composite write/read component.
+    pub component_id: u32,
+    /// [`Type::Number`] or [`Type::OnOff`].
+    pub typ: Type,
+    /// The channel, [`CompositeChannel::Variable`] if it's set from a node.
+    pub channel: CompositeChannel,
+}
+
+impl ChannelAccess {
+    /// Whether `self` and `other` can be the same channel.
+    fn overlaps(self, other: ChannelAccess) -> bool {
+        self.typ == other.typ
+            && (self.channel == other.channel
+                || self.channel == CompositeChannel::Variable
+                || other.channel == CompositeChannel::Variable)
+    }
+
+    /// Whether `self` and `other` are definitely the same channel.
+    fn same(self, other: ChannelAccess) -> bool {
+        self.typ == other.typ
+            && self.channel == other.channel
+            && self.channel != CompositeChannel::Variable
+    }
+}
+
+impl std::fmt::Display for ChannelAccess {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let typ = if self.typ == Type::OnOff {
+            "on/off"
+        } else {
+            "number"
+        };
+        match self.channel.number() {
+            Some(n) => write!(f, "{typ} channel {n} ({})", self.component_id),
+            None => write!(f, "variable {typ} channel ({})", self.component_id),
+        }
+    }
+}
+
+/// Channel usage of a single composite wire, see [`Microcontroller::composite_usage`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompositeWire {
+    /// The output driving the wire.
+    pub source: ComponentConnection,
+    /// Whether the wire can carry channels written somewhere the analysis can't see, like an
+    /// input node, Lua script or Number to Composite Binary.
+    pub external: bool,
+    /// The channels written before reaching this wire, in the order they're written.
+    pub written: Vec<ChannelAccess>,
+    /// The channels read directly from this wire.
+    pub read: Vec<ChannelAccess>,
+}
+
+/// A likely mistake found by [`Microcontroller::composite_usage`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum CompositeIssue {
+    /// A fixed channel is read, but nothing before it writes that channel.
+    ReadNotWritten(ChannelAccess),
+    /// A channel is written, but nothing after it reads that channel.
+    ///
+    /// Writes that reach an output node, Lua script or other component that uses the whole
+    /// composite signal count as read.
+    WrittenNotRead(ChannelAccess),
+    /// A channel written by `first` is written again by `second` further down the wire.
+    WrittenTwice {
+        #[allow(missing_docs)]
+        first: ChannelAccess,
+        #[allow(missing_docs)]
+        second: ChannelAccess,
+    },
+}
+
+impl std::fmt::Display for CompositeIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CompositeIssue::ReadNotWritten(a) => write!(f, "{a} is read but never written"),
+            CompositeIssue::WrittenNotRead(a) => write!(f, "{a} is written but never read"),
+            CompositeIssue::WrittenTwice { first, second } => {
+                write!(f, "{first} is written again by {}", second.component_id)
+            },
+        }
+    }
+}
+
+/// Result of [`Microcontroller::composite_usage`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompositeUsage {
+    /// Every composite wire, in the order of [`Microcontroller::components`].
+    pub wires: Vec<CompositeWire>,
+    /// Issues, grouped by the component they're about in the order of [`Microcontroller::components`].
+    pub issues: Vec<CompositeIssue>,
+}
+
+impl CompositeUsage {
+    /// Gets the wire driven by the given output.
+    #[must_use]
+    pub fn wire(&self, source: &ComponentConnection) -> Option<&CompositeWire> {
+        self.wires.iter().find(|w| w.source == *source)
+    }
+}
+
+/// Gets the channels written by a composite write component.
+fn writes(id: u32, c: &ComponentType) -> Vec<ChannelAccess> {
+    let span = |typ: Type, count: u8, offset: CompositeChannel| match offset {
+        CompositeChannel::Variable => {
+            vec![ChannelAccess { component_id: id, typ, channel: offset }]
+        },
+        CompositeChannel::Fixed(start) => (start..start.saturating_add(count).min(32))
+            .map(|ch| ChannelAccess {
+                component_id: id,
+                typ,
+                channel: CompositeChannel::Fixed(ch),
+            })
+            .collect(),
+    };
+    let single = |typ: Type, ch: u8| {
+        vec![ChannelAccess {
+            component_id: id,
+            typ,
+            channel: CompositeChannel::Fixed(ch),
+        }]
+    };
+
+    match c {
+        ComponentType::CompositeWriteNum { count, offset, .. } => {
+            span(Type::Number, *count, *offset)
+        },
+        ComponentType::CompositeWriteOnOff { count, offset, .. } => {
+            span(Type::OnOff, *count, *offset)
+        },
+        ComponentType::_OldCompositeWriteNum { channel, .. } => single(Type::Number, *channel),
+        ComponentType::_OldCompositeWriteOnOff { channel, .. } => single(Type::OnOff, *channel),
+        _ => vec![],
+    }
+}
+
+/// Gets the channel read by a composite read component.
+fn read(id: u32, c: &ComponentType) -> Option<ChannelAccess> {
+    let (typ, channel) = match c {
+        ComponentType::CompositeReadNum { channel, .. } => (Type::Number, *channel),
+        ComponentType::CompositeReadOnOff { channel, .. } => (Type::OnOff, *channel),
+        _ => return None,
+    };
+    Some(ChannelAccess { component_id: id, typ, channel })
+}
+
+fn is_writer(c: &ComponentType) -> bool {
+    matches!(
+        c,
+        ComponentType::CompositeWriteNum { .. }
+            | ComponentType::CompositeWriteOnOff { .. }
+            | ComponentType::_OldCompositeWriteNum { .. }
+            | ComponentType::_OldCompositeWriteOnOff { .. }
+    )
+}
+
+struct Analysis<'a, 'g> {
+    graph: &'g Graph<'a>,
+    /// (component id, output index) -> (external, written)
+    upstream: HashMap<(u32, u8), (bool, Vec<ChannelAccess>)>,
+    visiting: HashSet<(u32, u8)>,
+}
+
+impl Analysis<'_, '_> {
+    /// Gets the wire connected to an input, if it's connected to a component that exists.
+    fn source(&self, conn: Option<&ComponentConnection>) -> Option<ComponentConnection> {
+        conn.filter(|conn| self.graph.get(conn.component_id).is_some())
+            .cloned()
+    }
+
+    /// Gets whether the wire driven by `output` is external, and the channels written on it.
+    fn written(&mut self, output: &ComponentConnection) -> (bool, Vec<ChannelAccess>) {
+        let key = (output.component_id, output.node_index);
+        if let Some(w) = self.upstream.get(&key) {
+            return w.clone();
+        }
+        if !self.visiting.insert(key) {
+            // a loop, whatever comes around it isn't known yet
+            return (true, vec![]);
+        }
+
+        let w = match self.graph.get(output.component_id) {
+            Some(AnyComponentRef::Component(c)) if is_writer(&c.component) => {
+                let inputs = c.component.inputs();
+                let (external, mut written) = match self.source(inputs[0].as_ref()) {
+                    Some(src) => self.written(&src),
+                    None => (false, vec![]),
+                };
+                written.extend(writes(c.id, &c.component));
+                (external, written)
+            },
+            Some(AnyComponentRef::Component(c))
+                if matches!(c.component, ComponentType::CompositeSwitchbox { .. }) =>
+            {
+                let inputs = c.component.inputs();
+                let (mut external, mut written) = (false, vec![]);
+                for conn in &inputs[..2] {
+                    if let Some(src) = self.source(conn.as_ref()) {
+                        let (e, w) = self.written(&src);
+                        external |= e;
+                        for a in w {
+                            if !written.contains(&a) {
+                                written.push(a);
+                            }
+                        }
+                    }
+                }
+                (external, written)
+            },
+            _ => (true, vec![]),
+        };
+
+        self.visiting.remove(&key);
+        self.upstream.insert(key, w.clone());
+        w
+    }
+
+    /// Gets whether `access` is (possibly) read after the wire driven by `output`.
+    fn is_read(
+        &self,
+        output: &ComponentConnection,
+        access: ChannelAccess,
+        seen: &mut HashSet<(u32, u8)>,
+    ) -> bool {
+        if !seen.insert((output.component_id, output.node_index)) {
+            return false;
+        }
+
+        self.graph.consumers(output).iter().any(|consumer| {
+            let next = ComponentConnection { component_id: consumer.component_id, node_index: 0 };
+            match self.graph.get(consumer.component_id) {
+                Some(AnyComponentRef::Component(c)) => {
+                    if let Some(r) = read(c.id, &c.component) {
+                        r.overlaps(access)
+                    } else if is_writer(&c.component) {
+                        let overwritten = writes(c.id, &c.component).iter().any(|w| w.same(access));
+                        !overwritten && self.is_read(&next, access, seen)
+                    } else if matches!(c.component, ComponentType::CompositeSwitchbox { .. }) {
+                        self.is_read(&next, access, seen)
+                    } else {
+                        // uses the whole signal
+                        true
+                    }
+                },
+                Some(AnyComponentRef::BridgeComponent(_)) => true,
+                None => false,
+            }
+        })
+    }
+}
+
+impl Microcontroller {
+    /// Works out which composite channels are written and read on each composite wire, and finds
+    /// channels that are read but never written, written but never read, or written twice.
+    ///
+    /// Only looks at the main group, see [`groups`][`Self::groups`].
+    #[must_use]
+    pub fn composite_usage(&self) -> CompositeUsage {
+        let graph = self.graph();
+        let mut analysis = Analysis {
+            graph: &graph,
+            upstream: HashMap::new(),
+            visiting: HashSet::new(),
+        };
+
+        let mut wires = vec![];
+        for c in self.components() {
+            if let AnyComponentRef::BridgeComponent(bc) = &c {
+                if matches!(bc.component, BridgeComponentType::CompositeOut { .. }) {
+                    // the output of an output node isn't a real wire
+                    continue;
+                }
+            }
+            for (i, typ) in c.io_def().outputs.into_iter().enumerate() {
+                if typ == Type::Composite {
+                    #[allow(clippy::cast_possible_truncation)]
+                    let source = ComponentConnection { component_id: c.id(), node_index: i as u8 };
+                    let (external, written) = analysis.written(&source);
+                    wires.push(CompositeWire { source, external, written, read: vec![] });
+                }
+            }
+        }
+
+        let mut issues = vec![];
+        for c in &self.components {
+            let inputs = c.component.inputs();
+            if let Some(r) = read(c.id, &c.component) {
+                let wire = analysis
+                    .source(inputs[0].as_ref())
+                    .and_then(|src| wires.iter_mut().find(|w| w.source == src));
+                let written = match wire {
+                    Some(wire) => {
+                        wire.read.push(r);
+                        wire.external || wire.written.iter().any(|w| w.overlaps(r))
+                    },
+                    None => false,
+                };
+                if !written && r.channel != CompositeChannel::Variable {
+                    issues.push(CompositeIssue::ReadNotWritten(r));
+                }
+            } else if is_writer(&c.component) {
+                let own = writes(c.id, &c.component);
+                if let Some(src) = analysis.source(inputs[0].as_ref()) {
+                    let (_, before) = analysis.written(&src);
+                    for second in &own {
+                        for first in before.iter().filter(|w| w.same(*second)) {
+                            issues.push(CompositeIssue::WrittenTwice {
+                                first: *first,
+                                second: *second,
+                            });
+                        }
+                    }
+                }
+
+                let output = ComponentConnection { component_id: c.id, node_index: 0 };
+                for w in own {
+                    if !analysis.is_read(&output, w, &mut HashSet::new()) {
+                        issues.push(CompositeIssue::WrittenNotRead(w));
+                    }
+                }
+            }
+        }
+
+        CompositeUsage { wires, issues }
+    }
+}
@@ -0,0 +1,469 @@
+//! Module containing a semantic diff between two [`Microcontroller`]s.
+//!
+//! Components are matched structurally (by type, settings and what they're wired to) instead of by id,
+//! so re-saving a microcontroller with renumbered ids doesn't produce any changes.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write,
+};
+
+use crate::util::{serde_utils::PositionXY, AnyComponentRef};
+
+use super::{components::ComponentConnection, Microcontroller};
+
+/// Options for [`diff_with`].
+#[derive(Clone, Debug, Default)]
+pub struct DiffOptions {
+    /// Don't report components, IO bridges or IO nodes that only moved.
+    pub ignore_positions: bool,
+}
+
+/// A single difference between two [`Microcontroller`]s.
+///
+/// `old_id`s refer to the first microcontroller, `new_id`s to the second.
+#[allow(missing_docs)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change {
+    Name {
+        old: String,
+        new: String,
+    },
+    Description {
+        old: String,
+        new: String,
+    },
+    Size {
+        old: (u8, u8),
+        new: (u8, u8),
+    },
+    Icon,
+    IONodeAdded {
+        node_id: u32,
+        label: String,
+    },
+    IONodeRemoved {
+        node_id: u32,
+        label: String,
+    },
+    IONodeChanged {
+        old_id: u32,
+        new_id: u32,
+        field: &'static str,
+        old: String,
+        new: String,
+    },
+    ComponentAdded {
+        id: u32,
+        name: &'static str,
+    },
+    ComponentRemoved {
+        id: u32,
+        name: &'static str,
+    },
+    SettingChanged {
+        old_id: u32,
+        new_id: u32,
+        setting: &'static str,
+        old: String,
+        new: String,
+    },
+    Rewired {
+        old_id: u32,
+        new_id: u32,
+        input: &'static str,
+        old: Option<ComponentConnection>,
+        new: Option<ComponentConnection>,
+    },
+    Moved {
+        old_id: u32,
+        new_id: u32,
+        old: PositionXY,
+        new: PositionXY,
+    },
+}
+
+impl std::fmt::Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let conn = |c: &Option<ComponentConnection>| {
+            c.as_ref().map_or("(none)".into(), |c| {
+                format!("{}:{}", c.component_id, c.node_index)
+            })
+        };
+        match self {
+            Change::Name { old, new } => write!(f, "name: {old:?} -> {new:?}"),
+            Change::Description { old, new } => write!(f, "description: {old:?} -> {new:?}"),
+            Change::Size { old, new } => {
+                write!(f, "size: {}x{} -> {}x{}", old.0, old.1, new.0, new.1)
+            },
+            Change::Icon => write!(f, "icon changed"),
+            Change::IONodeAdded { node_id, label } => write!(f, "+ node {node_id} {label:?}"),
+            Change::IONodeRemoved { node_id, label } => write!(f, "- node {node_id} {label:?}"),
+            Change::IONodeChanged { old_id, new_id, field, old, new } => {
+                write!(f, "~ node {old_id}->{new_id} {field}: {old} -> {new}")
+            },
+            Change::ComponentAdded { id, name } => write!(f, "+ component {id} ({name})"),
+            Change::ComponentRemoved { id, name } => write!(f, "- component {id} ({name})"),
+            Change::SettingChanged { old_id, new_id, setting, old, new } => {
+                write!(
+                    f,
+                    "~ component {old_id}->{new_id} {setting}: {old} -> {new}"
+                )
+            },
+            Change::Rewired { old_id, new_id, input, old, new } => write!(
+                f,
+                "~ component {old_id}->{new_id} {input}: {} -> {}",
+                conn(old),
+                conn(new)
+            ),
+            Change::Moved { old_id, new_id, old, new } => write!(
+                f,
+                "~ component {old_id}->{new_id} moved: ({}, {}) -> ({}, {})",
+                old.x, old.y, new.x, new.y
+            ),
+        }
+    }
+}
+
+/// Result of [`diff`].
+#[derive(Clone, Debug, Default)]
+pub struct MCDiff {
+    /// All differences found.
+    pub changes: Vec<Change>,
+    /// Matched component ids (including IO bridges), from the first microcontroller to the second.
+    pub matches: HashMap<u32, u32>,
+}
+
+impl MCDiff {
+    /// Returns `true` if there were no differences.
+    #[allow(clippy::must_use_candidate)]
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+impl std::fmt::Display for MCDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for c in &self.changes {
+            writeln!(f, "{c}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Compares two microcontrollers using the default [`DiffOptions`].
+#[must_use]
+pub fn diff(a: &Microcontroller, b: &Microcontroller) -> MCDiff {
+    diff_with(a, b, &DiffOptions::default())
+}
+
+/// Compares two microcontrollers.
+#[must_use]
+pub fn diff_with(a: &Microcontroller, b: &Microcontroller, options: &DiffOptions) -> MCDiff {
+    let mut changes = vec![];
+
+    if a.name != b.name {
+        changes.push(Change::Name { old: a.name.clone(), new: b.name.clone() });
+    }
+    if a.description != b.description {
+        changes.push(Change::Description {
+            old: a.description.clone(),
+            new: b.description.clone(),
+        });
+    }
+    if (a.width, a.length) != (b.width, b.length) {
+        changes.push(Change::Size { old: (a.width, a.length), new: (b.width, b.length) });
+    }
+    if a.icon != b.icon {
+        changes.push(Change::Icon);
+    }
+
+    let io_pairs = match_io(a, b);
+    let mut matches: HashMap<u32, u32> = io_pairs
+        .iter()
+        .map(|&(ia, ib)| (a.io[ia].logic.id, b.io[ib].logic.id))
+        .collect();
+
+    for (i, ion) in a.io.iter().enumerate() {
+        if !io_pairs.iter().any(|&(ia, _)| ia == i) {
+            changes.push(Change::IONodeRemoved {
+                node_id: ion.design.node_id,
+                label: ion.design.label.clone(),
+            });
+        }
+    }
+    for (i, ion) in b.io.iter().enumerate() {
+        if !io_pairs.iter().any(|&(_, ib)| ib == i) {
+            changes.push(Change::IONodeAdded {
+                node_id: ion.design.node_id,
+                label: ion.design.label.clone(),
+            });
+        }
+    }
+    diff_io(a, b, &io_pairs, options, &mut changes);
+
+    match_components(a, b, &mut matches);
+    let reverse: HashSet<u32> = matches.values().copied().collect();
+
+    for c in &a.components {
+        if !matches.contains_key(&c.id) {
+            changes.push(Change::ComponentRemoved { id: c.id, name: c.component.name() });
+        }
+    }
+    for c in &b.components {
+        if !reverse.contains(&c.id) {
+            changes.push(Change::ComponentAdded { id: c.id, name: c.component.name() });
+        }
+    }
+
+    for ca in a.components() {
+        if let Some(cb) = matches.get(&ca.id()).and_then(|&id| b.get_component(id)) {
+            diff_matched(&ca, &cb, &matches, options, &mut changes);
+        }
+    }
+
+    MCDiff { changes, matches }
+}
+
+/// Compares the design of matched IO nodes.
+fn diff_io(
+    a: &Microcontroller,
+    b: &Microcontroller,
+    io_pairs: &[(usize, usize)],
+    options: &DiffOptions,
+    changes: &mut Vec<Change>,
+) {
+    for &(ia, ib) in io_pairs {
+        let (da, db) = (&a.io[ia].design, &b.io[ib].design);
+        let mut fields = vec![
+            (
+                "label",
+                format!("{:?}", da.label),
+                format!("{:?}", db.label),
+            ),
+            (
+                "description",
+                format!("{:?}", da.description),
+                format!("{:?}", db.description),
+            ),
+            ("type", format!("{:?}", da.typ), format!("{:?}", db.typ)),
+            ("mode", format!("{:?}", da.mode), format!("{:?}", db.mode)),
+        ];
+        if !options.ignore_positions {
+            fields.push((
+                "position",
+                format!("({}, {})", da.position.x, da.position.y),
+                format!("({}, {})", db.position.x, db.position.y),
+            ));
+        }
+        for (field, old, new) in fields {
+            if old != new {
+                changes.push(Change::IONodeChanged {
+                    old_id: da.node_id,
+                    new_id: db.node_id,
+                    field,
+                    old,
+                    new,
+                });
+            }
+        }
+    }
+}
+
+/// Compares the settings, wiring and position of two matched components.
+fn diff_matched(
+    ca: &AnyComponentRef,
+    cb: &AnyComponentRef,
+    matches: &HashMap<u32, u32>,
+    options: &DiffOptions,
+    changes: &mut Vec<Change>,
+) {
+    if let (AnyComponentRef::Component(x), AnyComponentRef::Component(y)) = (ca, cb) {
+        for ((setting, old), (_, new)) in x
+            .component
+            .settings()
+            .into_iter()
+            .zip(y.component.settings())
+        {
+            if !setting.starts_with("__") && old != new {
+                changes.push(Change::SettingChanged {
+                    old_id: x.id,
+                    new_id: y.id,
+                    setting,
+                    old,
+                    new,
+                });
+            }
+        }
+    }
+
+    let names = cb.io_def().input_names;
+    for (i, (old, new)) in ca.inputs().into_iter().zip(cb.inputs()).enumerate() {
+        let mapped = old.as_ref().map(|c| {
+            matches
+                .get(&c.component_id)
+                .map(|&id| ComponentConnection { component_id: id, node_index: c.node_index })
+        });
+        // an input from an unmatched component is always a change
+        let same = match (mapped, new) {
+            (None, None) => true,
+            (Some(Some(m)), Some(n)) => m == *n,
+            _ => false,
+        };
+        if !same {
+            changes.push(Change::Rewired {
+                old_id: ca.id(),
+                new_id: cb.id(),
+                input: names[i],
+                old: old.clone(),
+                new: new.clone(),
+            });
+        }
+    }
+
+    if !options.ignore_positions && ca.pos() != cb.pos() {
+        changes.push(Change::Moved {
+            old_id: ca.id(),
+            new_id: cb.id(),
+            old: ca.pos().clone(),
+            new: cb.pos().clone(),
+        });
+    }
+}
+
+/// Pairs up IO nodes (as indices into `io`) by label, then position, then order.
+fn match_io(a: &Microcontroller, b: &Microcontroller) -> Vec<(usize, usize)> {
+    type Key<'a> = Box<dyn Fn(&super::IONode) -> String + 'a>;
+
+    let mut pairs = vec![];
+    let mut used_a = HashSet::new();
+    let mut used_b = HashSet::new();
+
+    let keys: [Key; 3] = [
+        Box::new(|ion| format!("{:?}{}", ion.design.mode, ion.design.label)),
+        Box::new(|ion| {
+            format!(
+                "{:?}{:?}{},{}",
+                ion.design.mode, ion.design.typ, ion.design.position.x, ion.design.position.y
+            )
+        }),
+        Box::new(|ion| format!("{:?}{:?}", ion.design.mode, ion.design.typ)),
+    ];
+
+    for key in keys {
+        for (ia, ion_a) in a.io.iter().enumerate() {
+            if used_a.contains(&ia) {
+                continue;
+            }
+            let ka = key(ion_a);
+            let found =
+                b.io.iter()
+                    .enumerate()
+                    .find(|(ib, ion_b)| !used_b.contains(ib) && key(ion_b) == ka);
+            if let Some((ib, _)) = found {
+                used_a.insert(ia);
+                used_b.insert(ib);
+                pairs.push((ia, ib));
+            }
+        }
+    }
+
+    pairs.sort_unstable();
+    pairs
+}
+
+/// Matches the (non-bridge) components, extending `matches`.
+fn match_components(a: &Microcontroller, b: &Microcontroller, matches: &mut HashMap<u32, u32>) {
+    fn signature(c: &super::components::Component) -> String {
+        let mut sig = c.component.name().to_string();
+        for (name, value) in c.component.settings() {
+            if !name.starts_with("__") {
+                let _ = write!(sig, ";{name}={value}");
+            }
+        }
+        sig
+    }
+
+    fn inputs(c: &super::components::Component, id_of: impl Fn(u32) -> Option<u32>) -> String {
+        c.component
+            .inputs()
+            .iter()
+            .map(|conn| match conn {
+                None => "-".to_string(),
+                Some(conn) => id_of(conn.component_id)
+                    .map_or("?".into(), |id| format!("{id}:{}", conn.node_index)),
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    #[derive(Clone, Copy)]
+    enum Key {
+        Full,
+        Settings,
+        Wiring,
+        Position,
+        Type,
+    }
+
+    // the ids of b are used as the common id space
+    let key = |k: Key, c: &super::components::Component, id_of: &dyn Fn(u32) -> Option<u32>| {
+        let name = c.component.name();
+        match k {
+            Key::Full => format!("{}|{}", signature(c), inputs(c, id_of)),
+            Key::Settings => signature(c),
+            Key::Wiring => format!("{name}|{}", inputs(c, id_of)),
+            Key::Position => format!("{name}|{},{}", c.pos.x, c.pos.y),
+            Key::Type => name.to_string(),
+        }
+    };
+
+    loop {
+        let mut progress = false;
+        // groups with several components on a side are only paired up (in id order) as a last
+        // resort, once no key gives a unique match
+        'keys: for ambiguous in [false, true] {
+            for k in [
+                Key::Full,
+                Key::Settings,
+                Key::Wiring,
+                Key::Position,
+                Key::Type,
+            ] {
+                let reverse: HashSet<u32> = matches.values().copied().collect();
+                let id_of_a = |id: u32| matches.get(&id).copied();
+                let id_of_b = |id: u32| reverse.contains(&id).then_some(id);
+
+                let mut groups: HashMap<String, (Vec<u32>, Vec<u32>)> = HashMap::new();
+                for c in a.components.iter().filter(|c| !matches.contains_key(&c.id)) {
+                    groups.entry(key(k, c, &id_of_a)).or_default().0.push(c.id);
+                }
+                for c in b.components.iter().filter(|c| !reverse.contains(&c.id)) {
+                    groups.entry(key(k, c, &id_of_b)).or_default().1.push(c.id);
+                }
+
+                let mut found: Vec<(u32, u32)> = groups
+                    .into_values()
+                    .filter(|(ga, gb)| ambiguous || (ga.len() == 1 && gb.len() == 1))
+                    .flat_map(|(mut ga, mut gb)| {
+                        ga.sort_unstable();
+                        gb.sort_unstable();
+                        ga.into_iter().zip(gb)
+                    })
+                    .collect();
+
+                if !found.is_empty() {
+                    found.sort_unstable();
+                    matches.extend(found);
+                    progress = true;
+                    // retry the stricter keys now that more inputs can be resolved
+                    break 'keys;
+                }
+            }
+        }
+
+        if !progress {
+            break;
+        }
+    }
+}
@@ -0,0 +1,188 @@
+//! Module containing [Graphviz](https://graphviz.org/) DOT export for [`Microcontroller`] logic.
+
+use std::fmt::Write;
+
+use crate::util::AnyComponentRef;
+
+use super::{
+    components::{ComponentType, CompositeChannel, TextValue},
+    mc_serde::microcontroller::IONodeType,
+    types::Type,
+    Microcontroller,
+};
+
+impl Microcontroller {
+    /// Renders the logic of this microcontroller as a Graphviz DOT graph.
+    ///
+    /// Every [`Component`][super::components::Component] and
+    /// [`BridgeComponent`][super::components::BridgeComponent] becomes a node with one port per
+    /// input/output (named after the field), edges are coloured by [`Type`] and the IO nodes are
+    /// grouped into an inputs and an outputs cluster.
+    #[must_use]
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+
+        // writing to a String can't fail
+        let _ = writeln!(dot, "digraph \"{}\" {{", escape_str(&self.name));
+        let _ = writeln!(dot, "\trankdir=LR;");
+        let _ = writeln!(dot, "\tnode [shape=record, fontname=\"monospace\"];");
+
+        for (cluster, mode) in [
+            ("inputs", IONodeType::Input),
+            ("outputs", IONodeType::Output),
+        ] {
+            let _ = writeln!(dot, "\tsubgraph cluster_{cluster} {{");
+            let _ = writeln!(dot, "\t\tlabel=\"{cluster}\";");
+            for ion in self.io.iter().filter(|ion| ion.design.mode == mode) {
+                let title = format!("{} ({:?})", ion.design.label, ion.design.typ);
+                let c = AnyComponentRef::BridgeComponent(&ion.logic);
+                let _ = writeln!(dot, "\t\t{}", node(&c, &title, &[]));
+            }
+            let _ = writeln!(dot, "\t}}");
+        }
+
+        for c in &self.components {
+            let settings = key_settings(&c.component);
+            let _ = writeln!(
+                dot,
+                "\t{}",
+                node(
+                    &AnyComponentRef::Component(c),
+                    c.component.name(),
+                    &settings
+                )
+            );
+        }
+
+        for c in self.components() {
+            let def = c.io_def();
+            for (i, conn) in c.inputs().into_iter().enumerate() {
+                let Some(conn) = conn else { continue };
+                let Some(src) = self.get_component(conn.component_id) else {
+                    continue;
+                };
+                let src_def = src.io_def();
+                let Some(src_port) = src_def.output_names.get(conn.node_index as usize) else {
+                    continue;
+                };
+
+                let _ = writeln!(
+                    dot,
+                    "\tc{}:{src_port}:e -> c{}:{}:w [color=\"{}\"];",
+                    conn.component_id,
+                    c.id(),
+                    def.input_names[i],
+                    type_color(def.inputs[i]),
+                );
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+fn node(c: &AnyComponentRef, title: &str, settings: &[String]) -> String {
+    let def = c.io_def();
+    let ports = |names: &[&str]| {
+        names
+            .iter()
+            .map(|n| format!("<{n}> {n}"))
+            .collect::<Vec<_>>()
+            .join("|")
+    };
+
+    let mut body = escape_record(title);
+    for s in settings {
+        body.push_str("\\n");
+        body.push_str(&escape_record(s));
+    }
+
+    format!(
+        "c{} [label=\"{{{{{}}}|{body}|{{{}}}}}\"];",
+        c.id(),
+        ports(&def.input_names),
+        ports(&def.output_names),
+    )
+}
+
+/// Summarizes the settings that matter for reading the logic.
+fn key_settings(component: &ComponentType) -> Vec<String> {
+    let tv = |name: &str, v: &TextValue| format!("{name}={}", v.text());
+    match component {
+        ComponentType::ConstantNum { n, .. } => vec![n.text().to_string()],
+        ComponentType::Func1n { expr, .. }
+        | ComponentType::Func3n { expr, .. }
+        | ComponentType::Func8n { expr, .. }
+        | ComponentType::Func4b { expr, .. }
+        | ComponentType::Func8b { expr, .. } => vec![expr.clone()],
+        ComponentType::Clamp { min, max, .. } | ComponentType::Threshold { min, max, .. } => {
+            vec![tv("min", min), tv("max", max)]
+        },
+        ComponentType::MemoryRegister { reset_value, .. } => vec![tv("reset", reset_value)],
+        ComponentType::Equal { epsilon, .. } => vec![tv("epsilon", epsilon)],
+        ComponentType::PIDController { kp, ki, kd, .. } => {
+            vec![tv("kp", kp), tv("ki", ki), tv("kd", kd)]
+        },
+        ComponentType::UpDownCounter { increment, min, max, .. } => {
+            vec![tv("step", increment), tv("min", min), tv("max", max)]
+        },
+        ComponentType::PropertySlider { name, .. }
+        | ComponentType::PropertyDropdown { name, .. }
+        | ComponentType::PropertyToggle { name, .. }
+        | ComponentType::PropertyText { name, .. } => vec![format!("\"{name}\"")],
+        ComponentType::PropertyNumber { name, value, .. } => {
+            vec![format!("\"{name}\""), value.text().to_string()]
+        },
+        ComponentType::TooltipNum { label, .. } | ComponentType::TooltipOnOff { label, .. } => {
+            vec![format!("\"{label}\"")]
+        },
+        ComponentType::CompositeReadNum { channel, .. }
+        | ComponentType::CompositeReadOnOff { channel, .. } => vec![channel_setting(*channel)],
+        ComponentType::CompositeWriteNum { count, offset, .. }
+        | ComponentType::CompositeWriteOnOff { count, offset, .. } => {
+            vec![format!("count={count}"), channel_setting(*offset)]
+        },
+        _ => vec![],
+    }
+}
+
+fn channel_setting(channel: CompositeChannel) -> String {
+    match channel.number() {
+        Some(n) => format!("channel={n}"),
+        None => "channel=variable".into(),
+    }
+}
+
+fn type_color(typ: Type) -> &'static str {
+    match typ {
+        Type::OnOff => "#2e9e3e",
+        Type::Number => "#2e6fd4",
+        Type::Composite => "#8e44ad",
+        Type::Video => "#e67e22",
+        Type::Audio => "#c0392b",
+        _ => "#7f8c8d",
+    }
+}
+
+fn escape_str(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Escapes text for use inside a record label.
+fn escape_record(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for ch in s.chars() {
+        match ch {
+            '\n' => out.push_str("\\n"),
+            '{' | '}' | '|' | '<' | '>' | '"' | '\\' => {
+                out.push('\\');
+                out.push(ch);
+            },
+            _ => out.push(ch),
+        }
+    }
+    out
+}
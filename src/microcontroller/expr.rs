@@ -0,0 +1,630 @@
+//! Module containing a parser and evaluator for function node expressions.
+//!
+//! Used by [`ComponentType::Func1n`], [`ComponentType::Func3n`], [`ComponentType::Func8n`],
+//! [`ComponentType::Func4b`], and [`ComponentType::Func8b`].
+
+use thiserror::Error;
+
+use super::components::ComponentType;
+
+/// How deeply an expression can be nested (including chained operators) before it's rejected.
+pub const MAX_DEPTH: usize = 256;
+
+/// Names of the variables available in function nodes, in input order.
+pub const VARIABLES: [&str; 8] = ["x", "y", "z", "w", "a", "b", "c", "d"];
+
+/// Whether an expression works on numbers or on/off values.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ExprKind {
+    /// Number expression (`Func1n`, `Func3n`, `Func8n`).
+    Number,
+    /// On/Off expression (`Func4b`, `Func8b`).
+    OnOff,
+}
+
+/// Unary operators.
+#[allow(missing_docs)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum UnaryOp {
+    /// `-x`
+    Neg,
+    /// `!x`
+    Not,
+}
+
+/// Binary operators.
+#[allow(missing_docs)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BinaryOp {
+    /// `x + y`
+    Add,
+    /// `x - y`
+    Sub,
+    /// `x * y`
+    Mul,
+    /// `x / y`
+    Div,
+    /// `x % y`
+    Mod,
+    /// `x ^ y` in number expressions
+    Pow,
+    /// `x & y`
+    And,
+    /// `x | y`
+    Or,
+    /// `x ^ y` in on/off expressions
+    Xor,
+}
+
+macro_rules! functions {
+    ($($x:ident = $name:literal($arity:literal)),*$(,)?) => {
+        /// Functions available in number expressions.
+        #[allow(missing_docs)]
+        #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+        pub enum Function {
+            $($x,)*
+        }
+
+        impl Function {
+            /// Looks up a [`Function`] by name.
+            #[must_use]
+            pub fn from_name(name: &str) -> Option<Self> {
+                match name {
+                    $($name => Some(Self::$x),)*
+                    _ => None,
+                }
+            }
+
+            /// Gets the name of this [`Function`] as written in expressions.
+            #[must_use]
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(Self::$x => $name,)*
+                }
+            }
+
+            /// Gets the number of arguments this [`Function`] takes.
+            #[must_use]
+            pub fn arity(&self) -> usize {
+                match self {
+                    $(Self::$x => $arity,)*
+                }
+            }
+        }
+    };
+}
+
+functions! {
+    Abs = "abs"(1),
+    Sgn = "sgn"(1),
+    Sqrt = "sqrt"(1),
+    Pow = "pow"(2),
+    Exp = "exp"(1),
+    Log = "log"(1),
+    Sin = "sin"(1),
+    Cos = "cos"(1),
+    Tan = "tan"(1),
+    Asin = "asin"(1),
+    Acos = "acos"(1),
+    Atan = "atan"(1),
+    Atan2 = "atan2"(2),
+    Floor = "floor"(1),
+    Ceil = "ceil"(1),
+    Round = "round"(1),
+    Min = "min"(2),
+    Max = "max"(2),
+    Clamp = "clamp"(3),
+    Lerp = "lerp"(3),
+    InvLerp = "invlerp"(3),
+}
+
+/// A parsed expression.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Expr {
+    /// A number literal.
+    Number(f64),
+    /// A variable, by index into [`VARIABLES`].
+    Var(usize),
+    /// A unary operation.
+    Unary(UnaryOp, Box<Expr>),
+    /// A binary operation.
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    /// A function call.
+    Call(Function, Vec<Expr>),
+}
+
+/// An error found while parsing an expression.
+#[derive(Error, Clone, PartialEq, Debug)]
+#[error("{kind} at position {position}")]
+pub struct ExprError {
+    /// Byte offset into the expression where the error was found.
+    pub position: usize,
+    /// What went wrong.
+    pub kind: ExprErrorKind,
+}
+
+#[allow(missing_docs)]
+#[derive(Error, Clone, PartialEq, Debug)]
+pub enum ExprErrorKind {
+    #[error("Unexpected character {0:?}")]
+    UnexpectedChar(char),
+    #[error("Unexpected {0:?}")]
+    UnexpectedToken(String),
+    #[error("Unexpected end of expression")]
+    UnexpectedEnd,
+    #[error("Invalid number {0:?}")]
+    InvalidNumber(String),
+    #[error("Unknown variable {0:?}")]
+    UnknownVariable(String),
+    #[error("Variable {name:?} is not available, this node only has {allowed:?}")]
+    VariableNotAllowed {
+        name: String,
+        allowed: Vec<&'static str>,
+    },
+    #[error("Unknown function {0:?}")]
+    UnknownFunction(String),
+    #[error("Function {function:?} takes {expected} arguments but got {found}")]
+    WrongArgumentCount {
+        function: &'static str,
+        expected: usize,
+        found: usize,
+    },
+    #[error("Operator {0:?} is not allowed in this kind of expression")]
+    OperatorNotAllowed(char),
+    #[error("Expression is nested more than {MAX_DEPTH} levels deep")]
+    TooDeep,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Op(char),
+}
+
+struct Parser<'a> {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    len: usize,
+    kind: ExprKind,
+    variables: &'a [&'static str],
+    /// Current nesting depth, see [`MAX_DEPTH`].
+    depth: usize,
+}
+
+fn tokenize(src: &str) -> Result<Vec<(usize, Token)>, ExprError> {
+    let mut tokens = Vec::new();
+    let mut chars = src.char_indices().peekable();
+
+    while let Some(&(start, ch)) = chars.peek() {
+        if ch.is_whitespace() {
+            chars.next();
+        } else if ch.is_ascii_digit() || ch == '.' {
+            let mut end = start;
+            let mut prev = ch;
+            while let Some(&(i, c)) = chars.peek() {
+                let exp_sign = (c == '-' || c == '+') && (prev == 'e' || prev == 'E');
+                if c.is_ascii_alphanumeric() || c == '.' || exp_sign {
+                    end = i + c.len_utf8();
+                    prev = c;
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            let text = &src[start..end];
+            let n = text.parse().map_err(|_| ExprError {
+                position: start,
+                kind: ExprErrorKind::InvalidNumber(text.into()),
+            })?;
+            tokens.push((start, Token::Number(n)));
+        } else if ch.is_ascii_alphabetic() || ch == '_' {
+            let mut end = start;
+            while let Some(&(i, c)) = chars.peek() {
+                if c.is_ascii_alphanumeric() || c == '_' {
+                    end = i + c.len_utf8();
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            tokens.push((start, Token::Ident(src[start..end].to_ascii_lowercase())));
+        } else if "+-*/%^()!&|,".contains(ch) {
+            tokens.push((start, Token::Op(ch)));
+            chars.next();
+        } else {
+            return Err(ExprError {
+                position: start,
+                kind: ExprErrorKind::UnexpectedChar(ch),
+            });
+        }
+    }
+
+    Ok(tokens)
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(_, t)| t)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens.get(self.pos).map_or(self.len, |(p, _)| *p)
+    }
+
+    fn error(&self, kind: ExprErrorKind) -> ExprError {
+        ExprError { position: self.position(), kind }
+    }
+
+    fn unexpected(&self) -> ExprError {
+        match self.peek() {
+            Some(Token::Number(n)) => self.error(ExprErrorKind::UnexpectedToken(n.to_string())),
+            Some(Token::Ident(s)) => self.error(ExprErrorKind::UnexpectedToken(s.clone())),
+            Some(Token::Op(c)) => {
+                let other_kind = match self.kind {
+                    ExprKind::Number => "!&|",
+                    ExprKind::OnOff => "+-*/%",
+                };
+                if other_kind.contains(*c) {
+                    self.error(ExprErrorKind::OperatorNotAllowed(*c))
+                } else {
+                    self.error(ExprErrorKind::UnexpectedToken(c.to_string()))
+                }
+            },
+            None => self.error(ExprErrorKind::UnexpectedEnd),
+        }
+    }
+
+    /// Consumes the next token if it is the given operator.
+    fn eat(&mut self, op: char) -> bool {
+        if self.peek() == Some(&Token::Op(op)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, op: char) -> Result<(), ExprError> {
+        if self.eat(op) {
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    /// Goes one level deeper, failing past [`MAX_DEPTH`] so evaluating can't overflow the stack.
+    fn enter(&mut self) -> Result<(), ExprError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            Err(self.error(ExprErrorKind::TooDeep))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Parses `next` one level deeper.
+    fn nested(
+        &mut self,
+        next: fn(&mut Self) -> Result<Expr, ExprError>,
+    ) -> Result<Expr, ExprError> {
+        self.enter()?;
+        let e = next(self)?;
+        self.depth -= 1;
+        Ok(e)
+    }
+
+    /// Parses a chain of left-associative binary operators.
+    ///
+    /// Each operator in the chain nests the left side one level deeper.
+    fn binary(
+        &mut self,
+        ops: &[(char, BinaryOp)],
+        next: fn(&mut Self) -> Result<Expr, ExprError>,
+    ) -> Result<Expr, ExprError> {
+        let depth = self.depth;
+        let mut lhs = next(self)?;
+        'outer: loop {
+            for (c, op) in ops {
+                if self.eat(*c) {
+                    self.enter()?;
+                    let rhs = next(self)?;
+                    lhs = Expr::Binary(*op, Box::new(lhs), Box::new(rhs));
+                    continue 'outer;
+                }
+            }
+            self.depth = depth;
+            return Ok(lhs);
+        }
+    }
+
+    fn num_add(&mut self) -> Result<Expr, ExprError> {
+        self.binary(&[('+', BinaryOp::Add), ('-', BinaryOp::Sub)], Self::num_mul)
+    }
+
+    fn num_mul(&mut self) -> Result<Expr, ExprError> {
+        self.binary(
+            &[
+                ('*', BinaryOp::Mul),
+                ('/', BinaryOp::Div),
+                ('%', BinaryOp::Mod),
+            ],
+            Self::num_unary,
+        )
+    }
+
+    fn num_unary(&mut self) -> Result<Expr, ExprError> {
+        if self.eat('-') {
+            Ok(Expr::Unary(
+                UnaryOp::Neg,
+                Box::new(self.nested(Self::num_unary)?),
+            ))
+        } else if self.eat('+') {
+            self.nested(Self::num_unary)
+        } else {
+            self.num_pow()
+        }
+    }
+
+    fn num_pow(&mut self) -> Result<Expr, ExprError> {
+        let base = self.num_primary()?;
+        if self.eat('^') {
+            // right associative, binds tighter than unary minus on the left
+            let exp = self.nested(Self::num_unary)?;
+            Ok(Expr::Binary(BinaryOp::Pow, Box::new(base), Box::new(exp)))
+        } else {
+            Ok(base)
+        }
+    }
+
+    fn num_primary(&mut self) -> Result<Expr, ExprError> {
+        let position = self.position();
+        match self.peek().cloned() {
+            Some(Token::Number(n)) => {
+                self.pos += 1;
+                Ok(Expr::Number(n))
+            },
+            Some(Token::Op('(')) => {
+                self.pos += 1;
+                let e = self.nested(Self::num_add)?;
+                self.expect(')')?;
+                Ok(e)
+            },
+            Some(Token::Ident(name)) => {
+                self.pos += 1;
+                if self.peek() == Some(&Token::Op('(')) {
+                    self.pos += 1;
+                    let function = Function::from_name(&name).ok_or(ExprError {
+                        position,
+                        kind: ExprErrorKind::UnknownFunction(name.clone()),
+                    })?;
+
+                    let mut args = Vec::new();
+                    if !self.eat(')') {
+                        loop {
+                            args.push(self.nested(Self::num_add)?);
+                            if self.eat(')') {
+                                break;
+                            }
+                            self.expect(',')?;
+                        }
+                    }
+
+                    if args.len() != function.arity() {
+                        return Err(ExprError {
+                            position,
+                            kind: ExprErrorKind::WrongArgumentCount {
+                                function: function.name(),
+                                expected: function.arity(),
+                                found: args.len(),
+                            },
+                        });
+                    }
+
+                    Ok(Expr::Call(function, args))
+                } else if name == "pi" {
+                    Ok(Expr::Number(std::f64::consts::PI))
+                } else {
+                    self.variable(name, position)
+                }
+            },
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    fn bool_or(&mut self) -> Result<Expr, ExprError> {
+        self.binary(&[('|', BinaryOp::Or)], Self::bool_xor)
+    }
+
+    fn bool_xor(&mut self) -> Result<Expr, ExprError> {
+        self.binary(&[('^', BinaryOp::Xor)], Self::bool_and)
+    }
+
+    fn bool_and(&mut self) -> Result<Expr, ExprError> {
+        self.binary(&[('&', BinaryOp::And)], Self::bool_not)
+    }
+
+    fn bool_not(&mut self) -> Result<Expr, ExprError> {
+        if self.eat('!') {
+            Ok(Expr::Unary(
+                UnaryOp::Not,
+                Box::new(self.nested(Self::bool_not)?),
+            ))
+        } else {
+            self.bool_primary()
+        }
+    }
+
+    fn bool_primary(&mut self) -> Result<Expr, ExprError> {
+        let position = self.position();
+        match self.peek().cloned() {
+            Some(Token::Op('(')) => {
+                self.pos += 1;
+                let e = self.nested(Self::bool_or)?;
+                self.expect(')')?;
+                Ok(e)
+            },
+            Some(Token::Ident(name)) => {
+                self.pos += 1;
+                self.variable(name, position)
+            },
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    fn variable(&self, name: String, position: usize) -> Result<Expr, ExprError> {
+        if let Some(i) = self.variables.iter().position(|v| *v == name) {
+            Ok(Expr::Var(i))
+        } else if VARIABLES.contains(&name.as_str()) {
+            Err(ExprError {
+                position,
+                kind: ExprErrorKind::VariableNotAllowed { name, allowed: self.variables.to_vec() },
+            })
+        } else {
+            Err(ExprError {
+                position,
+                kind: ExprErrorKind::UnknownVariable(name),
+            })
+        }
+    }
+}
+
+/// Parses an expression.
+///
+/// `variables` is the number of variables available, starting from `x` (see [`VARIABLES`]).
+///
+/// An empty expression is valid and evaluates to `0`/`false`, same as ingame.
+///
+/// # Errors
+/// Returns an [`Err(ExprError)`] if the expression is invalid.
+pub fn parse(src: &str, kind: ExprKind, variables: usize) -> Result<Expr, ExprError> {
+    let tokens = tokenize(src)?;
+    if tokens.is_empty() {
+        return Ok(Expr::Number(0.0));
+    }
+
+    let mut parser = Parser {
+        tokens,
+        pos: 0,
+        len: src.len(),
+        kind,
+        variables: &VARIABLES[..variables.min(VARIABLES.len())],
+        depth: 0,
+    };
+
+    let expr = match parser.kind {
+        ExprKind::Number => parser.num_add()?,
+        ExprKind::OnOff => parser.bool_or()?,
+    };
+
+    if parser.pos < parser.tokens.len() {
+        return Err(parser.unexpected());
+    }
+
+    Ok(expr)
+}
+
+/// Gets the expression of a function node, along with its [`ExprKind`] and the number of variables it has.
+///
+/// Returns [`None`] if the component is not a function node.
+#[must_use]
+pub fn component_expression(component: &ComponentType) -> Option<(&str, ExprKind, usize)> {
+    match component {
+        ComponentType::Func1n { expr, .. } => Some((expr, ExprKind::Number, 1)),
+        ComponentType::Func3n { expr, .. } => Some((expr, ExprKind::Number, 3)),
+        ComponentType::Func8n { expr, .. } => Some((expr, ExprKind::Number, 8)),
+        ComponentType::Func4b { expr, .. } => Some((expr, ExprKind::OnOff, 4)),
+        ComponentType::Func8b { expr, .. } => Some((expr, ExprKind::OnOff, 8)),
+        _ => None,
+    }
+}
+
+/// Parses the expression of a function node.
+///
+/// Returns [`None`] if the component is not a function node.
+#[must_use]
+pub fn parse_component(component: &ComponentType) -> Option<Result<Expr, ExprError>> {
+    component_expression(component).map(|(src, kind, vars)| parse(src, kind, vars))
+}
+
+impl Expr {
+    /// Evaluates this expression as a number expression.
+    ///
+    /// Variables missing from `vars` are `0`.
+    #[must_use]
+    pub fn eval_number(&self, vars: &[f64]) -> f64 {
+        match self {
+            Expr::Number(n) => *n,
+            Expr::Var(i) => vars.get(*i).copied().unwrap_or(0.0),
+            Expr::Unary(UnaryOp::Neg, e) => -e.eval_number(vars),
+            Expr::Unary(UnaryOp::Not, e) => f64::from(u8::from(e.eval_number(vars) == 0.0)),
+            Expr::Binary(op, lhs, rhs) => {
+                let (a, b) = (lhs.eval_number(vars), rhs.eval_number(vars));
+                match op {
+                    BinaryOp::Add => a + b,
+                    BinaryOp::Sub => a - b,
+                    BinaryOp::Mul => a * b,
+                    BinaryOp::Div => a / b,
+                    BinaryOp::Mod => a % b,
+                    BinaryOp::Pow => a.powf(b),
+                    BinaryOp::And => f64::from(u8::from(a != 0.0 && b != 0.0)),
+                    BinaryOp::Or => f64::from(u8::from(a != 0.0 || b != 0.0)),
+                    BinaryOp::Xor => f64::from(u8::from((a != 0.0) != (b != 0.0))),
+                }
+            },
+            Expr::Call(f, args) => {
+                let arg = |i: usize| args.get(i).map_or(0.0, |a| a.eval_number(vars));
+                match f {
+                    Function::Abs => arg(0).abs(),
+                    Function::Sgn => {
+                        let x = arg(0);
+                        if x > 0.0 {
+                            1.0
+                        } else if x < 0.0 {
+                            -1.0
+                        } else {
+                            0.0
+                        }
+                    },
+                    Function::Sqrt => arg(0).sqrt(),
+                    Function::Pow => arg(0).powf(arg(1)),
+                    Function::Exp => arg(0).exp(),
+                    Function::Log => arg(0).ln(),
+                    Function::Sin => arg(0).sin(),
+                    Function::Cos => arg(0).cos(),
+                    Function::Tan => arg(0).tan(),
+                    Function::Asin => arg(0).asin(),
+                    Function::Acos => arg(0).acos(),
+                    Function::Atan => arg(0).atan(),
+                    Function::Atan2 => arg(0).atan2(arg(1)),
+                    Function::Floor => arg(0).floor(),
+                    Function::Ceil => arg(0).ceil(),
+                    Function::Round => arg(0).round(),
+                    Function::Min => arg(0).min(arg(1)),
+                    Function::Max => arg(0).max(arg(1)),
+                    Function::Clamp => arg(0).max(arg(1)).min(arg(2)),
+                    Function::Lerp => arg(0) + (arg(1) - arg(0)) * arg(2),
+                    Function::InvLerp => (arg(2) - arg(0)) / (arg(1) - arg(0)),
+                }
+            },
+        }
+    }
+
+    /// Evaluates this expression as an on/off expression.
+    ///
+    /// Variables missing from `vars` are `false`.
+    #[must_use]
+    pub fn eval_bool(&self, vars: &[bool]) -> bool {
+        match self {
+            Expr::Var(i) => vars.get(*i).copied().unwrap_or(false),
+            Expr::Unary(UnaryOp::Not, e) => !e.eval_bool(vars),
+            Expr::Binary(BinaryOp::And, lhs, rhs) => lhs.eval_bool(vars) && rhs.eval_bool(vars),
+            Expr::Binary(BinaryOp::Or, lhs, rhs) => lhs.eval_bool(vars) || rhs.eval_bool(vars),
+            Expr::Binary(BinaryOp::Xor, lhs, rhs) => lhs.eval_bool(vars) != rhs.eval_bool(vars),
+            _ => {
+                let vars: Vec<f64> = vars.iter().map(|b| f64::from(u8::from(*b))).collect();
+                self.eval_number(&vars) != 0.0
+            },
+        }
+    }
+}
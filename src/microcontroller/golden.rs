@@ -0,0 +1,450 @@
+//! Module for checking a microcontroller's behavior against golden files of per-tick IO values.
+//!
+//! A golden file lives next to the microcontroller, with the same name and a `.golden`
+//! extension, and runs it in the [`Simulator`]:
+//!
+//! ```text
+//! # Multiply Const with its `number` property set to 2 doubles its input.
+//! property number = 2
+//!
+//! tick | Input | Output
+//! 0    | 3     | 0
+//! 2    |       | 6
+//! 3    | -1.5  |
+//! 5    |       | -3
+//! ```
+//!
+//! - Lines starting with `#` are comments, blank lines are ignored.
+//! - `property <name> = <value>` sets a property before the run, see
+//!   [`Microcontroller::set_property`]. Values are numbers, `on`/`off`, or text (quoted if it
+//!   would otherwise be read as one of those).
+//! - `tolerance <number>` sets how far numbers can be from the expected value, default
+//!   [`DEFAULT_TOLERANCE`].
+//! - The table header is `tick` followed by the labels of the IO nodes to set or check. Each row
+//!   sets the inputs before that tick runs and checks the outputs after it, ticks counting from
+//!   0 and going up row by row. Empty cells leave an input as it was and don't check an output.
+//! - Cells are numbers, `on`/`off`, or composites as `{n1=2.5 b3=on}` (unlisted channels are
+//!   0/off).
+//!
+//! In tests, [`assert_golden`] runs a microcontroller file against its golden file:
+//!
+//! ```no_run
+//! sw_rs::microcontroller::golden::assert_golden("samples/microcontroller/mul_const.xml");
+//! ```
+
+use std::{
+    fmt::Display,
+    io,
+    path::{Path, PathBuf},
+};
+
+use thiserror::Error;
+
+use super::{
+    mc_serde::microcontroller::IONodeType,
+    properties::{PropertyError, PropertyValue},
+    simulator::{Composite, SimError, Simulator, Value, COMPOSITE_CHANNELS},
+    types::Type,
+    MCSerDeError, Microcontroller,
+};
+
+/// Extension of golden files.
+pub const EXTENSION: &str = "golden";
+
+/// How far numbers may be from the expected value if the file doesn't set a `tolerance`.
+pub const DEFAULT_TOLERANCE: f32 = 1e-5;
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum GoldenError {
+    #[error("{}: {error}", path.display())]
+    Io { path: PathBuf, error: io::Error },
+    #[error("{}: {error}", path.display())]
+    SerDe {
+        path: PathBuf,
+        error: Box<MCSerDeError>,
+    },
+    #[error("line {line}: {message}")]
+    Parse { line: usize, message: String },
+    #[error("line {line}: {error}")]
+    Property { line: usize, error: PropertyError },
+    #[error("line {line}: no IO node labeled {label:?}")]
+    UnknownLabel { line: usize, label: String },
+    #[error("line {line}: {count} IO nodes are labeled {label:?}")]
+    AmbiguousLabel {
+        line: usize,
+        label: String,
+        count: usize,
+    },
+    #[error(transparent)]
+    Sim(#[from] SimError),
+    #[error("line {line}: {label:?} has type {expected:?} but the value was {found:?}")]
+    TypeMismatch {
+        line: usize,
+        label: String,
+        expected: Type,
+        found: Type,
+    },
+}
+
+/// A row of a [`Golden`] table.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GoldenRow {
+    /// Line of the file the row is on.
+    pub line: usize,
+    /// The tick the row is for.
+    pub tick: u64,
+    /// Value for each of [`Golden::labels`], [`None`] for empty cells.
+    pub cells: Vec<Option<Value>>,
+}
+
+/// A parsed golden file, see the [module docs][self] for the format.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Golden {
+    /// Properties to set before the run, with the line they're on.
+    pub properties: Vec<(usize, String, PropertyValue)>,
+    /// How far numbers may be from the expected value.
+    pub tolerance: f32,
+    /// Line of the table header.
+    pub header_line: usize,
+    /// Labels of the IO nodes in the table, in column order.
+    pub labels: Vec<String>,
+    /// The table rows, in tick order.
+    pub rows: Vec<GoldenRow>,
+}
+
+/// An output that didn't have the expected value.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mismatch {
+    /// Line of the row with the expected value.
+    pub line: usize,
+    /// The tick it was checked after.
+    pub tick: u64,
+    /// Label of the output IO node.
+    pub label: String,
+    /// The value in the golden file.
+    pub expected: Value,
+    /// The value the microcontroller output.
+    pub actual: Value,
+}
+
+impl Display for Mismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "line {}: tick {}, {:?} expected {} but got {}",
+            self.line,
+            self.tick,
+            self.label,
+            CellValue(&self.expected),
+            CellValue(&self.actual)
+        )
+    }
+}
+
+/// Formats a [`Value`] the way it's written in a golden file.
+struct CellValue<'a>(&'a Value);
+
+impl Display for CellValue<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            Value::OnOff(true) => f.write_str("on"),
+            Value::OnOff(false) => f.write_str("off"),
+            Value::Number(n) => write!(f, "{n}"),
+            Value::Composite(c) => {
+                f.write_str("{")?;
+                let mut sep = "";
+                for (i, n) in c.numbers.iter().enumerate().filter(|(_, n)| **n != 0.0) {
+                    write!(f, "{sep}n{}={n}", i + 1)?;
+                    sep = " ";
+                }
+                for (i, _) in c.bools.iter().enumerate().filter(|(_, b)| **b) {
+                    write!(f, "{sep}b{}=on", i + 1)?;
+                    sep = " ";
+                }
+                f.write_str("}")
+            },
+            Value::Video => f.write_str("video"),
+            Value::Audio => f.write_str("audio"),
+        }
+    }
+}
+
+fn parse_bool(s: &str) -> Option<bool> {
+    match s {
+        "on" => Some(true),
+        "off" => Some(false),
+        _ => None,
+    }
+}
+
+fn parse_cell(s: &str) -> Result<Option<Value>, String> {
+    if s.is_empty() {
+        return Ok(None);
+    }
+    if let Some(b) = parse_bool(s) {
+        return Ok(Some(Value::OnOff(b)));
+    }
+    if let Ok(n) = s.parse() {
+        return Ok(Some(Value::Number(n)));
+    }
+
+    let Some(channels) = s.strip_prefix('{').and_then(|s| s.strip_suffix('}')) else {
+        return Err(format!("invalid value {s:?}"));
+    };
+    let mut c = Composite::default();
+    for channel in channels.split([' ', ',']).filter(|ch| !ch.is_empty()) {
+        let invalid = || format!("invalid composite channel {channel:?}");
+        let (name, value) = channel.split_once('=').ok_or_else(invalid)?;
+        let (is_number, index) = match (name.strip_prefix('n'), name.strip_prefix('b')) {
+            (Some(index), _) => (true, index),
+            (_, Some(index)) => (false, index),
+            _ => return Err(invalid()),
+        };
+        let index = index
+            .parse::<usize>()
+            .ok()
+            .filter(|i| (1..=COMPOSITE_CHANNELS).contains(i))
+            .ok_or_else(invalid)?;
+        if is_number {
+            c.numbers[index - 1] = value.parse().map_err(|_| invalid())?;
+        } else {
+            c.bools[index - 1] = parse_bool(value).ok_or_else(invalid)?;
+        }
+    }
+    Ok(Some(Value::Composite(c)))
+}
+
+fn parse_property_value(s: &str) -> PropertyValue {
+    if let Some(text) = s.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+        PropertyValue::Text(text.into())
+    } else if let Some(b) = parse_bool(s) {
+        PropertyValue::Bool(b)
+    } else if let Ok(n) = s.parse() {
+        PropertyValue::Number(n)
+    } else {
+        PropertyValue::Text(s.into())
+    }
+}
+
+fn values_match(expected: &Value, actual: &Value, tolerance: f32) -> bool {
+    let number = |e: f32, a: f32| (e - a).abs() <= tolerance || (e.is_nan() && a.is_nan());
+    match (expected, actual) {
+        (Value::Number(e), Value::Number(a)) => number(*e, *a),
+        (Value::Composite(e), Value::Composite(a)) => {
+            e.bools == a.bools
+                && e.numbers
+                    .iter()
+                    .zip(&a.numbers)
+                    .all(|(e, a)| number(*e, *a))
+        },
+        _ => expected == actual,
+    }
+}
+
+impl Golden {
+    /// Parses a golden file.
+    ///
+    /// # Errors
+    /// Returns a [`GoldenError::Parse`] if the file isn't valid.
+    pub fn parse(src: &str) -> Result<Self, GoldenError> {
+        let mut golden = Self {
+            properties: vec![],
+            tolerance: DEFAULT_TOLERANCE,
+            header_line: 0,
+            labels: vec![],
+            rows: vec![],
+        };
+
+        for (i, line) in src.lines().enumerate() {
+            let line_no = i + 1;
+            let err = |message: String| GoldenError::Parse { line: line_no, message };
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if golden.header_line == 0 {
+                if let Some(rest) = line.strip_prefix("property ") {
+                    let (name, value) = rest
+                        .split_once('=')
+                        .ok_or_else(|| err("expected `property <name> = <value>`".into()))?;
+                    golden.properties.push((
+                        line_no,
+                        name.trim().into(),
+                        parse_property_value(value.trim()),
+                    ));
+                } else if let Some(rest) = line.strip_prefix("tolerance ") {
+                    golden.tolerance = rest
+                        .trim()
+                        .parse()
+                        .map_err(|_| err(format!("invalid tolerance {rest:?}")))?;
+                } else {
+                    let mut cells = line.split('|').map(str::trim);
+                    if cells.next() != Some("tick") {
+                        return Err(err(format!(
+                            "expected a directive or table header, got {line:?}"
+                        )));
+                    }
+                    golden.labels = cells.map(Into::into).collect();
+                    golden.header_line = line_no;
+                }
+                continue;
+            }
+
+            let cells: Vec<_> = line.split('|').map(str::trim).collect();
+            if cells.len() != golden.labels.len() + 1 {
+                return Err(err(format!(
+                    "expected {} cells, got {}",
+                    golden.labels.len() + 1,
+                    cells.len()
+                )));
+            }
+            let tick: u64 = cells[0]
+                .parse()
+                .map_err(|_| err(format!("invalid tick {:?}", cells[0])))?;
+            if golden.rows.last().is_some_and(|r| r.tick >= tick) {
+                return Err(err(format!("tick {tick} isn't after the previous row's")));
+            }
+            golden.rows.push(GoldenRow {
+                line: line_no,
+                tick,
+                cells: cells[1..]
+                    .iter()
+                    .map(|c| parse_cell(c))
+                    .collect::<Result<_, _>>()
+                    .map_err(err)?,
+            });
+        }
+
+        if golden.header_line == 0 {
+            return Err(GoldenError::Parse {
+                line: src.lines().count(),
+                message: "missing the `tick | ...` table".into(),
+            });
+        }
+        Ok(golden)
+    }
+
+    /// Runs `mc` through the table, returning every output that didn't match.
+    ///
+    /// Properties are set on a copy, `mc` isn't changed.
+    ///
+    /// # Errors
+    /// Returns a [`GoldenError`] if a property can't be set, or a column doesn't match exactly one
+    /// IO node or has values of the wrong type.
+    pub fn run(&self, mc: &Microcontroller) -> Result<Vec<Mismatch>, GoldenError> {
+        let mut mc = mc.clone();
+        for (line, name, value) in &self.properties {
+            mc.set_property(name, value.clone())
+                .map_err(|error| GoldenError::Property { line: *line, error })?;
+        }
+
+        let mut columns = vec![];
+        for label in &self.labels {
+            let nodes: Vec<_> = mc
+                .io_nodes()
+                .iter()
+                .filter(|ion| &ion.design.label == label)
+                .collect();
+            let line = self.header_line;
+            match nodes.as_slice() {
+                [ion] => columns.push((ion.get_id(), ion.design.mode, ion.design.typ)),
+                [] => return Err(GoldenError::UnknownLabel { line, label: label.clone() }),
+                _ => {
+                    return Err(GoldenError::AmbiguousLabel {
+                        line,
+                        label: label.clone(),
+                        count: nodes.len(),
+                    })
+                },
+            }
+        }
+        for row in &self.rows {
+            for ((label, (_, _, typ)), cell) in self.labels.iter().zip(&columns).zip(&row.cells) {
+                if let Some(value) = cell.filter(|v| v.typ() != *typ) {
+                    return Err(GoldenError::TypeMismatch {
+                        line: row.line,
+                        label: label.clone(),
+                        expected: *typ,
+                        found: value.typ(),
+                    });
+                }
+            }
+        }
+
+        let mut sim = Simulator::new(&mc);
+        let mut mismatches = vec![];
+        for row in &self.rows {
+            sim.run(row.tick - sim.tick_count());
+            for ((node_id, mode, _), cell) in columns.iter().zip(&row.cells) {
+                if let (IONodeType::Input, Some(value)) = (mode, cell) {
+                    sim.set_input(*node_id, *value)?;
+                }
+            }
+
+            sim.tick();
+            for ((label, (node_id, mode, _)), cell) in
+                self.labels.iter().zip(&columns).zip(&row.cells)
+            {
+                if let (IONodeType::Output, Some(expected)) = (mode, cell) {
+                    let actual = sim.output(*node_id)?;
+                    if !values_match(expected, &actual, self.tolerance) {
+                        mismatches.push(Mismatch {
+                            line: row.line,
+                            tick: row.tick,
+                            label: label.clone(),
+                            expected: *expected,
+                            actual,
+                        });
+                    }
+                }
+            }
+        }
+        Ok(mismatches)
+    }
+}
+
+/// Gets the golden file for a microcontroller file, the same path with a `.golden` extension.
+#[must_use]
+pub fn golden_path(mc_path: &Path) -> PathBuf {
+    mc_path.with_extension(EXTENSION)
+}
+
+/// Loads the microcontroller at `mc_path` and runs it against its [golden file][golden_path].
+///
+/// # Errors
+/// Returns a [`GoldenError`] if either file can't be loaded, or [`Golden::run`] fails.
+pub fn check_golden(mc_path: impl AsRef<Path>) -> Result<Vec<Mismatch>, GoldenError> {
+    let mc_path = mc_path.as_ref();
+    let read = |path: &Path| {
+        std::fs::read_to_string(path).map_err(|error| GoldenError::Io { path: path.into(), error })
+    };
+
+    let mc = Microcontroller::from_xml_str(&read(mc_path)?)
+        .map_err(|error| GoldenError::SerDe { path: mc_path.into(), error: Box::new(error) })?;
+    Golden::parse(&read(&golden_path(mc_path))?)?.run(&mc)
+}
+
+/// Test helper that runs the microcontroller at `mc_path` against its
+/// [golden file][golden_path], see [`check_golden`].
+///
+/// # Panics
+/// Panics if the files can't be loaded or an output doesn't match, listing every mismatch.
+#[track_caller]
+pub fn assert_golden(mc_path: impl AsRef<Path>) {
+    let mc_path = mc_path.as_ref();
+    match check_golden(mc_path) {
+        Ok(mismatches) if mismatches.is_empty() => {},
+        Ok(mismatches) => {
+            let list: Vec<_> = mismatches.iter().map(ToString::to_string).collect();
+            panic!(
+                "{} doesn't match {}:\n{}",
+                mc_path.display(),
+                golden_path(mc_path).display(),
+                list.join("\n")
+            );
+        },
+        Err(e) => panic!("Failed to check {}: {e}", mc_path.display()),
+    }
+}
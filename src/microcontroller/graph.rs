@@ -0,0 +1,328 @@
+//! Module containing a read-only graph view over a [`Microcontroller`]'s logic.
+//!
+//! The model only stores each component's incoming connections, [`Graph`] also indexes the
+//! reverse edges so consumers and downstream components can be looked up directly.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::util::AnyComponentRef;
+
+use super::{
+    components::ComponentConnection, group::Group, mc_serde::microcontroller::IONodeType,
+    Microcontroller,
+};
+
+fn reachable<'g>(id: u32, next: impl Fn(u32) -> &'g [u32]) -> Vec<u32> {
+    let mut seen = HashSet::new();
+    let mut out = vec![];
+    let mut queue: VecDeque<u32> = next(id).iter().copied().collect();
+    while let Some(c) = queue.pop_front() {
+        if seen.insert(c) {
+            out.push(c);
+            queue.extend(next(c));
+        }
+    }
+    out
+}
+
+/// Graph view over the [`Component`][super::components::Component]s and
+/// [`BridgeComponent`][super::components::BridgeComponent]s of a [`Microcontroller`], including
+/// the ones in nested [`groups`][Microcontroller::groups].
+///
+/// Connections to components that don't exist are ignored.
+pub struct Graph<'a> {
+    components: Vec<AnyComponentRef<'a>>,
+    index: HashMap<u32, usize>,
+    /// (component id, output index) -> list of (component id, input index)
+    consumers: HashMap<(u32, u8), Vec<ComponentConnection>>,
+    /// component id -> ids of the components feeding into it, in input order
+    predecessors: HashMap<u32, Vec<u32>>,
+    /// component id -> ids of the components it feeds, in output order
+    successors: HashMap<u32, Vec<u32>>,
+    /// ids of the components in nested groups
+    nested: HashSet<u32>,
+    /// ids of the bridge components for input nodes
+    input_bridges: HashSet<u32>,
+    /// ids of the bridge components for output nodes
+    output_bridges: HashSet<u32>,
+}
+
+impl Microcontroller {
+    /// Builds a [`Graph`] view of this microcontroller's logic.
+    #[must_use]
+    pub fn graph(&self) -> Graph<'_> {
+        Graph::new(self)
+    }
+}
+
+impl<'a> Graph<'a> {
+    /// Builds a [`Graph`] view of the given [`Microcontroller`].
+    #[must_use]
+    pub fn new(mc: &'a Microcontroller) -> Self {
+        let components: Vec<_> = mc.all_components().collect();
+        let index = components
+            .iter()
+            .enumerate()
+            .map(|(i, c)| (c.id(), i))
+            .collect::<HashMap<_, _>>();
+
+        let mut consumers: HashMap<(u32, u8), Vec<ComponentConnection>> = HashMap::new();
+        for c in &components {
+            for (i, conn) in c.inputs().into_iter().enumerate() {
+                if let Some(conn) = conn {
+                    if index.contains_key(&conn.component_id) {
+                        #[allow(clippy::cast_possible_truncation)]
+                        consumers
+                            .entry((conn.component_id, conn.node_index))
+                            .or_default()
+                            .push(ComponentConnection {
+                                component_id: c.id(),
+                                node_index: i as u8,
+                            });
+                    }
+                }
+            }
+        }
+
+        let mut predecessors: HashMap<u32, Vec<u32>> = HashMap::new();
+        let mut successors: HashMap<u32, Vec<u32>> = HashMap::new();
+        for c in &components {
+            let preds = predecessors.entry(c.id()).or_default();
+            for conn in c.inputs().into_iter().flatten() {
+                if index.contains_key(&conn.component_id) && !preds.contains(&conn.component_id) {
+                    preds.push(conn.component_id);
+                }
+            }
+
+            let succs = successors.entry(c.id()).or_default();
+            for i in 0..c.io_def().outputs.len() {
+                #[allow(clippy::cast_possible_truncation)]
+                let consumers = consumers
+                    .get(&(c.id(), i as u8))
+                    .map_or(&[][..], Vec::as_slice);
+                for consumer in consumers {
+                    if !succs.contains(&consumer.component_id) {
+                        succs.push(consumer.component_id);
+                    }
+                }
+            }
+        }
+
+        let bridges = |mode: IONodeType| {
+            components
+                .iter()
+                .filter_map(|c| match c {
+                    AnyComponentRef::BridgeComponent(bc) if bc.component.mode() == mode => {
+                        Some(bc.id)
+                    },
+                    _ => None,
+                })
+                .collect()
+        };
+
+        Self {
+            nested: mc.groups().iter().flat_map(Group::ids).collect(),
+            input_bridges: bridges(IONodeType::Input),
+            output_bridges: bridges(IONodeType::Output),
+            components,
+            index,
+            consumers,
+            predecessors,
+            successors,
+        }
+    }
+
+    /// Gets the component with the given id.
+    #[must_use]
+    pub fn get(&self, id: u32) -> Option<&AnyComponentRef<'a>> {
+        self.index.get(&id).map(|&i| &self.components[i])
+    }
+
+    /// Gets the ids of all components, in the same order as [`Microcontroller::all_components`].
+    #[must_use]
+    pub fn ids(&self) -> Vec<u32> {
+        self.components.iter().map(AnyComponentRef::id).collect()
+    }
+
+    /// Checks if the component is in a nested [`Group`] rather than the microcontroller itself.
+    #[must_use]
+    pub fn is_nested(&self, id: u32) -> bool {
+        self.nested.contains(&id)
+    }
+
+    /// Gets the inputs connected to the given output.
+    ///
+    /// `output.node_index` is the output index, the returned [`ComponentConnection`]s use
+    /// `node_index` for the input index of the consumer.
+    #[must_use]
+    pub fn consumers(&self, output: &ComponentConnection) -> &[ComponentConnection] {
+        self.consumers
+            .get(&(output.component_id, output.node_index))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Gets the ids of the components feeding directly into the given component.
+    #[must_use]
+    pub fn predecessors(&self, id: u32) -> &[u32] {
+        self.predecessors.get(&id).map_or(&[], Vec::as_slice)
+    }
+
+    /// Gets the ids of the components directly fed by the given component.
+    #[must_use]
+    pub fn successors(&self, id: u32) -> &[u32] {
+        self.successors.get(&id).map_or(&[], Vec::as_slice)
+    }
+
+    /// Gets the ids of every component the given component (transitively) depends on.
+    ///
+    /// Doesn't include `id` itself unless it is part of a loop.
+    #[must_use]
+    pub fn upstream(&self, id: u32) -> Vec<u32> {
+        reachable(id, |c| self.predecessors(c))
+    }
+
+    /// Gets the ids of every component that (transitively) depends on the given component.
+    ///
+    /// Doesn't include `id` itself unless it is part of a loop.
+    #[must_use]
+    pub fn downstream(&self, id: u32) -> Vec<u32> {
+        reachable(id, |c| self.successors(c))
+    }
+
+    /// Gets the component ids ordered so that every component comes after the components it depends on.
+    ///
+    /// Returns [`None`] if the logic contains a loop, see [`Graph::strongly_connected_components`].
+    #[must_use]
+    pub fn topological_order(&self) -> Option<Vec<u32>> {
+        let order: Vec<u32> = self
+            .strongly_connected_components()
+            .into_iter()
+            .map(|scc| match scc[..] {
+                [id] if !self.predecessors(id).contains(&id) => Some(id),
+                _ => None,
+            })
+            .collect::<Option<_>>()?;
+        Some(order)
+    }
+
+    /// Gets the strongly connected components (groups of components that all depend on each other).
+    ///
+    /// Every component is in exactly one group, groups with more than one component (or a single
+    /// component connected to itself) are loops.
+    /// The groups are returned in topological order.
+    #[must_use]
+    pub fn strongly_connected_components(&self) -> Vec<Vec<u32>> {
+        // Tarjan's algorithm, with an explicit stack so long chains can't overflow
+        let mut next_index = 0;
+        let mut index: HashMap<u32, usize> = HashMap::new();
+        let mut lowlink: HashMap<u32, usize> = HashMap::new();
+        let mut stack = vec![];
+        let mut on_stack = HashSet::new();
+        let mut sccs = vec![];
+
+        // visit sources first so the order follows the components list where possible
+        let (sources, rest): (Vec<_>, Vec<_>) = self
+            .ids()
+            .into_iter()
+            .partition(|&id| self.predecessors(id).is_empty());
+        for root in sources.into_iter().chain(rest) {
+            if index.contains_key(&root) {
+                continue;
+            }
+
+            // (component id, index of the next successor to look at)
+            let mut calls = vec![(root, 0)];
+            while let Some(&(id, next)) = calls.last() {
+                if next == 0 {
+                    index.insert(id, next_index);
+                    lowlink.insert(id, next_index);
+                    next_index += 1;
+                    stack.push(id);
+                    on_stack.insert(id);
+                }
+
+                if let Some(&succ) = self.successors(id).get(next) {
+                    if let Some(call) = calls.last_mut() {
+                        call.1 += 1;
+                    }
+                    if !index.contains_key(&succ) {
+                        calls.push((succ, 0));
+                    } else if on_stack.contains(&succ) {
+                        let low = lowlink[&id].min(index[&succ]);
+                        lowlink.insert(id, low);
+                    }
+                    continue;
+                }
+
+                calls.pop();
+                if let Some(&(parent, _)) = calls.last() {
+                    let low = lowlink[&parent].min(lowlink[&id]);
+                    lowlink.insert(parent, low);
+                }
+
+                if lowlink[&id] == index[&id] {
+                    let mut scc = vec![];
+                    while let Some(c) = stack.pop() {
+                        on_stack.remove(&c);
+                        scc.push(c);
+                        if c == id {
+                            break;
+                        }
+                    }
+                    scc.reverse();
+                    sccs.push(scc);
+                }
+            }
+        }
+
+        // Tarjan produces reverse topological order
+        sccs.reverse();
+        sccs
+    }
+
+    /// Gets every input that isn't connected to anything, as (component id, input index).
+    ///
+    /// The placeholder inputs of input node bridges are skipped.
+    #[must_use]
+    pub fn unconnected_inputs(&self) -> Vec<ComponentConnection> {
+        let mut out = vec![];
+        for c in &self.components {
+            if self.input_bridges.contains(&c.id()) {
+                continue;
+            }
+
+            for (i, conn) in c.inputs().into_iter().enumerate() {
+                let connected = conn
+                    .as_ref()
+                    .is_some_and(|conn| self.index.contains_key(&conn.component_id));
+                if !connected {
+                    #[allow(clippy::cast_possible_truncation)]
+                    out.push(ComponentConnection { component_id: c.id(), node_index: i as u8 });
+                }
+            }
+        }
+        out
+    }
+
+    /// Gets every output that isn't connected to anything, as (component id, output index).
+    ///
+    /// The placeholder outputs of output node bridges are skipped.
+    #[must_use]
+    pub fn unconnected_outputs(&self) -> Vec<ComponentConnection> {
+        let mut out = vec![];
+        for c in &self.components {
+            if self.output_bridges.contains(&c.id()) {
+                continue;
+            }
+
+            for i in 0..c.io_def().outputs.len() {
+                #[allow(clippy::cast_possible_truncation)]
+                let conn = ComponentConnection { component_id: c.id(), node_index: i as u8 };
+                if self.consumers(&conn).is_empty() {
+                    out.push(conn);
+                }
+            }
+        }
+        out
+    }
+}
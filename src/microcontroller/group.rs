@@ -0,0 +1,110 @@
+//! Module containing groups of components nested in a microcontroller.
+
+use serde::{Deserialize, Serialize};
+
+use crate::util::{serde_utils::PositionXY, AnyComponentMut, AnyComponentRef};
+
+use super::{
+    components::{BridgeComponent, Component},
+    mc_serde::is_default,
+    types::Type,
+};
+
+/// A group of components nested in a [`Microcontroller`][`super::Microcontroller`] or another
+/// [`Group`].
+///
+/// Component ids are shared with the rest of the microcontroller.
+#[derive(Clone, Debug)]
+pub struct Group {
+    /// The tag this group is stored under in its parent's `groups`.
+    pub tag: String,
+    /// The group's data, see [`GroupData`].
+    pub data: GroupData,
+    /// The group's components.
+    pub components: Vec<Component>,
+    /// The group's bridge components, in the order they're stored.
+    pub bridge_components: Vec<BridgeComponent>,
+    /// Groups nested in this one.
+    pub groups: Vec<Group>,
+    /// Saved state of the nested groups.
+    pub group_states: Vec<GroupState>,
+}
+
+impl Group {
+    /// Gets every component id in this group and the groups nested in it, in storage order.
+    pub(crate) fn ids(&self) -> Vec<u32> {
+        let components = self.components.iter().map(Component::id);
+        let bridge = self.bridge_components.iter().map(BridgeComponent::id);
+        let groups = self.groups.iter().flat_map(Group::ids);
+        components.chain(bridge).chain(groups).collect()
+    }
+
+    /// Access every component in this group and the groups nested in it, in storage order.
+    pub(crate) fn all_components(&self) -> Vec<AnyComponentRef<'_>> {
+        let mut all: Vec<AnyComponentRef> = self
+            .components
+            .iter()
+            .map(AnyComponentRef::Component)
+            .chain(
+                self.bridge_components
+                    .iter()
+                    .map(AnyComponentRef::BridgeComponent),
+            )
+            .collect();
+        for group in &self.groups {
+            all.extend(group.all_components());
+        }
+        all
+    }
+
+    /// Mutably access every component in this group and the groups nested in it, in storage order.
+    pub(crate) fn all_components_mut(&mut self) -> Vec<AnyComponentMut<'_>> {
+        let mut all: Vec<AnyComponentMut> = self
+            .components
+            .iter_mut()
+            .map(AnyComponentMut::Component)
+            .chain(
+                self.bridge_components
+                    .iter_mut()
+                    .map(AnyComponentMut::BridgeComponent),
+            )
+            .collect();
+        for group in &mut self.groups {
+            all.extend(group.all_components_mut());
+        }
+        all
+    }
+}
+
+/// The `data` tag of a group.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GroupData {
+    /// The `type` attribute.
+    pub typ: Option<String>,
+    /// The data inputs, stored as `i` elements.
+    pub inputs: Vec<GroupDataNode>,
+    /// The data outputs, stored as `o` elements.
+    pub outputs: Vec<GroupDataNode>,
+}
+
+/// A data input or output of a group, see [`GroupData`].
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct GroupDataNode {
+    /// The name of the input or output.
+    #[serde(rename = "@label", default)]
+    pub label: String,
+    /// The data type.
+    #[serde(rename = "@type", default, skip_serializing_if = "is_default")]
+    pub typ: Type,
+    /// Position on the group.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub position: PositionXY,
+}
+
+/// Saved state of a nested group, stored as `g0`, `g1`... in its parent's `group_states`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct GroupState {
+    /// The `id` attribute.
+    #[serde(rename = "@id", default, skip_serializing_if = "is_default")]
+    pub id: u32,
+}
@@ -0,0 +1,951 @@
+//! Module containing a JSON interchange format for microcontrollers, behind the `json` feature.
+//!
+//! [`Microcontroller::to_json_string`] and [`Microcontroller::from_json_str`] convert to and from
+//! it. Nothing is lost on the way: converting a microcontroller's XML to JSON and back gives the
+//! same XML, so tools that don't want to deal with the game's format can edit the JSON instead.
+//!
+//! # Format
+//!
+//! Version [`VERSION`] looks like this (`...` marks left out parts):
+//!
+//! ```json
+//! {
+//!   "format": "sw-rs/microcontroller",
+//!   "version": 1,
+//!   "name": "Multiply Const",
+//!   "description": "No description set.",
+//!   "width": 2,
+//!   "length": 1,
+//!   "icon": [0, 0, 0, 4, 2060, 1032, 29208, 6448, 2272, 2272, 2224, 14616, 25356, 518, 0, 0],
+//!   "id_counter": 7,
+//!   "id_counter_node": 2,
+//!   "io": [
+//!     {
+//!       "node_id": 1,
+//!       "label": "Input",
+//!       "description": "The input signal to be processed.",
+//!       "type": "number",
+//!       "mode": "input",
+//!       "position": { "x": 0.0, "y": 0.0 },
+//!       "component": { "id": 3, "type": "NumberIn", "pos": { "x": 0.0, "y": 0.0 } }
+//!     },
+//!     ...
+//!   ],
+//!   "components": [
+//!     {
+//!       "id": 6,
+//!       "type": "PropertyNumber",
+//!       "pos": { "x": 0.0, "y": 0.75 },
+//!       "settings": { "name": "number", "value": { "text": "1", "value": 1.0 } }
+//!     },
+//!     { "id": 7, "type": "Multiply", "pos": { "x": 1.5, "y": 0.25 } }
+//!   ],
+//!   "wires": [
+//!     { "from": { "component": 6, "output": 0 }, "to": { "component": 7, "input": 0 } },
+//!     { "from": { "component": 3, "output": 0 }, "to": { "component": 7, "input": 1 } },
+//!     ...
+//!   ]
+//! }
+//! ```
+//!
+//! - `format` and `version` identify the document. The version goes up whenever a change would
+//!   make older readers get something wrong, [`Microcontroller::from_json_str`] rejects versions
+//!   it doesn't know.
+//! - `icon`, `id_counter` and `id_counter_node` are [`Microcontroller::icon`] and the highest
+//!   component and IO node ids used. `id_counter_node` is left out if there never were IO nodes.
+//! - `io` has the IO nodes in order, `type` is one of `on_off`, `number`, `composite`, `video`
+//!   and `audio` and `mode` is `input` or `output`. Each node's logic side is its `component`.
+//! - `components` and the IO nodes' `component`s have:
+//!   - `id`, `type` (the [`ComponentType`] or [`BridgeComponentType`] variant) and `pos`.
+//!   - `settings`: the variant's fields that aren't inputs or outputs. Strings, numbers and
+//!     bools are stored as-is, [`TextValue`]s as `{ "text": .., "value": .. }` (the value is a
+//!     string if it isn't finite), composite channels as the number shown ingame or
+//!     `"variable"`, the setting enums as their variant names and dropdown items as
+//!     `{ "label": .., "value": <TextValue> }`. Unset optional fields are left out.
+//!   - `state`: the runtime state the game saved, by field name without the leading `__`, as the
+//!     attribute text (see [`state`][super::components::state] to decode it).
+//!   - `hidden`: names of the unconnected input and output fields the XML leaves out. The game
+//!     writes all of them, so this is only there for microcontrollers made with this crate.
+//!   - `saved`: values the game cached on input and output fields, by field name. `value` is the
+//!     `v` attribute and `v` the attributes of the `v` element.
+//!
+//!   `settings`, `state`, `hidden` and `saved` are left out when empty.
+//! - `wires` has every connection, from an output to an input of another component (or IO node
+//!   component), by 0-based index into [`ComponentIODef`]'s lists. An input takes one wire.
+//!
+//! Only present when needed:
+//! - `bridge_order`: ids of the IO nodes' components in the order the XML stores them, if it
+//!   isn't the order of `io`.
+//! - `data`: the main group's [`GroupData`], with `type`, `inputs` and `outputs`. Each input and
+//!   output has `label`, `type` and `position` like an IO node.
+//! - `groups`: nested [`Group`]s with `tag`, `data`, `components`, `bridge_components`, `wires`,
+//!   `groups` and `group_states`.
+//! - `group_states`: see [`Microcontroller::group_states`], each with its `id`.
+//!
+//! [`ComponentIODef`]: super::components::ComponentIODef
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+use super::{
+    components::{
+        BridgeComponent, BridgeComponentType, Component, ComponentConnection, ComponentType,
+        CompositeChannel, ConnectionV, DropdownItem, PulseMode, Slot, TextValue, TimerUnits,
+        TooltipMode, UpDownCounterMode,
+    },
+    group::{Group, GroupData, GroupDataNode, GroupState},
+    mc_serde::microcontroller::IONodeType,
+    IONode, IONodeDesign, MCValidationError, Microcontroller,
+};
+use crate::util::serde_utils::PositionXY;
+
+/// Value of the `format` field.
+pub const FORMAT: &str = "sw-rs/microcontroller";
+/// The format version written by [`Microcontroller::to_json_string`].
+pub const VERSION: u32 = 1;
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum JsonError {
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error("Not a microcontroller document (format {0:?})")]
+    WrongFormat(String),
+    #[error("Unsupported format version {0}, expected {VERSION}")]
+    UnsupportedVersion(u32),
+    #[error("Unknown {what} {value:?}")]
+    UnknownName { what: &'static str, value: String },
+    #[error("Component {component_id} has unknown type {typ:?}")]
+    UnknownComponentType { component_id: u32, typ: String },
+    #[error("Invalid setting {setting:?} on component {component_id}: {message}")]
+    InvalidSetting {
+        component_id: u32,
+        setting: String,
+        message: String,
+    },
+    #[error("Component {component_id} has no input or output {slot:?}")]
+    UnknownSlot { component_id: u32, slot: String },
+    #[error("Wire to input {input} of component {component_id}, which doesn't exist")]
+    InvalidWire { component_id: u32, input: u8 },
+    #[error("Input {input} of component {component_id} has more than one wire")]
+    DuplicateWire { component_id: u32, input: u8 },
+    #[error(transparent)]
+    ValidationError(#[from] MCValidationError),
+}
+
+/// A setting field type that has a JSON representation.
+pub(crate) trait JsonSetting: Sized {
+    /// Converts to JSON, [`None`] leaves the setting out.
+    fn to_json(&self) -> Option<Value>;
+    /// Converts from JSON, `json` is [`None`] if the setting was left out.
+    fn from_json(json: Option<Value>) -> Result<Self, String>;
+}
+
+/// Component types that can be converted to and from JSON, implemented by `components!`.
+pub(crate) trait JsonComponent: Sized {
+    /// Name of the variant.
+    fn type_name(&self) -> &'static str;
+    /// Name and JSON value of every non-IO field.
+    fn settings_json(&self) -> Vec<(&'static str, Option<Value>)>;
+    /// Creates the variant called `name` with unconnected inputs and outputs, reading each non-IO
+    /// field with `setting`.
+    ///
+    /// Returns [`None`] if there's no such variant, or the field that failed with the reason.
+    fn from_settings_json(
+        name: &str,
+        setting: impl FnMut(&'static str) -> Option<Value>,
+    ) -> Option<Result<Self, (&'static str, String)>>;
+    /// Every input and output with its field name.
+    fn slots(&self) -> Vec<(&'static str, &dyn Slot)>;
+    /// Every input and output with its field name, mutably.
+    fn slots_mut(&mut self) -> Vec<(&'static str, &mut dyn Slot)>;
+    /// The input connections.
+    fn inputs_json(&self) -> Vec<&Option<ComponentConnection>>;
+    /// The input connections, mutably.
+    fn inputs_json_mut(&mut self) -> Vec<&mut Option<ComponentConnection>>;
+}
+
+fn required(json: Option<Value>) -> Result<Value, String> {
+    json.ok_or_else(|| "missing".to_string())
+}
+
+fn expected(what: &str, json: &Value) -> String {
+    format!("expected {what}, found {json}")
+}
+
+impl JsonSetting for String {
+    fn to_json(&self) -> Option<Value> {
+        Some(Value::String(self.clone()))
+    }
+
+    fn from_json(json: Option<Value>) -> Result<Self, String> {
+        match required(json)? {
+            Value::String(s) => Ok(s),
+            json => Err(expected("a string", &json)),
+        }
+    }
+}
+
+impl JsonSetting for Option<String> {
+    fn to_json(&self) -> Option<Value> {
+        self.as_ref().and_then(JsonSetting::to_json)
+    }
+
+    fn from_json(json: Option<Value>) -> Result<Self, String> {
+        match json {
+            None | Some(Value::Null) => Ok(None),
+            json => String::from_json(json).map(Some),
+        }
+    }
+}
+
+impl JsonSetting for bool {
+    fn to_json(&self) -> Option<Value> {
+        Some(Value::Bool(*self))
+    }
+
+    fn from_json(json: Option<Value>) -> Result<Self, String> {
+        match required(json)? {
+            Value::Bool(b) => Ok(b),
+            json => Err(expected("a bool", &json)),
+        }
+    }
+}
+
+macro_rules! json_int {
+    ($($t:ty),*) => {$(
+        impl JsonSetting for $t {
+            fn to_json(&self) -> Option<Value> {
+                Some(Value::from(*self))
+            }
+
+            fn from_json(json: Option<Value>) -> Result<Self, String> {
+                let json = required(json)?;
+                json.as_u64()
+                    .and_then(|n| n.try_into().ok())
+                    .ok_or_else(|| expected(concat!("a ", stringify!($t)), &json))
+            }
+        }
+    )*};
+}
+
+json_int!(u8, u32);
+
+impl JsonSetting for f32 {
+    fn to_json(&self) -> Option<Value> {
+        Some(float_json(f64::from(*self)))
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn from_json(json: Option<Value>) -> Result<Self, String> {
+        float_from_json(&required(json)?).map(|f| f as f32)
+    }
+}
+
+/// Numbers that JSON can't represent are stored as strings.
+fn float_json(f: f64) -> Value {
+    if f.is_finite() {
+        Value::from(f)
+    } else {
+        Value::String(f.to_string())
+    }
+}
+
+fn float_from_json(json: &Value) -> Result<f64, String> {
+    match json {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.parse().ok().filter(|f: &f64| !f.is_finite()),
+        _ => None,
+    }
+    .ok_or_else(|| expected("a number", json))
+}
+
+impl JsonSetting for TextValue {
+    fn to_json(&self) -> Option<Value> {
+        let mut map = Map::new();
+        map.insert("text".into(), self.text().into());
+        map.insert("value".into(), float_json(self.value()));
+        Some(Value::Object(map))
+    }
+
+    fn from_json(json: Option<Value>) -> Result<Self, String> {
+        let json = required(json)?;
+        let err = || expected("{ \"text\": .., \"value\": .. }", &json);
+        let text = json.get("text").and_then(Value::as_str).ok_or_else(err)?;
+        let value = float_from_json(json.get("value").ok_or_else(err)?)?;
+        Ok(TextValue::from_parts(text.into(), value))
+    }
+}
+
+impl JsonSetting for Vec<DropdownItem> {
+    fn to_json(&self) -> Option<Value> {
+        let items = self.iter().map(|item| {
+            let mut map = Map::new();
+            map.insert("label".into(), item.label.clone().into());
+            map.insert("value".into(), item.value.to_json().unwrap_or_default());
+            Value::Object(map)
+        });
+        Some(Value::Array(items.collect()))
+    }
+
+    fn from_json(json: Option<Value>) -> Result<Self, String> {
+        let json = required(json)?;
+        let Value::Array(items) = json else {
+            return Err(expected("a list", &json));
+        };
+        items
+            .into_iter()
+            .map(|mut item| {
+                let label = String::from_json(item.get_mut("label").map(Value::take))?;
+                let value = TextValue::from_json(item.get_mut("value").map(Value::take))?;
+                Ok(DropdownItem { label, value })
+            })
+            .collect()
+    }
+}
+
+impl JsonSetting for CompositeChannel {
+    fn to_json(&self) -> Option<Value> {
+        Some(match self {
+            CompositeChannel::Variable => "variable".into(),
+            CompositeChannel::Fixed(i) => (u16::from(*i) + 1).into(),
+        })
+    }
+
+    fn from_json(json: Option<Value>) -> Result<Self, String> {
+        let json = required(json)?;
+        if json == "variable" {
+            return Ok(CompositeChannel::Variable);
+        }
+        json.as_u64()
+            .and_then(|n| CompositeChannel::from_number(n.try_into().ok()?))
+            .ok_or_else(|| expected("a channel 1..=32 or \"variable\"", &json))
+    }
+}
+
+macro_rules! json_enum {
+    ($($t:ident { $($v:ident),* $(,)? })*) => {$(
+        impl JsonSetting for $t {
+            fn to_json(&self) -> Option<Value> {
+                Some(match self {
+                    $($t::$v => stringify!($v).into(),)*
+                })
+            }
+
+            fn from_json(json: Option<Value>) -> Result<Self, String> {
+                let json = required(json)?;
+                match json.as_str() {
+                    $(Some(stringify!($v)) => Ok($t::$v),)*
+                    _ => Err(expected(concat!("a ", stringify!($t)), &json)),
+                }
+            }
+        }
+    )*};
+}
+
+json_enum! {
+    TooltipMode { Always, IfError, IfNoError }
+    TimerUnits { Seconds, Ticks }
+    UpDownCounterMode { Unclamped, Clamped }
+    PulseMode { OffToOn, OnToOff, Always }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct PosJson {
+    x: f32,
+    y: f32,
+}
+
+impl From<&PositionXY> for PosJson {
+    fn from(p: &PositionXY) -> Self {
+        Self { x: p.x, y: p.y }
+    }
+}
+
+impl From<PosJson> for PositionXY {
+    fn from(p: PosJson) -> Self {
+        Self { x: p.x, y: p.y }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct SavedJson {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    value: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    v: Option<Map<String, Value>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ComponentJson {
+    id: u32,
+    #[serde(rename = "type")]
+    typ: String,
+    #[serde(default)]
+    pos: PosJson,
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    settings: Map<String, Value>,
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    state: Map<String, Value>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    hidden: Vec<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    saved: BTreeMap<String, SavedJson>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct WireFrom {
+    component: u32,
+    output: u8,
+}
+
+#[derive(Serialize, Deserialize)]
+struct WireTo {
+    component: u32,
+    input: u8,
+}
+
+#[derive(Serialize, Deserialize)]
+struct WireJson {
+    from: WireFrom,
+    to: WireTo,
+}
+
+#[derive(Serialize, Deserialize)]
+struct IoJson {
+    node_id: u32,
+    label: String,
+    description: String,
+    #[serde(rename = "type")]
+    typ: String,
+    mode: String,
+    position: PosJson,
+    component: ComponentJson,
+}
+
+#[derive(Serialize, Deserialize)]
+struct DataNodeJson {
+    label: String,
+    #[serde(rename = "type")]
+    typ: String,
+    #[serde(default)]
+    position: PosJson,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct DataJson {
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    typ: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    inputs: Vec<DataNodeJson>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    outputs: Vec<DataNodeJson>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct GroupStateJson {
+    id: u32,
+}
+
+impl DataJson {
+    fn is_empty(&self) -> bool {
+        self.typ.is_none() && self.inputs.is_empty() && self.outputs.is_empty()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct GroupJson {
+    tag: String,
+    #[serde(default, skip_serializing_if = "DataJson::is_empty")]
+    data: DataJson,
+    #[serde(default)]
+    components: Vec<ComponentJson>,
+    #[serde(default)]
+    bridge_components: Vec<ComponentJson>,
+    #[serde(default)]
+    wires: Vec<WireJson>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    groups: Vec<GroupJson>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    group_states: Vec<GroupStateJson>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct MicrocontrollerJson {
+    format: String,
+    version: u32,
+    name: String,
+    description: String,
+    width: u8,
+    length: u8,
+    icon: [u16; 16],
+    id_counter: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id_counter_node: Option<u32>,
+    #[serde(default, skip_serializing_if = "DataJson::is_empty")]
+    data: DataJson,
+    #[serde(default)]
+    io: Vec<IoJson>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    bridge_order: Option<Vec<u32>>,
+    #[serde(default)]
+    components: Vec<ComponentJson>,
+    #[serde(default)]
+    wires: Vec<WireJson>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    groups: Vec<GroupJson>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    group_states: Vec<GroupStateJson>,
+}
+
+/// Only peeks at the header, so a newer version gets a version error instead of a parse error.
+#[derive(Deserialize)]
+struct Header {
+    #[serde(default)]
+    format: String,
+    #[serde(default)]
+    version: u32,
+}
+
+impl From<&GroupDataNode> for DataNodeJson {
+    fn from(node: &GroupDataNode) -> Self {
+        Self {
+            label: node.label.clone(),
+            typ: node.typ.to_string(),
+            position: (&node.position).into(),
+        }
+    }
+}
+
+impl TryFrom<DataNodeJson> for GroupDataNode {
+    type Error = JsonError;
+
+    fn try_from(node: DataNodeJson) -> Result<Self, JsonError> {
+        Ok(Self {
+            label: node.label,
+            typ: node
+                .typ
+                .parse()
+                .map_err(|_| JsonError::UnknownName { what: "type", value: node.typ.clone() })?,
+            position: node.position.into(),
+        })
+    }
+}
+
+impl From<&GroupData> for DataJson {
+    fn from(data: &GroupData) -> Self {
+        Self {
+            typ: data.typ.clone(),
+            inputs: data.inputs.iter().map(Into::into).collect(),
+            outputs: data.outputs.iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl TryFrom<DataJson> for GroupData {
+    type Error = JsonError;
+
+    fn try_from(data: DataJson) -> Result<Self, JsonError> {
+        Ok(Self {
+            typ: data.typ,
+            inputs: data
+                .inputs
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+            outputs: data
+                .outputs
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+fn group_states_json(states: &[GroupState]) -> Vec<GroupStateJson> {
+    states.iter().map(|s| GroupStateJson { id: s.id }).collect()
+}
+
+fn group_states_from_json(json: Vec<GroupStateJson>) -> Vec<GroupState> {
+    json.into_iter().map(|s| GroupState { id: s.id }).collect()
+}
+
+fn v_json(v: &ConnectionV) -> Result<Map<String, Value>, JsonError> {
+    let Value::Object(map) = serde_json::to_value(v)? else {
+        unreachable!("ConnectionV serializes to a map");
+    };
+    Ok(map
+        .into_iter()
+        .map(|(k, v)| (k.trim_start_matches('@').to_string(), v))
+        .collect())
+}
+
+fn v_from_json(map: Map<String, Value>) -> Result<ConnectionV, JsonError> {
+    let map: Map<_, _> = map.into_iter().map(|(k, v)| (format!("@{k}"), v)).collect();
+    Ok(serde_json::from_value(Value::Object(map))?)
+}
+
+fn component_json<C: JsonComponent>(
+    id: u32,
+    pos: &PositionXY,
+    component: &C,
+) -> Result<ComponentJson, JsonError> {
+    let mut settings = Map::new();
+    let mut state = Map::new();
+    for (name, value) in component.settings_json() {
+        let Some(value) = value else { continue };
+        match name.strip_prefix("__") {
+            Some(name) => state.insert(name.into(), value),
+            None => settings.insert(name.into(), value),
+        };
+    }
+
+    let mut hidden = vec![];
+    let mut saved = BTreeMap::new();
+    for (name, slot) in component.slots() {
+        if slot.hidden() {
+            hidden.push(name.into());
+        }
+        let (value, v) = slot.saved();
+        if value.is_some() || v.is_some() {
+            let v = v.map(v_json).transpose()?;
+            saved.insert(name.into(), SavedJson { value: value.cloned(), v });
+        }
+    }
+
+    Ok(ComponentJson {
+        id,
+        typ: component.type_name().into(),
+        pos: pos.into(),
+        settings,
+        state,
+        hidden,
+        saved,
+    })
+}
+
+fn component_from_json<C: JsonComponent>(
+    json: ComponentJson,
+) -> Result<(u32, PositionXY, C), JsonError> {
+    let ComponentJson {
+        id,
+        typ,
+        pos,
+        mut settings,
+        mut state,
+        hidden,
+        saved,
+    } = json;
+
+    let mut component = C::from_settings_json(&typ, |name| match name.strip_prefix("__") {
+        Some(name) => state.remove(name),
+        None => settings.remove(name),
+    })
+    .ok_or(JsonError::UnknownComponentType { component_id: id, typ })?
+    .map_err(|(setting, message)| JsonError::InvalidSetting {
+        component_id: id,
+        setting: setting.trim_start_matches("__").into(),
+        message,
+    })?;
+
+    if let Some(setting) = settings.keys().chain(state.keys()).next() {
+        return Err(JsonError::InvalidSetting {
+            component_id: id,
+            setting: setting.clone(),
+            message: "unknown setting".into(),
+        });
+    }
+
+    let mut slots = component.slots_mut();
+    let unknown = hidden
+        .iter()
+        .chain(saved.keys())
+        .find(|name| !slots.iter().any(|(n, _)| n == name));
+    if let Some(slot) = unknown {
+        return Err(JsonError::UnknownSlot { component_id: id, slot: slot.clone() });
+    }
+    for (name, slot) in &mut slots {
+        slot.set_hidden(hidden.iter().any(|h| h == name));
+    }
+    for (name, saved) in saved {
+        if let Some((_, slot)) = slots.iter_mut().find(|(n, _)| *n == name) {
+            slot.set_saved(saved.value, saved.v.map(v_from_json).transpose()?);
+        }
+    }
+    drop(slots);
+
+    Ok((id, pos.into(), component))
+}
+
+/// Lists the wires going into the given components.
+fn wires_json<'a, C: JsonComponent + 'a>(
+    components: impl IntoIterator<Item = (u32, &'a C)>,
+) -> Vec<WireJson> {
+    let mut wires = vec![];
+    for (id, component) in components {
+        for (input, conn) in (0..).zip(component.inputs_json()) {
+            if let Some(conn) = conn {
+                wires.push(WireJson {
+                    from: WireFrom {
+                        component: conn.component_id,
+                        output: conn.node_index,
+                    },
+                    to: WireTo { component: id, input },
+                });
+            }
+        }
+    }
+    wires
+}
+
+/// Connects the inputs of `components` and `bridge_components` with the given wires.
+fn connect(
+    wires: Vec<WireJson>,
+    components: &mut [Component],
+    mut bridge_components: Vec<&mut BridgeComponent>,
+) -> Result<(), JsonError> {
+    for WireJson { from, to } in wires {
+        let inputs = if let Some(c) = components.iter_mut().find(|c| c.id == to.component) {
+            c.component.inputs_json_mut()
+        } else if let Some(c) = bridge_components.iter_mut().find(|c| c.id == to.component) {
+            c.component.inputs_json_mut()
+        } else {
+            return Err(JsonError::InvalidWire { component_id: to.component, input: to.input });
+        };
+        let Some(input) = inputs.into_iter().nth(usize::from(to.input)) else {
+            return Err(JsonError::InvalidWire { component_id: to.component, input: to.input });
+        };
+        if input.is_some() {
+            return Err(JsonError::DuplicateWire { component_id: to.component, input: to.input });
+        }
+        *input = Some(ComponentConnection {
+            component_id: from.component,
+            node_index: from.output,
+        });
+    }
+    Ok(())
+}
+
+fn components_json(components: &[Component]) -> Result<Vec<ComponentJson>, JsonError> {
+    components
+        .iter()
+        .map(|c| component_json(c.id, &c.pos, &c.component))
+        .collect()
+}
+
+fn components_from_json(json: Vec<ComponentJson>) -> Result<Vec<Component>, JsonError> {
+    json.into_iter()
+        .map(|c| {
+            let (id, pos, component) = component_from_json::<ComponentType>(c)?;
+            Ok(Component { id, pos, component })
+        })
+        .collect()
+}
+
+fn bridge_component_from_json(json: ComponentJson) -> Result<BridgeComponent, JsonError> {
+    let (id, pos, component) = component_from_json::<BridgeComponentType>(json)?;
+    Ok(BridgeComponent { id, pos, component })
+}
+
+impl TryFrom<&Group> for GroupJson {
+    type Error = JsonError;
+
+    fn try_from(g: &Group) -> Result<Self, JsonError> {
+        let components = g.components.iter().map(|c| (c.id, &c.component));
+        let bridges = g.bridge_components.iter().map(|c| (c.id, &c.component));
+        let mut wires = wires_json(components);
+        wires.extend(wires_json(bridges));
+
+        Ok(Self {
+            tag: g.tag.clone(),
+            data: (&g.data).into(),
+            components: components_json(&g.components)?,
+            bridge_components: g
+                .bridge_components
+                .iter()
+                .map(|c| component_json(c.id, &c.pos, &c.component))
+                .collect::<Result<_, _>>()?,
+            wires,
+            groups: g
+                .groups
+                .iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+            group_states: group_states_json(&g.group_states),
+        })
+    }
+}
+
+impl TryFrom<GroupJson> for Group {
+    type Error = JsonError;
+
+    fn try_from(g: GroupJson) -> Result<Self, JsonError> {
+        let mut components = components_from_json(g.components)?;
+        let mut bridge_components: Vec<_> = g
+            .bridge_components
+            .into_iter()
+            .map(bridge_component_from_json)
+            .collect::<Result<_, _>>()?;
+        connect(
+            g.wires,
+            &mut components,
+            bridge_components.iter_mut().collect(),
+        )?;
+
+        Ok(Self {
+            tag: g.tag,
+            data: g.data.try_into()?,
+            components,
+            bridge_components,
+            groups: g
+                .groups
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+            group_states: group_states_from_json(g.group_states),
+        })
+    }
+}
+
+impl Microcontroller {
+    /// Converts this microcontroller to the JSON format described in [`json`][super::json].
+    ///
+    /// # Errors
+    /// Returns an [`Err(JsonError)`] if the microcontroller was invalid.
+    pub fn to_json_string(&self) -> Result<String, JsonError> {
+        self.validate()?;
+
+        let io = self
+            .io
+            .iter()
+            .map(|ion| {
+                Ok(IoJson {
+                    node_id: ion.design.node_id,
+                    label: ion.design.label.clone(),
+                    description: ion.design.description.clone(),
+                    typ: ion.design.typ.to_string(),
+                    mode: match ion.design.mode {
+                        IONodeType::Input => "input",
+                        IONodeType::Output => "output",
+                    }
+                    .into(),
+                    position: (&ion.design.position).into(),
+                    component: component_json(ion.logic.id, &ion.logic.pos, &ion.logic.component)?,
+                })
+            })
+            .collect::<Result<_, JsonError>>()?;
+
+        let io_order: Vec<_> = self.io.iter().map(|ion| ion.logic.id).collect();
+        let components = self.components.iter().map(|c| (c.id, &c.component));
+        let bridges = self
+            .io
+            .iter()
+            .map(|ion| (ion.logic.id, &ion.logic.component));
+        let mut wires = wires_json(components);
+        wires.extend(wires_json(bridges));
+
+        let json = MicrocontrollerJson {
+            format: FORMAT.into(),
+            version: VERSION,
+            name: self.name.clone(),
+            description: self.description.clone(),
+            width: self.width,
+            length: self.length,
+            icon: self.icon,
+            id_counter: self.id_counter,
+            id_counter_node: self.id_counter_node,
+            data: (&self.data).into(),
+            io,
+            bridge_order: (self.components_bridge_order != io_order)
+                .then(|| self.components_bridge_order.clone()),
+            components: components_json(&self.components)?,
+            wires,
+            groups: self
+                .groups
+                .iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+            group_states: group_states_json(&self.group_states),
+        };
+        Ok(serde_json::to_string_pretty(&json)?)
+    }
+
+    /// Reads a microcontroller from the JSON format described in [`json`][super::json].
+    ///
+    /// # Errors
+    /// Returns an [`Err(JsonError)`] if the JSON isn't a microcontroller of a supported version,
+    /// or if the microcontroller was invalid.
+    pub fn from_json_str(json: &str) -> Result<Self, JsonError> {
+        let header: Header = serde_json::from_str(json)?;
+        if header.format != FORMAT {
+            return Err(JsonError::WrongFormat(header.format));
+        }
+        if header.version != VERSION {
+            return Err(JsonError::UnsupportedVersion(header.version));
+        }
+        let json: MicrocontrollerJson = serde_json::from_str(json)?;
+
+        let io: Vec<IONode> = json
+            .io
+            .into_iter()
+            .map(|ion| {
+                Ok(IONode {
+                    design: IONodeDesign {
+                        node_id: ion.node_id,
+                        label: ion.label,
+                        description: ion.description,
+                        typ: ion.typ.parse().map_err(|_| JsonError::UnknownName {
+                            what: "type",
+                            value: ion.typ.clone(),
+                        })?,
+                        mode: match ion.mode.as_str() {
+                            "input" => IONodeType::Input,
+                            "output" => IONodeType::Output,
+                            _ => {
+                                return Err(JsonError::UnknownName {
+                                    what: "mode",
+                                    value: ion.mode,
+                                })
+                            },
+                        },
+                        position: ion.position.into(),
+                    },
+                    logic: bridge_component_from_json(ion.component)?,
+                })
+            })
+            .collect::<Result<_, JsonError>>()?;
+
+        let mut mc = Self {
+            name: json.name,
+            description: json.description,
+            width: json.width,
+            length: json.length,
+            id_counter: json.id_counter,
+            id_counter_node: json.id_counter_node,
+            icon: json.icon,
+            data: json.data.try_into()?,
+            components_bridge_order: json
+                .bridge_order
+                .unwrap_or_else(|| io.iter().map(|ion| ion.logic.id).collect()),
+            io,
+            components: components_from_json(json.components)?,
+            groups: json
+                .groups
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+            group_states: group_states_from_json(json.group_states),
+        };
+        connect(
+            json.wires,
+            &mut mc.components,
+            mc.io.iter_mut().map(|ion| &mut ion.logic).collect(),
+        )?;
+
+        mc.validate()?;
+        Ok(mc)
+    }
+}
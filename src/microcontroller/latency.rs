@@ -0,0 +1,240 @@
+//! Module containing a latency analysis of a [`Microcontroller`]'s logic, in ticks.
+//!
+//! Every component, including the IO node bridges, reads its inputs from the previous tick, so
+//! a path through `n` components takes `n` ticks: a value fed into an input before a tick is
+//! visible at an output after `n` ticks (see [`simulator`][super::simulator]).
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::util::AnyComponentRef;
+
+use super::{
+    components::ComponentType, graph::Graph, mc_serde::microcontroller::IONodeType, Microcontroller,
+};
+
+/// Latency from one input [`IONode`][super::IONode] to one output [`IONode`][super::IONode].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PathLatency {
+    /// Node id of the input.
+    pub input: u32,
+    /// Node id of the output.
+    pub output: u32,
+    /// Ticks until a change at the input first reaches the output.
+    pub shortest: u32,
+    /// Ticks until a change at the input stops reaching the output.
+    ///
+    /// [`None`] if a path goes through a feedback loop, see [`loops`][Self::loops].
+    pub longest: Option<u32>,
+    /// Component ids on the longest path (or the shortest one if the longest is unbounded),
+    /// from the input bridge to the output bridge.
+    pub critical_path: Vec<u32>,
+    /// Indices into [`Latency::loops`] of the feedback loops between the input and the output.
+    pub loops: Vec<usize>,
+}
+
+/// A group of components that feed back into each other.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FeedbackLoop {
+    /// Component ids in the loop.
+    pub components: Vec<u32>,
+    /// Ids of the memory elements (registers, latches, timers...) in the loop.
+    pub memory: Vec<u32>,
+}
+
+impl FeedbackLoop {
+    /// Whether this loop has a memory element in it.
+    ///
+    /// Loops without one just pass values around every tick, which is usually a mistake.
+    #[must_use]
+    pub fn is_buffered(&self) -> bool {
+        !self.memory.is_empty()
+    }
+}
+
+/// Result of [`Microcontroller::latency`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Latency {
+    /// Every (input, output) pair where the output depends on the input, in IO node order.
+    pub paths: Vec<PathLatency>,
+    /// Every feedback loop in the logic.
+    pub loops: Vec<FeedbackLoop>,
+}
+
+impl Latency {
+    /// Gets the latency between the input and output nodes with the given node ids.
+    ///
+    /// Returns [`None`] if the output doesn't depend on the input.
+    #[must_use]
+    pub fn get(&self, input: u32, output: u32) -> Option<&PathLatency> {
+        self.paths
+            .iter()
+            .find(|p| p.input == input && p.output == output)
+    }
+
+    /// Gets the feedback loops that have no memory element in them.
+    pub fn unbuffered_loops(&self) -> impl Iterator<Item = &FeedbackLoop> {
+        self.loops.iter().filter(|l| !l.is_buffered())
+    }
+}
+
+/// Whether a component keeps state between ticks, like in the simulator.
+fn is_memory(component: &ComponentType) -> bool {
+    matches!(
+        component,
+        ComponentType::MemoryRegister { .. }
+            | ComponentType::SRLatch { .. }
+            | ComponentType::JKFlipFlop { .. }
+            | ComponentType::Capacitor { .. }
+            | ComponentType::Blinker { .. }
+            | ComponentType::PushToToggle { .. }
+            | ComponentType::Pulse { .. }
+            | ComponentType::TimerTON { .. }
+            | ComponentType::TimerTOF { .. }
+            | ComponentType::TimerRTO { .. }
+            | ComponentType::TimerRTF { .. }
+            | ComponentType::PIDController { .. }
+            | ComponentType::PIDControllerAdvanced { .. }
+            | ComponentType::Delta { .. }
+            | ComponentType::UpDownCounter { .. }
+    )
+}
+
+/// Follows `parents` back from `id`.
+fn trace(parents: &HashMap<u32, u32>, mut id: u32) -> Vec<u32> {
+    let mut path = vec![id];
+    while let Some(&parent) = parents.get(&id) {
+        path.push(parent);
+        id = parent;
+    }
+    path.reverse();
+    path
+}
+
+impl Microcontroller {
+    /// Computes how many ticks it takes for each input to affect each output, see
+    /// [`Latency`].
+    #[must_use]
+    pub fn latency(&self) -> Latency {
+        let g = self.graph();
+        let sccs = g.strongly_connected_components();
+
+        let mut loops = vec![];
+        let mut loop_of: HashMap<u32, usize> = HashMap::new();
+        for scc in &sccs {
+            let is_loop = match scc[..] {
+                [id] => g.predecessors(id).contains(&id),
+                _ => true,
+            };
+            if !is_loop {
+                continue;
+            }
+            let memory = scc
+                .iter()
+                .copied()
+                .filter(|id| {
+                    matches!(g.get(*id), Some(AnyComponentRef::Component(c)) if is_memory(&c.component))
+                })
+                .collect();
+            loop_of.extend(scc.iter().map(|&id| (id, loops.len())));
+            loops.push(FeedbackLoop { components: scc.clone(), memory });
+        }
+        let order: Vec<u32> = sccs.into_iter().flatten().collect();
+
+        let inputs: Vec<_> = self
+            .io
+            .iter()
+            .filter(|ion| ion.design.mode == IONodeType::Input)
+            .collect();
+        // components on some path to each output, including the output bridge itself
+        let outputs: Vec<_> = self
+            .io
+            .iter()
+            .filter(|ion| ion.design.mode == IONodeType::Output)
+            .map(|ion| {
+                let mut upstream: HashSet<u32> = g.upstream(ion.logic.id).into_iter().collect();
+                upstream.insert(ion.logic.id);
+                (ion, upstream)
+            })
+            .collect();
+
+        let mut paths = vec![];
+        for input in inputs {
+            let from = input.logic.id;
+
+            // breadth first for the shortest paths
+            let mut ticks = HashMap::from([(from, 1)]);
+            let mut parents = HashMap::new();
+            let mut queue = VecDeque::from([from]);
+            while let Some(id) = queue.pop_front() {
+                for &succ in g.successors(id) {
+                    if !ticks.contains_key(&succ) {
+                        ticks.insert(succ, ticks[&id] + 1);
+                        parents.insert(succ, id);
+                        queue.push_back(succ);
+                    }
+                }
+            }
+
+            for (output, upstream) in &outputs {
+                let to = output.logic.id;
+                let Some(&shortest) = ticks.get(&to) else {
+                    continue;
+                };
+
+                // components on some path from the input to the output
+                let between = |id: u32| ticks.contains_key(&id) && upstream.contains(&id);
+
+                let mut path_loops: Vec<usize> = upstream
+                    .iter()
+                    .filter(|&&id| ticks.contains_key(&id))
+                    .filter_map(|id| loop_of.get(id).copied())
+                    .collect();
+                path_loops.sort_unstable();
+                path_loops.dedup();
+
+                let (longest, critical_path) = if path_loops.is_empty() {
+                    let (longest, path) = longest_path(&g, &order, between, from, to);
+                    (Some(longest), path)
+                } else {
+                    (None, trace(&parents, to))
+                };
+
+                paths.push(PathLatency {
+                    input: input.design.node_id,
+                    output: output.design.node_id,
+                    shortest,
+                    longest,
+                    critical_path,
+                    loops: path_loops,
+                });
+            }
+        }
+
+        Latency { paths, loops }
+    }
+}
+
+/// Finds the longest path from `from` to `to` through the (loop free) `between` components,
+/// visiting them in topological `order`.
+fn longest_path(
+    g: &Graph,
+    order: &[u32],
+    between: impl Fn(u32) -> bool,
+    from: u32,
+    to: u32,
+) -> (u32, Vec<u32>) {
+    let mut ticks = HashMap::from([(from, 1)]);
+    let mut parents = HashMap::new();
+    for &id in order.iter().filter(|&&id| between(id)) {
+        let Some(&t) = ticks.get(&id) else {
+            continue;
+        };
+        for &succ in g.successors(id) {
+            if between(succ) && ticks.get(&succ).is_none_or(|&s| s <= t) {
+                ticks.insert(succ, t + 1);
+                parents.insert(succ, id);
+            }
+        }
+    }
+    (ticks[&to], trace(&parents, to))
+}
@@ -0,0 +1,320 @@
+//! Module containing automatic layout of logic component positions.
+//!
+//! Uses a simple layered (Sugiyama style) approach: components are put in columns by data-flow depth,
+//! columns are reordered to reduce wire crossings, then stacked so nodes don't overlap.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::util::{serde_utils::PositionXY, AnyComponentRef};
+
+use super::{graph::Graph, mc_serde::microcontroller::IONodeType, Microcontroller};
+
+/// Horizontal distance between columns.
+pub const COLUMN_SPACING: f32 = 2.0;
+/// Vertical gap between nodes in the same column.
+pub const NODE_GAP: f32 = 0.5;
+/// Width of a node.
+pub const NODE_WIDTH: f32 = 1.0;
+/// Vertical space taken per input/output port.
+pub const PORT_SPACING: f32 = 0.25;
+
+const CROSSING_SWEEPS: usize = 4;
+
+/// Which components [`Microcontroller::layout`] should move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LayoutMode {
+    /// Lay out every component and IO bridge.
+    All,
+    /// Only place components still at [`PositionXY::default()`], leaving everything else where it is.
+    ///
+    /// If nothing has been positioned yet this is the same as [`LayoutMode::All`].
+    Unpositioned,
+}
+
+/// Gets the size (width, height) a component takes up in the logic view.
+#[must_use]
+pub fn footprint(c: &AnyComponentRef) -> (f32, f32) {
+    let def = c.io_def();
+    #[allow(clippy::cast_precision_loss)]
+    let ports = def.inputs.len().max(def.outputs.len()).max(1) as f32;
+    (NODE_WIDTH, ports * PORT_SPACING + PORT_SPACING)
+}
+
+struct Rect {
+    x: f32,
+    y: f32,
+    w: f32,
+    h: f32,
+}
+
+impl Rect {
+    fn around(pos: &PositionXY, (w, h): (f32, f32)) -> Self {
+        Self { x: pos.x, y: pos.y, w, h }
+    }
+
+    fn overlaps(&self, o: &Rect) -> bool {
+        (self.x - o.x).abs() * 2.0 < self.w + o.w
+            && (self.y - o.y).abs() * 2.0 < self.h + o.h + NODE_GAP * 2.0
+    }
+}
+
+fn snap(v: f32) -> f32 {
+    (v / PORT_SPACING).round() * PORT_SPACING
+}
+
+impl Microcontroller {
+    /// Sets the [`pos`][super::components::Component::pos] of components and IO bridge components
+    /// based on data-flow depth.
+    ///
+    /// Inputs end up on the left, outputs on the right, with `pos` being the center of each node.
+    /// Components in nested [`groups`][`Self::groups`] aren't moved.
+    pub fn layout(&mut self, mode: LayoutMode) {
+        let g = self.graph();
+        let unpositioned: HashSet<u32> = self
+            .components()
+            .filter(|c| *c.pos() == PositionXY::default())
+            .map(|c| c.id())
+            .collect();
+
+        let positions =
+            if mode == LayoutMode::All || unpositioned.len() == self.components().count() {
+                self.layered_positions(&g)
+            } else {
+                place_unpositioned(&g, &unpositioned)
+            };
+
+        for c in &mut self.components {
+            if let Some(pos) = positions.get(&c.id) {
+                c.pos = pos.clone();
+            }
+        }
+        for ion in &mut self.io {
+            if let Some(pos) = positions.get(&ion.logic.id) {
+                ion.logic.pos = pos.clone();
+            }
+        }
+    }
+
+    fn layered_positions(&self, g: &Graph) -> HashMap<u32, PositionXY> {
+        let bridges = |mode: IONodeType| -> HashSet<u32> {
+            self.io
+                .iter()
+                .filter(|ion| ion.design.mode == mode)
+                .map(|ion| ion.logic.id)
+                .collect()
+        };
+        let inputs = bridges(IONodeType::Input);
+        let outputs = bridges(IONodeType::Output);
+
+        // flattening the SCCs gives an order where only loop edges point backwards
+        let order: Vec<u32> = g
+            .strongly_connected_components()
+            .concat()
+            .into_iter()
+            .filter(|&id| !g.is_nested(id))
+            .collect();
+        let rank: HashMap<u32, usize> = order.iter().enumerate().map(|(i, &id)| (id, i)).collect();
+        let forward_preds = |id: u32| -> Vec<u32> {
+            g.predecessors(id)
+                .iter()
+                .copied()
+                .filter(|p| rank.get(p).is_some_and(|&r| r < rank[&id]))
+                .collect()
+        };
+
+        // longest path layering
+        let mut layer: HashMap<u32, usize> = HashMap::new();
+        for &id in &order {
+            let l = if inputs.contains(&id) {
+                0
+            } else {
+                forward_preds(id)
+                    .iter()
+                    .map(|p| layer[p] + 1)
+                    .max()
+                    .unwrap_or(0)
+            };
+            layer.insert(id, l);
+        }
+
+        // pull sources (constants, properties) next to where they're used
+        for &id in order.iter().rev() {
+            if inputs.contains(&id) || !forward_preds(id).is_empty() {
+                continue;
+            }
+            let succ_layer = g
+                .successors(id)
+                .iter()
+                .copied()
+                .filter(|s| rank.get(s).is_some_and(|&r| r > rank[&id]))
+                .map(|s| layer[&s])
+                .min();
+            if let Some(l) = succ_layer {
+                layer.insert(id, l.saturating_sub(1));
+            }
+        }
+
+        let last = order
+            .iter()
+            .filter(|id| !outputs.contains(id))
+            .map(|id| layer[id] + 1)
+            .max()
+            .unwrap_or(0);
+        for id in &outputs {
+            layer.insert(*id, last);
+        }
+
+        let mut layers: Vec<Vec<u32>> = vec![vec![]; last + 1];
+        for &id in &order {
+            layers[layer[&id]].push(id);
+        }
+
+        reduce_crossings(g, &mut layers);
+
+        let mut positions = HashMap::new();
+        for (l, ids) in layers.iter().enumerate() {
+            #[allow(clippy::cast_precision_loss)]
+            let x = l as f32 * COLUMN_SPACING;
+            let mut top = 0.0;
+            for &id in ids {
+                let (_, h) = g.get(id).map_or((NODE_WIDTH, PORT_SPACING), footprint);
+                positions.insert(id, PositionXY { x, y: snap(top - h / 2.0) });
+                top -= h + NODE_GAP;
+            }
+        }
+
+        positions
+    }
+}
+
+/// Reorders each layer by the barycenter of its neighbours, sweeping down then up.
+fn reduce_crossings(g: &Graph, layers: &mut [Vec<u32>]) {
+    /// Updates the index of every component in `layer` within it.
+    fn index_layer(layer: &[u32], idx: &mut HashMap<u32, f32>) {
+        for (i, &id) in layer.iter().enumerate() {
+            #[allow(clippy::cast_precision_loss)]
+            idx.insert(id, i as f32);
+        }
+    }
+
+    fn sort_layer<'g>(
+        layer: &mut [u32],
+        idx: &HashMap<u32, f32>,
+        neighbours: impl Fn(u32) -> &'g [u32],
+    ) {
+        let mut keyed: Vec<(f32, u32)> = layer
+            .iter()
+            .map(|&id| {
+                let ns: Vec<f32> = neighbours(id)
+                    .iter()
+                    .filter_map(|n| idx.get(n))
+                    .copied()
+                    .collect();
+                #[allow(clippy::cast_precision_loss)]
+                let key = if ns.is_empty() {
+                    idx[&id]
+                } else {
+                    ns.iter().sum::<f32>() / ns.len() as f32
+                };
+                (key, id)
+            })
+            .collect();
+        keyed.sort_by(|a, b| a.0.total_cmp(&b.0));
+        for (slot, (_, id)) in layer.iter_mut().zip(keyed) {
+            *slot = id;
+        }
+    }
+
+    // built once, then only the layer that was just sorted needs updating
+    let mut idx = HashMap::new();
+    for layer in layers.iter() {
+        index_layer(layer, &mut idx);
+    }
+
+    for _ in 0..CROSSING_SWEEPS {
+        for layer in layers.iter_mut().skip(1) {
+            sort_layer(layer, &idx, |id| g.predecessors(id));
+            index_layer(layer, &mut idx);
+        }
+        for layer in layers.iter_mut().rev().skip(1) {
+            sort_layer(layer, &idx, |id| g.successors(id));
+            index_layer(layer, &mut idx);
+        }
+    }
+}
+
+/// Places the given components next to their already positioned neighbours without moving anything else.
+fn place_unpositioned(g: &Graph, unpositioned: &HashSet<u32>) -> HashMap<u32, PositionXY> {
+    let mut placed: HashMap<u32, PositionXY> = g
+        .ids()
+        .into_iter()
+        .filter(|&id| !g.is_nested(id) && !unpositioned.contains(&id))
+        .filter_map(|id| Some((id, g.get(id)?.pos().clone())))
+        .collect();
+    let mut rects: Vec<Rect> = placed
+        .iter()
+        .filter_map(|(id, pos)| Some(Rect::around(pos, footprint(g.get(*id)?))))
+        .collect();
+
+    let max_x = placed.values().map(|p| p.x).fold(f32::MIN, f32::max);
+    let mut new = HashMap::new();
+
+    // go in data-flow order so chains of new components are placed left to right
+    for id in g.strongly_connected_components().concat() {
+        if !unpositioned.contains(&id) {
+            continue;
+        }
+        let Some(c) = g.get(id) else { continue };
+
+        let preds: Vec<&PositionXY> = g
+            .predecessors(id)
+            .iter()
+            .filter_map(|p| placed.get(p))
+            .collect();
+        let succs: Vec<&PositionXY> = g
+            .successors(id)
+            .iter()
+            .filter_map(|s| placed.get(s))
+            .collect();
+
+        let x = if let Some(x) = preds.iter().map(|p| p.x).reduce(f32::max) {
+            x + COLUMN_SPACING
+        } else if let Some(x) = succs.iter().map(|p| p.x).reduce(f32::min) {
+            x - COLUMN_SPACING
+        } else {
+            max_x + COLUMN_SPACING
+        };
+
+        let neighbours: Vec<f32> = preds.iter().chain(&succs).map(|p| p.y).collect();
+        #[allow(clippy::cast_precision_loss)]
+        let y = if neighbours.is_empty() {
+            0.0
+        } else {
+            neighbours.iter().sum::<f32>() / neighbours.len() as f32
+        };
+
+        // search outwards from the ideal spot for a free one
+        let size = footprint(c);
+        let mut step = 0u16;
+        let pos = loop {
+            let offset = f32::from(step.div_ceil(2)) * PORT_SPACING;
+            let y = if step & 1 == 0 {
+                y - offset
+            } else {
+                y + offset
+            };
+            let pos = PositionXY { x: snap(x), y: snap(y) };
+            let rect = Rect::around(&pos, size);
+            if pos != PositionXY::default() && !rects.iter().any(|r| r.overlaps(&rect)) {
+                rects.push(rect);
+                break pos;
+            }
+            step += 1;
+        };
+
+        placed.insert(id, pos.clone());
+        new.insert(id, pos);
+    }
+
+    new
+}
@@ -0,0 +1,341 @@
+//! Module containing [`McLibrary`], an index over a folder of microcontroller files.
+//!
+//! ```no_run
+//! use sw_rs::{microcontroller::library::McLibrary, util::find_microcontroller_folder};
+//!
+//! let lib = McLibrary::load(find_microcontroller_folder().unwrap()).unwrap();
+//! for e in lib.errors() {
+//!     eprintln!("{e}");
+//! }
+//! for entry in lib.by_component_count(100..) {
+//!     println!("{}: {}", entry.path.display(), entry.mc.name);
+//! }
+//! ```
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    io::{self, Write},
+    num::NonZeroUsize,
+    ops::RangeBounds,
+    path::{Path, PathBuf},
+    thread,
+};
+
+use thiserror::Error;
+
+use super::{
+    group::Group, mc_serde::microcontroller::IONodeType, types::Type, MCSerDeError, Microcontroller,
+};
+
+/// Longest file name in bytes (without the extension and suffix) [`McLibrary::save`] will use.
+const MAX_STEM_LEN: usize = 200;
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum LibraryError {
+    #[error("{}: {error}", path.display())]
+    Io { path: PathBuf, error: io::Error },
+    #[error("{}: {error}", path.display())]
+    SerDe {
+        path: PathBuf,
+        error: Box<MCSerDeError>,
+    },
+}
+
+impl LibraryError {
+    /// Gets the path of the file (or folder) this error is about.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        match self {
+            LibraryError::Io { path, .. } | LibraryError::SerDe { path, .. } => path,
+        }
+    }
+}
+
+/// The types of a microcontroller's input and output nodes, ignoring their order and labels.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct IoSignature {
+    /// Types of the input nodes, sorted.
+    pub inputs: Vec<Type>,
+    /// Types of the output nodes, sorted.
+    pub outputs: Vec<Type>,
+}
+
+impl From<&Microcontroller> for IoSignature {
+    fn from(mc: &Microcontroller) -> Self {
+        let types = |mode: IONodeType| {
+            let mut types: Vec<_> = mc
+                .io
+                .iter()
+                .filter(|ion| ion.design.mode == mode)
+                .map(|ion| ion.design.typ)
+                .collect();
+            types.sort_unstable();
+            types
+        };
+        Self {
+            inputs: types(IONodeType::Input),
+            outputs: types(IONodeType::Output),
+        }
+    }
+}
+
+/// A microcontroller in a [`McLibrary`].
+#[derive(Clone, Debug)]
+pub struct LibraryEntry {
+    /// The file it was loaded from or saved to.
+    pub path: PathBuf,
+    /// The microcontroller.
+    pub mc: Microcontroller,
+}
+
+/// Index over the microcontroller files in a folder, usually the game's microprocessors folder
+/// (see [`find_microcontroller_folder`][crate::util::find_microcontroller_folder]).
+#[derive(Debug)]
+pub struct McLibrary {
+    folder: PathBuf,
+    entries: Vec<LibraryEntry>,
+    errors: Vec<LibraryError>,
+    by_name: HashMap<String, Vec<usize>>,
+    by_signature: HashMap<IoSignature, Vec<usize>>,
+    by_component_count: BTreeMap<usize, Vec<usize>>,
+}
+
+fn load_file(path: &Path) -> Result<LibraryEntry, LibraryError> {
+    let src =
+        fs::read_to_string(path).map_err(|error| LibraryError::Io { path: path.into(), error })?;
+    let mc = Microcontroller::from_xml_str(&src)
+        .map_err(|error| LibraryError::SerDe { path: path.into(), error: Box::new(error) })?;
+    Ok(LibraryEntry { path: path.into(), mc })
+}
+
+/// Counts the components of a microcontroller and its nested groups, not counting IO nodes.
+fn component_count(mc: &Microcontroller) -> usize {
+    fn group(g: &Group) -> usize {
+        g.components.len() + g.groups.iter().map(group).sum::<usize>()
+    }
+    mc.components.len() + mc.groups.iter().map(group).sum::<usize>()
+}
+
+/// Makes a file name (without extension) from a microcontroller name that is valid on every
+/// platform the game runs on.
+fn file_stem(name: &str) -> String {
+    let mut stem: String = name
+        .chars()
+        .map(|c| {
+            if c.is_control() || r#"<>:"/\|?*"#.contains(c) {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect();
+    // file name limits are in bytes
+    let mut len = stem.len().min(MAX_STEM_LEN);
+    while !stem.is_char_boundary(len) {
+        len -= 1;
+    }
+    stem.truncate(len);
+    // windows drops trailing dots and spaces
+    let stem = stem.trim_start().trim_end_matches(['.', ' ']);
+
+    // device names are reserved with any extension too
+    let base = stem.split('.').next().unwrap_or_default().trim_end();
+    let upper = base.to_ascii_uppercase();
+    let reserved = matches!(upper.as_str(), "CON" | "PRN" | "AUX" | "NUL")
+        || (upper.len() == 4
+            && (upper.starts_with("COM") || upper.starts_with("LPT"))
+            && upper.ends_with(|c: char| c.is_ascii_digit() && c != '0'));
+
+    if stem.is_empty() {
+        "microcontroller".into()
+    } else if reserved {
+        format!("{base}_{}", &stem[base.len()..])
+    } else {
+        stem.into()
+    }
+}
+
+impl McLibrary {
+    /// Loads every `.xml` file in `folder` (not recursive), parsing them in parallel.
+    ///
+    /// Files that can't be read or parsed don't stop the others from loading, they end up in
+    /// [`errors`][`Self::errors`].
+    ///
+    /// # Errors
+    /// Returns an [`Err(LibraryError)`] if the folder itself can't be read.
+    pub fn load(folder: impl Into<PathBuf>) -> Result<Self, LibraryError> {
+        let folder = folder.into();
+        let io_err = |error| LibraryError::Io { path: folder.clone(), error };
+
+        let mut paths = vec![];
+        for entry in fs::read_dir(&folder).map_err(io_err)? {
+            let path = entry.map_err(io_err)?.path();
+            let xml = path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("xml"));
+            if xml && path.is_file() {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+
+        let threads = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+        let chunk_size = paths.len().div_ceil(threads).max(1);
+        let results: Vec<_> = thread::scope(|s| {
+            let handles: Vec<_> = paths
+                .chunks(chunk_size)
+                .map(|chunk| s.spawn(|| chunk.iter().map(|p| load_file(p)).collect::<Vec<_>>()))
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|h| h.join().unwrap_or_else(|e| std::panic::resume_unwind(e)))
+                .collect()
+        });
+
+        let mut lib = Self {
+            folder,
+            entries: vec![],
+            errors: vec![],
+            by_name: HashMap::new(),
+            by_signature: HashMap::new(),
+            by_component_count: BTreeMap::new(),
+        };
+        for r in results {
+            match r {
+                Ok(entry) => lib.insert(entry),
+                Err(e) => lib.errors.push(e),
+            }
+        }
+        Ok(lib)
+    }
+
+    fn insert(&mut self, entry: LibraryEntry) {
+        let i = self.entries.len();
+        self.by_name
+            .entry(entry.mc.name.clone())
+            .or_default()
+            .push(i);
+        self.by_signature
+            .entry(IoSignature::from(&entry.mc))
+            .or_default()
+            .push(i);
+        self.by_component_count
+            .entry(component_count(&entry.mc))
+            .or_default()
+            .push(i);
+        self.entries.push(entry);
+    }
+
+    fn get_all(&self, indices: Option<&Vec<usize>>) -> Vec<&LibraryEntry> {
+        indices
+            .into_iter()
+            .flatten()
+            .map(|&i| &self.entries[i])
+            .collect()
+    }
+
+    /// Gets the folder this library was loaded from.
+    #[must_use]
+    pub fn folder(&self) -> &Path {
+        &self.folder
+    }
+
+    /// Gets every microcontroller that loaded successfully, sorted by path.
+    ///
+    /// Microcontrollers added with [`save`][`Self::save`] come after the loaded ones.
+    #[must_use]
+    pub fn entries(&self) -> &[LibraryEntry] {
+        &self.entries
+    }
+
+    /// Gets the errors for the files that couldn't be loaded.
+    #[must_use]
+    pub fn errors(&self) -> &[LibraryError] {
+        &self.errors
+    }
+
+    /// Gets the microcontrollers with the given [`name`][`Microcontroller::name`].
+    #[must_use]
+    pub fn by_name(&self, name: &str) -> Vec<&LibraryEntry> {
+        self.get_all(self.by_name.get(name))
+    }
+
+    /// Gets the microcontrollers with the given [`IoSignature`].
+    #[must_use]
+    pub fn by_signature(&self, signature: &IoSignature) -> Vec<&LibraryEntry> {
+        self.get_all(self.by_signature.get(signature))
+    }
+
+    /// Gets the microcontrollers with a component count in the given range, fewest components
+    /// first.
+    ///
+    /// Components in nested groups are counted, IO nodes aren't.
+    #[must_use]
+    pub fn by_component_count(&self, range: impl RangeBounds<usize>) -> Vec<&LibraryEntry> {
+        self.by_component_count
+            .range(range)
+            .flat_map(|(_, indices)| indices)
+            .map(|&i| &self.entries[i])
+            .collect()
+    }
+
+    /// Saves `mc` to a new file in the library's folder and adds it to the library.
+    ///
+    /// The file is named after the microcontroller, with characters that aren't allowed in file
+    /// names replaced by `_`. Existing files are never overwritten, a ` (2)`, ` (3)`, ... suffix is
+    /// added instead.
+    ///
+    /// # Errors
+    /// Returns an [`Err(LibraryError)`] if the microcontroller can't be serialized or the file
+    /// can't be written.
+    pub fn save(&mut self, mc: &Microcontroller) -> Result<&LibraryEntry, LibraryError> {
+        let stem = file_stem(&mc.name);
+        let xml = mc.to_xml_string().map_err(|error| LibraryError::SerDe {
+            path: self.folder.join(format!("{stem}.xml")),
+            error: Box::new(error),
+        })?;
+
+        let mut n = 1;
+        let path = loop {
+            let name = match n {
+                1 => format!("{stem}.xml"),
+                n => format!("{stem} ({n}).xml"),
+            };
+            n += 1;
+
+            // also check the index, the game's folder may end up on a case-insensitive filesystem
+            let taken = self.entries.iter().any(|e| {
+                e.path
+                    .file_name()
+                    .and_then(|f| f.to_str())
+                    .is_some_and(|f| f.eq_ignore_ascii_case(&name))
+            });
+            if taken {
+                continue;
+            }
+
+            let path = self.folder.join(name);
+            match fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+            {
+                Ok(mut f) => {
+                    if let Err(error) = f.write_all(xml.as_bytes()) {
+                        drop(f);
+                        let _ = fs::remove_file(&path);
+                        return Err(LibraryError::Io { path, error });
+                    }
+                    break path;
+                },
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {},
+                Err(error) => return Err(LibraryError::Io { path, error }),
+            }
+        };
+
+        self.insert(LibraryEntry { path, mc: mc.clone() });
+        Ok(&self.entries[self.entries.len() - 1])
+    }
+}
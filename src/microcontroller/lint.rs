@@ -0,0 +1,323 @@
+//! Module containing configurable lints for [`Microcontroller`]s.
+//!
+//! Unlike [`MCValidationError`][super::MCValidationError]s, lints don't make a microcontroller invalid,
+//! they point out things that are likely mistakes or go against house style.
+
+use std::collections::HashMap;
+
+use crate::util::AnyComponentRef;
+
+use super::{
+    components::{Component, ComponentConnection, ComponentType},
+    graph::Graph,
+    mc_serde::microcontroller::IONodeType,
+    Microcontroller,
+};
+
+/// How serious a [`Lint`] is.
+#[allow(missing_docs)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
+
+/// A lint rule, see [`Rule::id`] for the id used in configs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Rule {
+    /// A component (or input node) where none of the outputs are connected to anything.
+    UnusedOutput,
+    /// An arithmetic component where every connected input comes from a constant.
+    ConstantArithmetic,
+    /// A `Divide` where the `div_by_zero` output isn't connected.
+    IgnoredDivByZero,
+    /// A `PropertySlider` where min is greater than max.
+    SliderMinMax,
+    /// An `Equal` with an epsilon of 0.
+    ZeroEpsilon,
+    /// An IO node that still has the default `"Input"` label.
+    DefaultLabel,
+    /// A `Lua` script over [`LintConfig::lua_char_limit`] characters.
+    LuaTooLong,
+}
+
+impl Rule {
+    /// Every rule, in the order lints are reported.
+    pub const ALL: [Rule; 7] = [
+        Rule::UnusedOutput,
+        Rule::ConstantArithmetic,
+        Rule::IgnoredDivByZero,
+        Rule::SliderMinMax,
+        Rule::ZeroEpsilon,
+        Rule::DefaultLabel,
+        Rule::LuaTooLong,
+    ];
+
+    /// Gets the id of this rule.
+    #[must_use]
+    pub fn id(self) -> &'static str {
+        match self {
+            Rule::UnusedOutput => "unused-output",
+            Rule::ConstantArithmetic => "constant-arithmetic",
+            Rule::IgnoredDivByZero => "ignored-div-by-zero",
+            Rule::SliderMinMax => "slider-min-max",
+            Rule::ZeroEpsilon => "zero-epsilon",
+            Rule::DefaultLabel => "default-label",
+            Rule::LuaTooLong => "lua-too-long",
+        }
+    }
+
+    /// Gets the rule with the given id.
+    #[must_use]
+    pub fn from_id(id: &str) -> Option<Rule> {
+        Self::ALL.into_iter().find(|r| r.id() == id)
+    }
+
+    /// Gets the severity this rule has in the default [`LintConfig`].
+    #[must_use]
+    pub fn default_severity(self) -> Severity {
+        match self {
+            Rule::ConstantArithmetic | Rule::DefaultLabel => Severity::Info,
+            Rule::UnusedOutput | Rule::IgnoredDivByZero | Rule::ZeroEpsilon => Severity::Warning,
+            Rule::SliderMinMax | Rule::LuaTooLong => Severity::Error,
+        }
+    }
+}
+
+impl std::fmt::Display for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.id())
+    }
+}
+
+/// Configuration for [`Microcontroller::lint_with`].
+#[derive(Clone, Debug)]
+pub struct LintConfig {
+    /// Severity for each rule, rules that aren't in here are disabled.
+    severities: HashMap<Rule, Severity>,
+    /// Maximum number of characters in a `Lua` script.
+    ///
+    /// Default is `8192`, the game's limit.
+    pub lua_char_limit: usize,
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self {
+            severities: Rule::ALL
+                .into_iter()
+                .map(|r| (r, r.default_severity()))
+                .collect(),
+            lua_char_limit: 8192,
+        }
+    }
+}
+
+impl LintConfig {
+    /// Creates a config with every rule disabled.
+    #[must_use]
+    pub fn none() -> Self {
+        Self { severities: HashMap::new(), ..Self::default() }
+    }
+
+    /// Gets the severity for a rule, or [`None`] if it's disabled.
+    #[must_use]
+    pub fn severity(&self, rule: Rule) -> Option<Severity> {
+        self.severities.get(&rule).copied()
+    }
+
+    /// Sets the severity for a rule, [`None`] disables it.
+    pub fn set(&mut self, rule: Rule, severity: Option<Severity>) -> &mut Self {
+        match severity {
+            Some(s) => self.severities.insert(rule, s),
+            None => self.severities.remove(&rule),
+        };
+        self
+    }
+}
+
+/// A single lint result.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Lint {
+    /// Rule that produced this lint.
+    pub rule: Rule,
+    /// Severity from the [`LintConfig`].
+    pub severity: Severity,
+    /// Ids of the components this lint is about (bridge component ids for IO nodes).
+    pub component_ids: Vec<u32>,
+    /// Human readable description.
+    pub message: String,
+}
+
+impl std::fmt::Display for Lint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}[{}]: {}", self.severity, self.rule, self.message)
+    }
+}
+
+impl Microcontroller {
+    /// Runs every lint with its default severity, see [`LintConfig::default`].
+    #[must_use]
+    pub fn lint(&self) -> Vec<Lint> {
+        self.lint_with(&LintConfig::default())
+    }
+
+    /// Runs the lints enabled in `config`, on every component including the ones in nested
+    /// [`groups`][`Self::groups`].
+    ///
+    /// Results are ordered by [`Rule`], then by component id.
+    #[must_use]
+    pub fn lint_with(&self, config: &LintConfig) -> Vec<Lint> {
+        let mut found: Vec<(Rule, u32, String)> = vec![];
+        let g = self.graph();
+        let used = |id: u32, index: u8| {
+            !g.consumers(&ComponentConnection { component_id: id, node_index: index })
+                .is_empty()
+        };
+
+        for c in self.all_components() {
+            let outputs = c.io_def().outputs.len();
+            let is_output_bridge = matches!(
+                &c,
+                AnyComponentRef::BridgeComponent(bc) if bc.component.mode() == IONodeType::Output
+            );
+            #[allow(clippy::cast_possible_truncation)]
+            if outputs > 0 && !is_output_bridge && !(0..outputs).any(|i| used(c.id(), i as u8)) {
+                let what = match &c {
+                    AnyComponentRef::Component(c) => c.component.name(),
+                    AnyComponentRef::BridgeComponent(_) => "input node",
+                };
+                found.push((
+                    Rule::UnusedOutput,
+                    c.id(),
+                    format!("outputs of {what} {} are never used", c.id()),
+                ));
+            }
+        }
+
+        for c in self.all_components() {
+            if let AnyComponentRef::Component(c) = c {
+                lint_component(c, &g, config, &mut found);
+            }
+        }
+
+        for ion in &self.io {
+            if ion.design.label == "Input" {
+                found.push((
+                    Rule::DefaultLabel,
+                    ion.logic.id,
+                    format!(
+                        "node {} has the default label \"Input\"",
+                        ion.design.node_id
+                    ),
+                ));
+            }
+        }
+
+        found.sort_by_key(|(rule, id, _)| (*rule, *id));
+        found
+            .into_iter()
+            .filter_map(|(rule, id, message)| {
+                Some(Lint {
+                    rule,
+                    severity: config.severity(rule)?,
+                    component_ids: vec![id],
+                    message,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Runs the lints that only look at a single component's type and settings.
+fn lint_component(
+    c: &Component,
+    g: &Graph,
+    config: &LintConfig,
+    found: &mut Vec<(Rule, u32, String)>,
+) {
+    let used = |id: u32, index: u8| {
+        !g.consumers(&ComponentConnection { component_id: id, node_index: index })
+            .is_empty()
+    };
+
+    let id = c.id;
+    match &c.component {
+        ComponentType::Add { .. }
+        | ComponentType::Subtract { .. }
+        | ComponentType::Multiply { .. }
+        | ComponentType::Divide { .. }
+        | ComponentType::Modulo { .. }
+        | ComponentType::Abs { .. } => {
+            let sources: Vec<_> = c.component.inputs().into_iter().flatten().collect();
+            let constant = !sources.is_empty()
+                && sources.iter().all(|conn| {
+                    matches!(
+                        g.get(conn.component_id),
+                        Some(AnyComponentRef::Component(src))
+                            if matches!(src.component, ComponentType::ConstantNum { .. })
+                    )
+                });
+            if constant {
+                found.push((
+                    Rule::ConstantArithmetic,
+                    id,
+                    format!(
+                        "{} {id} only has constant inputs and could be a constant",
+                        c.component.name()
+                    ),
+                ));
+            }
+            if matches!(c.component, ComponentType::Divide { .. }) && used(id, 0) && !used(id, 1) {
+                found.push((
+                    Rule::IgnoredDivByZero,
+                    id,
+                    format!("div_by_zero output of Divide {id} is never used"),
+                ));
+            }
+        },
+        ComponentType::PropertySlider { name, min, max, .. } if min.value() > max.value() => {
+            found.push((
+                Rule::SliderMinMax,
+                id,
+                format!(
+                    "slider {name:?} ({id}) has min {} greater than max {}",
+                    min.text(),
+                    max.text()
+                ),
+            ));
+        },
+        ComponentType::Equal { epsilon, .. } if epsilon.value() == 0.0 => {
+            found.push((
+                Rule::ZeroEpsilon,
+                id,
+                format!("Equal {id} has an epsilon of 0"),
+            ));
+        },
+        ComponentType::Lua { script: Some(script), .. } => {
+            let len = script.chars().count();
+            if len > config.lua_char_limit {
+                found.push((
+                    Rule::LuaTooLong,
+                    id,
+                    format!(
+                        "Lua script {id} is {len} characters, the limit is {}",
+                        config.lua_char_limit
+                    ),
+                ));
+            }
+        },
+        _ => {},
+    }
+}
@@ -0,0 +1,850 @@
+//! Module for compiling a microcontroller's logic into a single Lua script.
+//!
+//! [`Microcontroller::to_lua`] turns the logic graph into an `onTick` script that reads the
+//! microcontroller's number and on/off inputs from one composite with `input.getNumber` /
+//! `input.getBool` and writes its outputs to another with `output.setNumber` /
+//! `output.setBool`. [`Microcontroller::compile_to_lua`] puts that script into a new
+//! microcontroller with a single [`ComponentType::Lua`] node and a composite input and output.
+//!
+//! The script keeps the game's timing: every component output is a table entry that's updated
+//! from the previous tick's values, so a signal takes as many ticks through the script as it did
+//! through the components. Memory registers, latches, timers, PID controllers and the other
+//! stateful components keep their state in upvalues.
+//!
+//! ```
+//! use sw_rs::microcontroller::{builder::Builder, types::TNumber, Microcontroller};
+//!
+//! let mut mc = Microcontroller::default();
+//! let mut b = Builder::new(&mut mc);
+//! let x = b.input::<TNumber>("X");
+//! let two = b.constant(2);
+//! let y = b.mul(x, two);
+//! b.output("Y", y);
+//!
+//! let (compiled, lua) = mc.compile_to_lua().unwrap();
+//! assert!(lua.script.contains("input.getNumber(1)"));
+//! assert_eq!(compiled.components().count(), 3);
+//! ```
+
+use std::{collections::HashMap, fmt::Write};
+
+use thiserror::Error;
+
+use super::{
+    builder::Builder,
+    components::{
+        state::ComponentState, ComponentConnection, ComponentType, CompositeChannel, PulseMode,
+        TextValue, TimerUnits, TypedInputConnection, TypedOutputConnection, UpDownCounterMode,
+    },
+    expr::{self, BinaryOp, Expr, ExprKind, Function, UnaryOp},
+    lint::LintConfig,
+    mc_serde::microcontroller::IONodeType,
+    simulator::{duration_ticks, COMPOSITE_CHANNELS, TICKS_PER_SECOND},
+    types::{TComposite, Type},
+    MCValidationError, Microcontroller,
+};
+
+/// Label of the composite input of a [compiled][Microcontroller::compile_to_lua] microcontroller.
+pub const INPUT_LABEL: &str = "Data In";
+/// Label of the composite output of a [compiled][Microcontroller::compile_to_lua] microcontroller.
+pub const OUTPUT_LABEL: &str = "Data Out";
+
+/// Something in a microcontroller that can't be compiled to Lua.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Unsupported {
+    /// A component with no Lua equivalent: video and audio switchboxes and Lua scripts.
+    Component {
+        /// Id of the component.
+        component_id: u32,
+        /// Name of the component type.
+        typ: &'static str,
+    },
+    /// An IO node that can't be packed into a composite channel (composite, video and audio).
+    IoNode {
+        /// Label of the node.
+        label: String,
+        /// Type of the node.
+        typ: Type,
+    },
+    /// More number or on/off IO nodes of one mode than a composite has channels.
+    TooManyChannels {
+        /// Whether these are inputs or outputs.
+        mode: IONodeType,
+        /// Type of the nodes.
+        typ: Type,
+        /// How many there are.
+        count: usize,
+    },
+    /// Nested groups, whose logic isn't compiled.
+    Groups(usize),
+}
+
+impl std::fmt::Display for Unsupported {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Unsupported::Component { component_id, typ } => {
+                write!(f, "{typ} {component_id} has no Lua equivalent")
+            },
+            Unsupported::IoNode { label, typ } => {
+                write!(
+                    f,
+                    "IO node {label:?} is {typ:?}, which doesn't fit a composite channel"
+                )
+            },
+            Unsupported::TooManyChannels { mode, typ, count } => write!(
+                f,
+                "{count} {typ:?} {} nodes, a composite only has {COMPOSITE_CHANNELS} channels",
+                match mode {
+                    IONodeType::Input => "input",
+                    IONodeType::Output => "output",
+                }
+            ),
+            Unsupported::Groups(count) => write!(f, "{count} nested groups aren't compiled"),
+        }
+    }
+}
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum LuaError {
+    #[error("Can't compile to Lua: {}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join(", "))]
+    Unsupported(Vec<Unsupported>),
+    #[error(transparent)]
+    ValidationError(#[from] MCValidationError),
+}
+
+/// Where an IO node ended up in the compiled script's composite.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LuaChannel {
+    /// Label of the original IO node.
+    pub label: String,
+    /// [`Type::Number`] or [`Type::OnOff`].
+    pub typ: Type,
+    /// 1-based number or on/off channel.
+    pub channel: u8,
+}
+
+/// A compiled script, see [`Microcontroller::to_lua`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LuaScript {
+    /// The Lua source.
+    pub script: String,
+    /// Channels of the original input nodes, in IO node order.
+    pub inputs: Vec<LuaChannel>,
+    /// Channels of the original output nodes, in IO node order.
+    pub outputs: Vec<LuaChannel>,
+    /// Things that behave differently from the original, like properties that are compiled in
+    /// as constants.
+    pub notes: Vec<String>,
+}
+
+/// Helper functions the script may need, in the order they're defined.
+///
+/// `C` copies a composite (or makes an empty one), `E` is an empty composite, `K` turns a
+/// number into a channel, `T` a duration into ticks, `R` rounds and `S` is the sign.
+/// `F` rounds to single precision like the game, so capacitors take the same number of ticks.
+/// `NB` and `BN` convert between numbers and composite bits.
+const HELPERS: [(&str, &str); 9] = [
+    ("C", "local function C(c)local r={n={},b={}}for i=1,32 do r.n[i]=c and c.n[i]or 0.0 r.b[i]=c and c.b[i]or false end return r end"),
+    ("E", "local E=C()"),
+    ("K", "local function K(x)x=math.floor(x+.5)if x>=1 and x<=32 then return x end end"),
+    ("T", "local function T(x)return x==x and math.floor(math.max(x,0)+.5)or 0 end"),
+    ("R", "local function R(x)return(x>=0 and math.floor(x+.5)or math.ceil(x-.5))*1.0 end"),
+    ("S", "local function S(x)return x>0 and 1.0 or x<0 and -1.0 or 0.0 end"),
+    ("F", "local function F(x)return(string.unpack(\"f\",string.pack(\"f\",x)))end"),
+    ("NB", "local function NB(x)local r=C()x=x~=x and 0 or math.max(-2^31,math.min(x,2^31-1))x=math.tointeger(x>=0 and math.floor(x)or math.ceil(x))&0xFFFFFFFF for i=1,32 do r.b[i]=x>>(i-1)&1==1 end return r end"),
+    ("BN", "local function BN(c)local x=0 for i=1,32 do if c.b[i]then x=x|1<<(i-1)end end return(x>=2^31 and x-2^32 or x)+0.0 end"),
+];
+
+/// Formats a number as a Lua float literal.
+fn num(n: f64) -> String {
+    if n.is_nan() {
+        "(0/0)".into()
+    } else if n.is_infinite() {
+        if n > 0.0 { "(1/0)" } else { "(-1/0)" }.into()
+    } else {
+        format!("{n:?}")
+    }
+}
+
+fn tv(v: &TextValue) -> String {
+    num(v.value())
+}
+
+fn default_value(typ: Type) -> &'static str {
+    match typ {
+        Type::Number => "0.0",
+        Type::Composite => "E",
+        _ => "false",
+    }
+}
+
+/// Compiles an expression, with `vars` as the Lua expressions for its variables.
+fn expr_number(e: &Expr, vars: &[String], helpers: &mut Vec<&'static str>) -> String {
+    let mut sub = |e: &Expr| expr_number(e, vars, helpers);
+    match e {
+        Expr::Number(n) => num(*n),
+        Expr::Var(i) => vars.get(*i).map_or("0.0".into(), Clone::clone),
+        Expr::Unary(UnaryOp::Neg, a) => format!("(-{})", sub(a)),
+        Expr::Unary(UnaryOp::Not, a) => format!("({}==0 and 1.0 or 0.0)", sub(a)),
+        Expr::Binary(op, a, b) => {
+            let (a, b) = (sub(a), sub(b));
+            match op {
+                BinaryOp::Add => format!("({a}+{b})"),
+                BinaryOp::Sub => format!("({a}-{b})"),
+                BinaryOp::Mul => format!("({a}*{b})"),
+                BinaryOp::Div => format!("({a}/{b})"),
+                BinaryOp::Mod => format!("math.fmod({a},{b})"),
+                BinaryOp::Pow => format!("({a}^{b})"),
+                BinaryOp::And => format!("(({a}~=0 and {b}~=0)and 1.0 or 0.0)"),
+                BinaryOp::Or => format!("(({a}~=0 or {b}~=0)and 1.0 or 0.0)"),
+                BinaryOp::Xor => format!("((({a}~=0)~=({b}~=0))and 1.0 or 0.0)"),
+            }
+        },
+        Expr::Call(f, args) => {
+            let args: Vec<String> = (0..f.arity())
+                .map(|i| {
+                    args.get(i)
+                        .map_or("0.0".into(), |a| expr_number(a, vars, helpers))
+                })
+                .collect();
+            let a = |i: usize| &args[i];
+            let mut helper = |name: &'static str| {
+                helpers.push(name);
+                format!("{name}({})", a(0))
+            };
+            match f {
+                Function::Sgn => helper("S"),
+                Function::Round => helper("R"),
+                Function::Pow => format!("({}^{})", a(0), a(1)),
+                Function::Log => format!("math.log({})", a(0)),
+                Function::Atan2 => format!("math.atan({},{})", a(0), a(1)),
+                Function::Floor | Function::Ceil => {
+                    format!("(math.{}({})*1.0)", f.name(), a(0))
+                },
+                Function::Min | Function::Max => format!("math.{}({},{})", f.name(), a(0), a(1)),
+                Function::Clamp => format!("math.min(math.max({},{}),{})", a(0), a(1), a(2)),
+                Function::Lerp => format!("({0}+({1}-{0})*{2})", a(0), a(1), a(2)),
+                Function::InvLerp => format!("(({2}-{0})/({1}-{0}))", a(0), a(1), a(2)),
+                _ => format!("math.{}({})", f.name(), a(0)),
+            }
+        },
+    }
+}
+
+/// Compiles an on/off expression, see [`Expr::eval_bool`].
+fn expr_bool(e: &Expr, vars: &[String], helpers: &mut Vec<&'static str>) -> String {
+    let mut sub = |e: &Expr| expr_bool(e, vars, helpers);
+    match e {
+        Expr::Var(i) => vars.get(*i).map_or("false".into(), Clone::clone),
+        Expr::Unary(UnaryOp::Not, a) => format!("(not {})", sub(a)),
+        Expr::Binary(BinaryOp::And, a, b) => format!("({} and {})", sub(a), sub(b)),
+        Expr::Binary(BinaryOp::Or, a, b) => format!("({} or {})", sub(a), sub(b)),
+        Expr::Binary(BinaryOp::Xor, a, b) => format!("({}~={})", sub(a), sub(b)),
+        _ => {
+            let vars: Vec<String> = vars
+                .iter()
+                .map(|v| format!("({v} and 1.0 or 0.0)"))
+                .collect();
+            format!("({}~=0)", expr_number(e, &vars, helpers))
+        },
+    }
+}
+
+/// Script being built by [`Microcontroller::to_lua`].
+struct Gen {
+    /// Slot in the value tables of every component output.
+    slots: HashMap<(u32, u8), usize>,
+    /// Initial value of every slot.
+    initial: Vec<&'static str>,
+    /// Slots of the input bridges, which are read from the composite instead of computed.
+    bridge_slots: HashMap<u32, usize>,
+    helpers: Vec<&'static str>,
+    /// Upvalue declarations.
+    state: Vec<String>,
+    /// Statements in `onTick`.
+    body: Vec<String>,
+    notes: Vec<String>,
+}
+
+impl Gen {
+    /// Gets the Lua expression for an input: the previous tick's value of what it's connected to.
+    fn input(&mut self, conn: Option<&ComponentConnection>, typ: Type) -> String {
+        if let Some(slot) = conn.and_then(|c| self.slots.get(&(c.component_id, c.node_index))) {
+            return format!("o[{slot}]");
+        }
+        if typ == Type::Composite {
+            self.helpers.extend(["C", "E"]);
+        }
+        default_value(typ).into()
+    }
+
+    /// Gets the duration in ticks of a timer's duration input.
+    fn ticks(&mut self, duration: &str, units: TimerUnits) -> String {
+        self.helpers.push("T");
+        match units {
+            TimerUnits::Ticks => format!("T({duration})"),
+            TimerUnits::Seconds => format!("T({duration}*60)"),
+        }
+    }
+
+    #[allow(clippy::too_many_lines, clippy::cast_precision_loss)]
+    fn component(&mut self, id: u32, component: &ComponentType, ins: &[String], outs: &[String]) {
+        let s = format!("s{id}");
+        let (sb, sc) = (format!("{s}b"), format!("{s}c"));
+        let i = |k: usize| &ins[k];
+        let o = |k: usize| &outs[k];
+
+        let code = match component {
+            ComponentType::NOT { .. } => format!("{}=not {}", o(0), i(0)),
+            ComponentType::AND { .. } => format!("{}={} and {}", o(0), i(0), i(1)),
+            ComponentType::OR { .. } => format!("{}={} or {}", o(0), i(0), i(1)),
+            ComponentType::XOR { .. } => format!("{}={}~={}", o(0), i(0), i(1)),
+            ComponentType::NAND { .. } => format!("{}=not({} and {})", o(0), i(0), i(1)),
+            ComponentType::NOR { .. } => format!("{}=not({} or {})", o(0), i(0), i(1)),
+            ComponentType::Add { .. } => format!("{}={}+{}", o(0), i(0), i(1)),
+            ComponentType::Subtract { .. } => format!("{}={}-{}", o(0), i(0), i(1)),
+            ComponentType::Multiply { .. } => format!("{}={}*{}", o(0), i(0), i(1)),
+            ComponentType::Divide { .. } => format!(
+                "{0}={3}==0 and 0.0 or {2}/{3} {1}={3}==0",
+                o(0),
+                o(1),
+                i(0),
+                i(1)
+            ),
+            ComponentType::Modulo { .. } => {
+                format!("{}={2}==0 and 0.0 or math.fmod({1},{2})", o(0), i(0), i(1))
+            },
+            ComponentType::Abs { .. } => format!("{}=math.abs({})", o(0), i(0)),
+            // the bound goes first so a NaN input gives the bound, like `f32::max`
+            ComponentType::Clamp { min, max, .. } => {
+                format!(
+                    "{}=math.min({},math.max({},{}))",
+                    o(0),
+                    tv(max),
+                    tv(min),
+                    i(0)
+                )
+            },
+            ComponentType::Threshold { min, max, .. } => {
+                format!("{0}={2}<={1} and {1}<={3}", o(0), i(0), tv(min), tv(max))
+            },
+            ComponentType::GreaterThan { .. } => format!("{}={}>{}", o(0), i(0), i(1)),
+            ComponentType::LessThan { .. } => format!("{}={}<{}", o(0), i(0), i(1)),
+            ComponentType::Equal { epsilon, .. } => {
+                format!("{}=math.abs({}-{})<={}", o(0), i(0), i(1), tv(epsilon))
+            },
+            ComponentType::ConstantNum { n, .. } => format!("{}={}", o(0), tv(n)),
+            ComponentType::ConstantOn { .. } => format!("{}=true", o(0)),
+            ComponentType::PropertySlider { name, v, .. } => {
+                self.notes
+                    .push(format!("property {name:?} is compiled in as {}", v.value()));
+                format!("{}={}", o(0), tv(v))
+            },
+            ComponentType::PropertyNumber { name, value, .. } => {
+                self.notes.push(format!(
+                    "property {name:?} is compiled in as {}",
+                    value.value()
+                ));
+                format!("{}={}", o(0), tv(value))
+            },
+            ComponentType::PropertyToggle { name, value, .. } => {
+                self.notes
+                    .push(format!("property {name:?} is compiled in as {value}"));
+                format!("{}={value}", o(0))
+            },
+            ComponentType::PropertyDropdown { name, items, selected, .. } => {
+                let value = items
+                    .get(*selected as usize)
+                    .map_or(0.0, |item| item.value.value());
+                self.notes
+                    .push(format!("property {name:?} is compiled in as {value}"));
+                format!("{}={}", o(0), num(value))
+            },
+            ComponentType::PropertyText { .. } => return,
+            ComponentType::TooltipNum { label, .. } | ComponentType::TooltipOnOff { label, .. } => {
+                self.notes.push(format!("tooltip {label:?} is dropped"));
+                return;
+            },
+            ComponentType::NumericalJunction { .. } => format!(
+                "{0}={3} and {2} or 0.0 {1}={3} and 0.0 or {2}",
+                o(0),
+                o(1),
+                i(0),
+                i(1)
+            ),
+            ComponentType::NumericalSwitchbox { .. } | ComponentType::CompositeSwitchbox { .. } => {
+                format!("{}={} and {} or {}", o(0), i(2), i(0), i(1))
+            },
+            ComponentType::MemoryRegister { reset_value, .. } => {
+                let initial = match component.state() {
+                    Ok(Some(ComponentState::MemoryRegister(m))) => f64::from(m.value),
+                    _ => 0.0,
+                };
+                self.state.push(format!("local {s}={}", num(initial)));
+                format!(
+                    "if {} then {s}={} elseif {} then {s}={} end {}={s}",
+                    i(1),
+                    tv(reset_value),
+                    i(0),
+                    i(2),
+                    o(0)
+                )
+            },
+            ComponentType::SRLatch { .. } => {
+                self.state.push(format!("local {s}=false"));
+                format!(
+                    "if {} then {s}=true end if {} then {s}=false end {}={s} {}=not {s}",
+                    i(0),
+                    i(1),
+                    o(0),
+                    o(1)
+                )
+            },
+            ComponentType::JKFlipFlop { .. } => {
+                self.state.push(format!("local {s}=false"));
+                format!(
+                    "if {0} and {1} then {s}=not {s} elseif {0} then {s}=true elseif {1} then {s}=false end {2}={s} {3}=not {s}",
+                    i(0),
+                    i(1),
+                    o(0),
+                    o(1)
+                )
+            },
+            ComponentType::Capacitor { ct, dt, .. } => {
+                self.state.push(format!("local {s}=0.0"));
+                self.helpers.push("F");
+                let per_tick = |secs: f32| f64::from(1.0 / (secs * TICKS_PER_SECOND as f32));
+                let charge = if *ct <= 0.0 {
+                    "1.0".into()
+                } else {
+                    format!("F(math.min({s}+{},1.0))", num(per_tick(*ct)))
+                };
+                let discharge = if *dt <= 0.0 {
+                    "0.0".into()
+                } else {
+                    format!("F(math.max({s}-{},0.0))", num(per_tick(*dt)))
+                };
+                format!(
+                    "if {} then {s}={charge} {1}={s}>=1 else {s}={discharge} {1}={s}>0 end",
+                    i(0),
+                    o(0)
+                )
+            },
+            ComponentType::Blinker { on, off, .. } => {
+                self.state.push(format!("local {s}=0"));
+                let on_ticks = duration_ticks(*on, TimerUnits::Seconds).max(1);
+                let period = on_ticks + duration_ticks(*off, TimerUnits::Seconds).max(1);
+                format!(
+                    "if {} then {1}={s}%{period}<{on_ticks} {s}=({s}+1)%{period} else {s}=0 {1}=false end",
+                    i(0),
+                    o(0)
+                )
+            },
+            ComponentType::PushToToggle { .. } => {
+                self.state.push(format!("local {s},{sb}=false,false"));
+                format!(
+                    "if {0} and not {sb} then {s}=not {s} end {sb}={0} {1}={s}",
+                    i(0),
+                    o(0)
+                )
+            },
+            ComponentType::Pulse { mode, .. } => {
+                self.state.push(format!("local {s}=false"));
+                let out = match mode {
+                    PulseMode::OffToOn => format!("{} and not {s}", i(0)),
+                    PulseMode::OnToOff => format!("not {} and {s}", i(0)),
+                    PulseMode::Always => format!("{}~={s}", i(0)),
+                };
+                format!("{}={out} {s}={}", o(0), i(0))
+            },
+            ComponentType::TimerTON { units, .. } => {
+                self.state.push(format!("local {s}=0"));
+                let ticks = self.ticks(i(1), *units);
+                format!(
+                    "if {0} then {s}={s}+1 else {s}=0 end {1}={0} and {s}>={ticks}",
+                    i(0),
+                    o(0)
+                )
+            },
+            ComponentType::TimerTOF { units, .. } => {
+                self.state.push(format!("local {s},{sb}=0,false"));
+                let ticks = self.ticks(i(1), *units);
+                format!(
+                    "if {0} then {s}=0 {sb}=true {1}=false else {s}={s}+1 {1}={sb} and {s}<={ticks} end",
+                    i(0),
+                    o(0)
+                )
+            },
+            ComponentType::TimerRTO { units, .. } => {
+                self.state.push(format!("local {s}=0"));
+                let ticks = self.ticks(i(1), *units);
+                format!(
+                    "if {} then {s}=0 elseif {} then {s}={s}+1 end {}={s}>={ticks}",
+                    i(2),
+                    i(0),
+                    o(0)
+                )
+            },
+            ComponentType::TimerRTF { units, .. } => {
+                self.state.push(format!("local {s}=0"));
+                let ticks = self.ticks(i(1), *units);
+                format!(
+                    "if {1} then {s}=0 elseif not {0} then {s}={s}+1 end {2}=not {0} and not {1} and {s}<={ticks}",
+                    i(0),
+                    i(2),
+                    o(0)
+                )
+            },
+            ComponentType::PIDController { kp, ki, kd, .. } => {
+                self.state
+                    .push(format!("local {s},{sb},{sc}=0.0,0.0,false"));
+                pid(&s, [i(0), i(1), &tv(kp), &tv(ki), &tv(kd), i(2)], o(0))
+            },
+            ComponentType::PIDControllerAdvanced { .. } => {
+                self.state
+                    .push(format!("local {s},{sb},{sc}=0.0,0.0,false"));
+                pid(&s, [i(0), i(1), i(2), i(3), i(4), i(5)], o(0))
+            },
+            ComponentType::Delta { .. } => {
+                self.state.push(format!("local {s}=0.0"));
+                format!("{1}={0}-{s} {s}={0}", i(0), o(0))
+            },
+            ComponentType::UpDownCounter { mode, reset_val, increment, min, max, .. } => {
+                self.state.push(format!("local {s}"));
+                let clamp = if *mode == UpDownCounterMode::Clamped {
+                    format!(" {s}=math.min(math.max({s},{}),{})", tv(min), tv(max))
+                } else {
+                    String::new()
+                };
+                format!(
+                    "if {s}==nil or {} then {s}={} else if {} then {s}={s}+{inc} end if {} then {s}={s}-{inc} end end{clamp} {}={s}",
+                    i(2),
+                    tv(reset_val),
+                    i(0),
+                    i(1),
+                    o(0),
+                    inc = tv(increment),
+                )
+            },
+            ComponentType::CompositeReadOnOff { channel, .. } => match channel {
+                CompositeChannel::Variable => {
+                    self.helpers.push("K");
+                    format!("{}={}.b[K({})]==true", o(0), i(0), i(1))
+                },
+                CompositeChannel::Fixed(ch) if usize::from(*ch) < COMPOSITE_CHANNELS => {
+                    format!("{}={}.b[{}]", o(0), i(0), ch + 1)
+                },
+                CompositeChannel::Fixed(_) => format!("{}=false", o(0)),
+            },
+            ComponentType::CompositeReadNum { channel, .. } => match channel {
+                CompositeChannel::Variable => {
+                    self.helpers.push("K");
+                    format!("{}={}.n[K({})]or 0.0", o(0), i(0), i(1))
+                },
+                CompositeChannel::Fixed(ch) if usize::from(*ch) < COMPOSITE_CHANNELS => {
+                    format!("{}={}.n[{}]", o(0), i(0), ch + 1)
+                },
+                CompositeChannel::Fixed(_) => format!("{}=0.0", o(0)),
+            },
+            ComponentType::_OldCompositeWriteOnOff { channel, .. }
+            | ComponentType::_OldCompositeWriteNum { channel, .. } => {
+                self.helpers.push("C");
+                let kind = if matches!(component, ComponentType::_OldCompositeWriteNum { .. }) {
+                    'n'
+                } else {
+                    'b'
+                };
+                if usize::from(*channel) < COMPOSITE_CHANNELS {
+                    format!(
+                        "do local c=C({}) c.{kind}[{}]={} {}=c end",
+                        i(0),
+                        channel + 1,
+                        i(1),
+                        o(0)
+                    )
+                } else {
+                    format!("{}=C({})", o(0), i(0))
+                }
+            },
+            ComponentType::CompositeWriteNum { count, offset, .. }
+            | ComponentType::CompositeWriteOnOff { count, offset, .. } => {
+                self.helpers.push("C");
+                let kind = if matches!(component, ComponentType::CompositeWriteNum { .. }) {
+                    'n'
+                } else {
+                    'b'
+                };
+                let count = usize::from(*count).min(COMPOSITE_CHANNELS);
+                let mut writes = String::new();
+                match offset {
+                    CompositeChannel::Variable => {
+                        self.helpers.push("K");
+                        let _ = write!(writes, " local k=K({}) if k then", i(33));
+                        for k in 0..count {
+                            let _ = write!(writes, " c.{kind}[k+{k}]={}", i(1 + k));
+                        }
+                        writes.push_str(" end");
+                    },
+                    CompositeChannel::Fixed(start) => {
+                        let start = usize::from(*start);
+                        for k in (0..count).filter(|k| start + k < COMPOSITE_CHANNELS) {
+                            let _ = write!(writes, " c.{kind}[{}]={}", start + k + 1, i(1 + k));
+                        }
+                    },
+                }
+                format!("do local c=C({}){writes} {}=c end", i(0), o(0))
+            },
+            ComponentType::NumToCompositeBin { .. } => {
+                self.helpers.extend(["C", "NB"]);
+                format!("{}=NB({})", o(0), i(0))
+            },
+            ComponentType::CompositeBinToNum { .. } => {
+                self.helpers.push("BN");
+                format!("{}=BN({})", o(0), i(0))
+            },
+            ComponentType::Func1n { .. }
+            | ComponentType::Func3n { .. }
+            | ComponentType::Func8n { .. }
+            | ComponentType::Func4b { .. }
+            | ComponentType::Func8b { .. } => match expr::parse_component(component) {
+                Some(Ok(e)) => {
+                    let kind = expr::component_expression(component).map(|(_, kind, _)| kind);
+                    if kind == Some(ExprKind::OnOff) {
+                        format!("{}={}", o(0), expr_bool(&e, ins, &mut self.helpers))
+                    } else {
+                        format!("{}={}", o(0), expr_number(&e, ins, &mut self.helpers))
+                    }
+                },
+                _ => format!("{}={}", o(0), default_value(component.io_def().outputs[0])),
+            },
+            ComponentType::VideoSwitchbox { .. }
+            | ComponentType::AudioSwitchbox { .. }
+            | ComponentType::Lua { .. } => unreachable!("unsupported components are checked first"),
+        };
+        self.body.push(code);
+    }
+}
+
+/// Compiles a PID controller, see `step_pid` in the simulator.
+///
+/// `ins` are the setpoint, process variable, p, i, d and active expressions.
+fn pid(s: &str, ins: [&str; 6], out: &str) -> String {
+    let [setpoint, process, kp, ki, kd, active] = ins;
+    format!(
+        "if {active} then local e={setpoint}-{process} {s}={s}+e {out}={kp}*e+{ki}*{s}+{kd}*({s}c and e-{s}b or 0.0) {s}b=e {s}c=true else {s},{s}b,{s}c=0.0,0.0,false {out}=0.0 end"
+    )
+}
+
+impl Microcontroller {
+    /// Compiles this microcontroller's logic into a Lua `onTick` script, see the
+    /// [module docs][super::lua].
+    ///
+    /// # Errors
+    /// Returns a [`LuaError::Unsupported`] listing everything that can't be compiled.
+    #[allow(clippy::too_many_lines)]
+    pub fn to_lua(&self) -> Result<LuaScript, LuaError> {
+        let mut unsupported = vec![];
+        if !self.groups.is_empty() {
+            unsupported.push(Unsupported::Groups(self.groups.len()));
+        }
+        for c in &self.components {
+            if matches!(
+                c.component,
+                ComponentType::VideoSwitchbox { .. }
+                    | ComponentType::AudioSwitchbox { .. }
+                    | ComponentType::Lua { .. }
+            ) {
+                unsupported
+                    .push(Unsupported::Component { component_id: c.id, typ: c.component.name() });
+            }
+        }
+
+        // channels of the IO nodes, by bridge component id
+        let mut channels: HashMap<u32, u8> = HashMap::new();
+        let mut inputs = vec![];
+        let mut outputs = vec![];
+        for mode in [IONodeType::Input, IONodeType::Output] {
+            for typ in [Type::Number, Type::OnOff] {
+                let nodes: Vec<_> = self
+                    .io
+                    .iter()
+                    .filter(|ion| ion.design.mode == mode && ion.design.typ == typ)
+                    .collect();
+                if nodes.len() > COMPOSITE_CHANNELS {
+                    unsupported.push(Unsupported::TooManyChannels {
+                        mode,
+                        typ,
+                        count: nodes.len(),
+                    });
+                }
+                for (channel, ion) in (1..).zip(nodes) {
+                    channels.insert(ion.logic.id, channel);
+                }
+            }
+        }
+        for ion in &self.io {
+            let d = &ion.design;
+            if !matches!(d.typ, Type::Number | Type::OnOff) {
+                unsupported.push(Unsupported::IoNode { label: d.label.clone(), typ: d.typ });
+                continue;
+            }
+            let channel = LuaChannel {
+                label: d.label.clone(),
+                typ: d.typ,
+                channel: channels[&ion.logic.id],
+            };
+            match d.mode {
+                IONodeType::Input => inputs.push(channel),
+                IONodeType::Output => outputs.push(channel),
+            }
+        }
+        if !unsupported.is_empty() {
+            return Err(LuaError::Unsupported(unsupported));
+        }
+
+        let mut gen = Gen {
+            slots: HashMap::new(),
+            initial: vec![],
+            bridge_slots: HashMap::new(),
+            helpers: vec![],
+            state: vec![],
+            body: vec![],
+            notes: vec![],
+        };
+        for c in self.components() {
+            for (index, typ) in (0..).zip(c.io_def().outputs) {
+                gen.initial.push(default_value(typ));
+                if typ == Type::Composite {
+                    gen.helpers.extend(["C", "E"]);
+                }
+                gen.slots.insert((c.id(), index), gen.initial.len());
+            }
+        }
+
+        // the inputs are what the input bridges output, read at the start of the tick
+        let mut reads = vec![];
+        for ion in &self.io {
+            if ion.design.mode == IONodeType::Input {
+                let slot = gen.slots[&(ion.logic.id, 0)];
+                gen.bridge_slots.insert(ion.logic.id, slot);
+                let get = match ion.design.typ {
+                    Type::Number => "getNumber",
+                    _ => "getBool",
+                };
+                reads.push(format!(
+                    "o[{slot}]=input.{get}({})",
+                    channels[&ion.logic.id]
+                ));
+            }
+        }
+
+        for c in &self.components {
+            let io = c.component.io_def();
+            let ins: Vec<String> = c
+                .component
+                .inputs()
+                .into_iter()
+                .zip(io.inputs)
+                .map(|(conn, typ)| gen.input(conn.as_ref(), typ))
+                .collect();
+            let outs: Vec<String> = (0..)
+                .zip(io.outputs)
+                .map(|(index, _)| format!("n[{}]", gen.slots[&(c.id, index)]))
+                .collect();
+            gen.component(c.id, &c.component, &ins, &outs);
+        }
+
+        // outputs get the value their bridge will have on the next tick
+        let mut writes = vec![];
+        for ion in &self.io {
+            if ion.design.mode != IONodeType::Output {
+                continue;
+            }
+            let conn = ion.logic.component.inputs()[0].as_ref();
+            let value = match conn.and_then(|c| gen.slots.get(&(c.component_id, c.node_index))) {
+                Some(slot) if gen.bridge_slots.values().any(|s| s == slot) => {
+                    gen.notes.push(format!(
+                        "output {:?} is wired straight to an input, it's one tick later",
+                        ion.design.label
+                    ));
+                    format!("o[{slot}]")
+                },
+                Some(slot) => format!("n[{slot}]"),
+                None => default_value(ion.design.typ).into(),
+            };
+            let set = match ion.design.typ {
+                Type::Number => "setNumber",
+                _ => "setBool",
+            };
+            writes.push(format!("output.{set}({},{value})", channels[&ion.logic.id]));
+        }
+
+        let mut lines = vec![format!(
+            "-- {}, compiled by sw-rs",
+            self.name.replace('\n', " ")
+        )];
+        for (name, def) in HELPERS {
+            if gen.helpers.contains(&name) {
+                lines.push(def.into());
+            }
+        }
+        lines.push(format!("local o={{{}}}", gen.initial.join(",")));
+        lines.extend(gen.state);
+        lines.push("function onTick()".into());
+        lines.push("local n={}".into());
+        lines.extend(reads);
+        lines.extend(gen.body);
+        lines.extend(writes);
+        lines.push("o=n".into());
+        lines.push("end".into());
+        let script = lines.join("\n");
+
+        let limit = LintConfig::default().lua_char_limit;
+        let len = script.chars().count();
+        if len > limit {
+            gen.notes.push(format!(
+                "the script is {len} characters, over the game's limit of {limit}"
+            ));
+        }
+
+        Ok(LuaScript { script, inputs, outputs, notes: gen.notes })
+    }
+
+    /// Compiles this microcontroller's logic with [`to_lua`][Self::to_lua] and puts the script
+    /// into a new microcontroller with the same name, description and size.
+    ///
+    /// The new microcontroller has a composite input ([`INPUT_LABEL`]) wired to a single
+    /// [`ComponentType::Lua`] node, which is wired to a composite output ([`OUTPUT_LABEL`]). The
+    /// returned [`LuaScript`] says which channel each original IO node is on.
+    ///
+    /// # Errors
+    /// Returns a [`LuaError`] if the logic can't be compiled.
+    pub fn compile_to_lua(&self) -> Result<(Microcontroller, LuaScript), LuaError> {
+        let lua = self.to_lua()?;
+
+        let mut mc = Microcontroller::new(
+            self.name.clone(),
+            self.description.clone(),
+            self.width,
+            self.length,
+        )?;
+        mc.icon = self.icon;
+
+        let mut b = Builder::new(&mut mc);
+        let data_in = b.input::<TComposite>(INPUT_LABEL);
+        let id = b.component(ComponentType::Lua {
+            data_in: data_in.into(),
+            video_in: TypedInputConnection::empty(),
+            data_out: TypedOutputConnection::default(),
+            video_out: TypedOutputConnection::default(),
+            script: Some(lua.script.clone()),
+        });
+        // Lua nodes always have a composite output 0
+        if let Some(data_out) = b.out::<TComposite>(id, 0) {
+            b.output(OUTPUT_LABEL, data_out);
+        }
+
+        Ok((mc, lua))
+    }
+}
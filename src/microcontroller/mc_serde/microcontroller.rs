@@ -1,16 +1,14 @@
 //! Most of the ser/de code
 
-use std::collections::BTreeMap;
-
-use fakemap::FakeMap;
 use serde::{Deserialize, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
 
 use crate::microcontroller::{
     components::{BridgeComponent, Component},
+    group::{GroupDataNode, GroupState},
     types::Type,
 };
-use crate::util::serde_utils::{PositionXZ, RecursiveStringMap};
+use crate::util::serde_utils::{tagged, PositionXZ};
 
 use super::is_default;
 
@@ -122,18 +120,21 @@ pub(crate) struct Group {
     pub data: Data,
     pub components: Components,
     pub components_bridge: ComponentsBridge,
-    pub groups: (), // unused?
+    /// Nested groups, with the tag each is stored under.
+    #[serde(with = "tagged")]
+    pub groups: Vec<(String, Group)>,
     #[serde(
-        serialize_with = "ser_component_states",
-        deserialize_with = "de_component_states"
+        serialize_with = "crate::microcontroller::components::serialize_states",
+        deserialize_with = "skip_component_states"
     )]
-    pub component_states: Vec<ComponentsBridgeInnerObject>,
+    pub component_states: Vec<Component>,
     #[serde(
-        serialize_with = "ser_component_states",
-        deserialize_with = "de_component_states"
+        serialize_with = "crate::microcontroller::components::serialize_states",
+        deserialize_with = "skip_component_states"
     )]
-    pub component_bridge_states: Vec<ComponentsBridgeInnerObject>,
-    pub group_states: (), // unused?
+    pub component_bridge_states: Vec<BridgeComponent>,
+    #[serde(with = "tagged")]
+    pub group_states: Vec<(String, GroupState)>,
 }
 
 #[derive(Serialize, Deserialize, Default, Debug)]
@@ -142,31 +143,35 @@ pub(crate) struct Data {
     #[serde(rename = "@type", default, skip_serializing_if = "is_default")]
     pub typ: Option<String>, // ??
 
-    pub inputs: (),  // unused?
-    pub outputs: (), // unused?
+    pub inputs: DataInputs,
+    pub outputs: DataOutputs,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+#[serde(rename = "inputs")]
+pub(crate) struct DataInputs {
+    #[serde(rename = "i", default)]
+    pub nodes: Vec<GroupDataNode>,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+#[serde(rename = "outputs")]
+pub(crate) struct DataOutputs {
+    #[serde(rename = "o", default)]
+    pub nodes: Vec<GroupDataNode>,
 }
 
 #[derive(Serialize, Deserialize, Default, Debug)]
 #[serde(rename = "components")]
 pub(crate) struct Components {
-    #[serde(
-        rename = "c",
-        default,
-        deserialize_with = "crate::microcontroller::components::components_deserialize",
-        serialize_with = "crate::microcontroller::components::components_serialize"
-    )]
+    #[serde(rename = "c", default)]
     pub components: Vec<Component>,
 }
 
 #[derive(Serialize, Deserialize, Default, Debug)]
 #[serde(rename = "components_bridge")]
 pub(crate) struct ComponentsBridge {
-    #[serde(
-        rename = "c",
-        default,
-        deserialize_with = "crate::microcontroller::components::bridge_components_deserialize",
-        serialize_with = "crate::microcontroller::components::bridge_components_serialize"
-    )]
+    #[serde(rename = "c", default)]
     pub components_bridge: Vec<BridgeComponent>,
 }
 
@@ -186,34 +191,11 @@ pub(crate) enum ComponentsBridgeType {
     AudioOut,
 }
 
-#[derive(Serialize, Deserialize, Default, Clone, Debug)]
-pub(crate) struct ComponentsBridgeInnerObject {
-    #[serde(rename = "@id", default, skip_serializing_if = "is_default")]
-    pub id: u32,
-
-    #[serde(flatten)]
-    pub(crate) other: FakeMap<String, RecursiveStringMap>,
-}
-
-/// Serializes Vec into tags with names c0, c1, c2, etc.
-fn ser_component_states<S, T: Serialize>(states: &[T], ser: S) -> Result<S::Ok, S::Error>
-where
-    S: serde::Serializer,
-{
-    ser.collect_map(states.iter().enumerate().map(|(i, v)| (format!("c{i}"), v)))
-}
-
-fn de_component_states<'de, D, T: Deserialize<'de> + std::fmt::Debug>(
-    de: D,
-) -> Result<Vec<T>, D::Error>
+/// The states are a copy of the components, so they're ignored when deserializing.
+fn skip_component_states<'de, D, T>(de: D) -> Result<Vec<T>, D::Error>
 where
     D: serde::Deserializer<'de>,
 {
-    BTreeMap::<String, T>::deserialize(de).map(|m| m.into_values().collect())
-}
-
-#[derive(Serialize, Deserialize, Default)]
-#[serde(rename = "components_bridge")]
-pub(crate) struct ComponentBridgeStates {
-    pub components_bridge: Vec<ComponentsBridgeInnerObject>,
+    serde::de::IgnoredAny::deserialize(de)?;
+    Ok(Vec::new())
 }
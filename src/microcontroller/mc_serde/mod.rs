@@ -1,11 +1,14 @@
 //! Module containing ser/de code for microcontrollers
 
-use super::{IONode, IONodeDesign, Microcontroller};
+use crate::util::serde_utils::ElementContext;
 
-use self::microcontroller::{
-    ComponentsBridgeInnerObject, Group, IONodeInner, IONodeSerDe, MicrocontrollerSerDe,
+use super::{
+    group::{GroupData, GroupState},
+    IONode, IONodeDesign, Microcontroller,
 };
 
+use self::microcontroller::{Group, IONodeInner, IONodeSerDe, MicrocontrollerSerDe};
+
 pub mod microcontroller;
 
 pub(crate) fn is_default<T: Default + PartialEq>(t: &T) -> bool {
@@ -17,6 +20,14 @@ pub(crate) fn is_default<T: Default + PartialEq>(t: &T) -> bool {
 impl From<Microcontroller> for MicrocontrollerSerDe {
     #[allow(clippy::too_many_lines)]
     fn from(mc: Microcontroller) -> Self {
+        let mut bridge_components: Vec<_> = mc.io.iter().map(|ion| ion.logic.clone()).collect();
+        bridge_components.sort_by_key(|c| {
+            mc.components_bridge_order
+                .iter()
+                .position(|id| *id == c.id)
+                .unwrap()
+        });
+
         MicrocontrollerSerDe {
             name: mc.name,
             description: mc.description,
@@ -58,76 +69,133 @@ impl From<Microcontroller> for MicrocontrollerSerDe {
                     .collect(),
             },
             group: Group {
-                data: microcontroller::Data { typ: mc.data_type, inputs: (), outputs: () },
-                groups: (),
-                component_states: mc
-                    .components
-                    .iter()
-                    .map(|c| ComponentsBridgeInnerObject {
-                        id: c.id,
-                        other: {
-                            let mut m = c.ser_to_map();
-                            let mut o = m.remove("object").unwrap().into_map().unwrap();
-                            if let Some(pos) = m.remove("pos") {
-                                o.insert_idx(0, "pos".into(), pos);
-                            }
-                            o.remove("@id");
-                            o
-                        },
-                    })
-                    .collect(),
-                component_bridge_states: {
-                    let mut v: Vec<_> = mc
-                        .io
-                        .iter()
-                        .map(|ion| &ion.logic)
-                        .map(|c| ComponentsBridgeInnerObject {
-                            id: c.id,
-                            other: {
-                                let mut m = c.ser_to_map();
-                                let mut o = m.remove("object").unwrap().into_map().unwrap();
-                                if let Some(pos) = m.remove("pos") {
-                                    o.insert_idx(0, "pos".into(), pos);
-                                }
-                                o.remove("@id");
-                                o
-                            },
-                        })
-                        .collect();
-
-                    v.sort_by_key(|c| {
-                        mc.components_bridge_order
-                            .iter()
-                            .position(|id| *id == c.id)
-                            .unwrap()
-                    });
-
-                    v
-                },
+                data: mc.data.into(),
+                groups: mc.groups.into_iter().map(Into::into).collect(),
+                component_states: mc.components.clone(),
+                component_bridge_states: bridge_components.clone(),
                 components: microcontroller::Components { components: mc.components },
                 components_bridge: microcontroller::ComponentsBridge {
-                    components_bridge: {
-                        let mut v: Vec<_> = mc.io.into_iter().map(|ion| ion.logic).collect();
+                    components_bridge: bridge_components,
+                },
+                group_states: tag_group_states(mc.group_states),
+            },
+        }
+    }
+}
 
-                        v.sort_by_key(|c| {
-                            mc.components_bridge_order
-                                .iter()
-                                .position(|id| *id == c.id)
-                                .unwrap()
-                        });
+impl From<GroupData> for microcontroller::Data {
+    fn from(data: GroupData) -> Self {
+        Self {
+            typ: data.typ,
+            inputs: microcontroller::DataInputs { nodes: data.inputs },
+            outputs: microcontroller::DataOutputs { nodes: data.outputs },
+        }
+    }
+}
 
-                        v
-                    },
+impl From<microcontroller::Data> for GroupData {
+    fn from(data: microcontroller::Data) -> Self {
+        Self {
+            typ: data.typ,
+            inputs: data.inputs.nodes,
+            outputs: data.outputs.nodes,
+        }
+    }
+}
+
+/// Tags the states `g0`, `g1`... like the game does.
+fn tag_group_states(states: Vec<GroupState>) -> Vec<(String, GroupState)> {
+    states
+        .into_iter()
+        .enumerate()
+        .map(|(i, state)| (format!("g{i}"), state))
+        .collect()
+}
+
+fn untag_group_states(states: Vec<(String, GroupState)>) -> Vec<GroupState> {
+    states.into_iter().map(|(_, state)| state).collect()
+}
+
+impl From<super::group::Group> for (String, Group) {
+    fn from(g: super::group::Group) -> Self {
+        (
+            g.tag,
+            Group {
+                data: g.data.into(),
+                component_states: g.components.clone(),
+                component_bridge_states: g.bridge_components.clone(),
+                components: microcontroller::Components { components: g.components },
+                components_bridge: microcontroller::ComponentsBridge {
+                    components_bridge: g.bridge_components,
                 },
-                group_states: (),
+                groups: g.groups.into_iter().map(Into::into).collect(),
+                group_states: tag_group_states(g.group_states),
             },
-        }
+        )
     }
 }
 
-impl From<MicrocontrollerSerDe> for Microcontroller {
-    fn from(mut sd: MicrocontrollerSerDe) -> Self {
+impl From<(String, Group)> for super::group::Group {
+    fn from((tag, g): (String, Group)) -> Self {
         Self {
+            tag,
+            data: g.data.into(),
+            components: g.components.components,
+            bridge_components: g.components_bridge.components_bridge,
+            groups: g.groups.into_iter().map(Into::into).collect(),
+            group_states: untag_group_states(g.group_states),
+        }
+    }
+}
+
+impl TryFrom<MicrocontrollerSerDe> for Microcontroller {
+    type Error = quick_xml::DeError;
+
+    fn try_from(mut sd: MicrocontrollerSerDe) -> Result<Self, Self::Error> {
+        let components_bridge_order = sd
+            .group
+            .components_bridge
+            .components_bridge
+            .iter()
+            .map(|bc| bc.id)
+            .collect();
+        let io = sd
+            .nodes
+            .nodes
+            .into_iter()
+            .map(|n| {
+                let c_idx = sd
+                    .group
+                    .components_bridge
+                    .components_bridge
+                    .iter()
+                    .position(|c| c.id == n.component_id)
+                    .ok_or_else(|| {
+                        ElementContext::attribute(
+                            "n",
+                            None,
+                            "component_id",
+                            &n.component_id.to_string(),
+                            format!("couldn't find node {}'s component", n.id),
+                        )
+                        .into_de::<quick_xml::DeError>()
+                    })?;
+                let c = sd.group.components_bridge.components_bridge.remove(c_idx);
+                Ok(IONode {
+                    design: IONodeDesign {
+                        node_id: n.id,
+                        label: n.node.label.clone(),
+                        mode: n.node.mode,
+                        typ: n.node.typ,
+                        description: n.node.description.clone(),
+                        position: n.node.position.into(),
+                    },
+                    logic: c,
+                })
+            })
+            .collect::<Result<_, Self::Error>>()?;
+
+        Ok(Self {
             name: sd.name,
             description: sd.description,
             width: sd.width,
@@ -138,49 +206,13 @@ impl From<MicrocontrollerSerDe> for Microcontroller {
                 sd.sym0, sd.sym1, sd.sym2, sd.sym3, sd.sym4, sd.sym5, sd.sym6, sd.sym7, sd.sym8,
                 sd.sym9, sd.sym10, sd.sym11, sd.sym12, sd.sym13, sd.sym14, sd.sym15,
             ],
-            data_type: sd.group.data.typ,
+            data: sd.group.data.into(),
+            groups: sd.group.groups.into_iter().map(Into::into).collect(),
+            group_states: untag_group_states(sd.group.group_states),
 
-            components_bridge_order: sd
-                .group
-                .components_bridge
-                .components_bridge
-                .iter()
-                .map(|bc| bc.id)
-                .collect(),
-            io: sd
-                .nodes
-                .nodes
-                .into_iter()
-                .map(|n| {
-                    let c_idx = sd
-                        .group
-                        .components_bridge
-                        .components_bridge
-                        .iter()
-                        .position(|c| c.id == n.component_id)
-                        .expect(&format!(
-                            "Couldn't find node {}'s component with id {}",
-                            n.id, n.component_id
-                        ));
-                    let c = sd.group.components_bridge.components_bridge.remove(c_idx);
-                    assert_eq!(
-                        n.component_id, c.id,
-                        "Node's component_id didn't match component's id"
-                    );
-                    IONode {
-                        design: IONodeDesign {
-                            node_id: n.id,
-                            label: n.node.label.clone(),
-                            mode: n.node.mode,
-                            typ: n.node.typ,
-                            description: n.node.description.clone(),
-                            position: n.node.position.into(),
-                        },
-                        logic: c,
-                    }
-                })
-                .collect(),
+            components_bridge_order,
+            io,
             components: sd.group.components.components,
-        }
+        })
     }
 }
@@ -5,17 +5,41 @@
 #![allow(clippy::expect_fun_call)]
 #![warn(missing_docs)]
 
+pub mod builder;
+pub mod canonical;
 pub mod components;
+pub mod composite;
+pub mod diff;
+pub mod dot;
+pub mod expr;
+pub mod golden;
+pub mod graph;
+pub mod group;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod latency;
+pub mod layout;
+pub mod library;
+pub mod lint;
+pub mod lua;
 pub mod mc_serde;
+pub mod properties;
+pub mod simulator;
 pub mod types;
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
-use crate::util::{serde_utils::PositionXY, AnyComponentMut, AnyComponentRef};
+use crate::util::{
+    serde_utils::{locate_de_error, PositionXY, XmlElementError},
+    AnyComponentMut, AnyComponentRef,
+};
 use components::{
     BridgeComponent, BridgeComponentType, Component, ComponentConnection, ComponentType,
-    TypedInputConnection, TypedOutputConnection,
+    CompositeChannel, TypedInputConnection, TypedOutputConnection,
 };
+pub use diff::{diff, diff_with};
+use expr::ExprError;
+use group::{Group, GroupData, GroupState};
 use mc_serde::microcontroller::{IONodeType, MicrocontrollerSerDe};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
@@ -25,7 +49,7 @@ use types::Type;
 ///
 /// Can be (de)serialized from XML using [`Microcontroller::from_xml_string()`] and [`Microcontroller::to_xml_string()`].
 #[derive(Serialize, Deserialize, Clone, Debug)]
-#[serde(from = "MicrocontrollerSerDe", into = "MicrocontrollerSerDe")]
+#[serde(try_from = "MicrocontrollerSerDe", into = "MicrocontrollerSerDe")]
 pub struct Microcontroller {
     /// The name of the microcontroller.
     pub name: String,
@@ -50,7 +74,8 @@ pub struct Microcontroller {
     /// 16x16 binary microcontroller icon.
     pub icon: [u16; 16],
 
-    data_type: Option<String>,
+    /// The data of the microcontroller's main group.
+    pub data: GroupData,
 
     /// Definition of IO nodes.
     ///
@@ -65,6 +90,13 @@ pub struct Microcontroller {
     ///
     /// Needs to be private so we can manage ids
     components: Vec<Component>,
+
+    /// Groups nested in the microcontroller.
+    ///
+    /// Needs to be private so we can manage ids
+    groups: Vec<Group>,
+    /// Saved state of the nested groups.
+    pub group_states: Vec<GroupState>,
 }
 
 #[allow(missing_docs)]
@@ -74,6 +106,8 @@ pub enum MCSerDeError {
     SerDeError(#[from] quick_xml::DeError),
     #[error(transparent)]
     ValidationError(#[from] MCValidationError),
+    #[error(transparent)]
+    ElementError(#[from] XmlElementError),
 }
 
 #[allow(missing_docs)]
@@ -91,6 +125,65 @@ pub enum MCValidationError {
     DuplicateComponentId(u32),
     #[error("Component id was greater than id_counter {found_id}/{max}")]
     ComponentIdTooHigh { found_id: u32, max: u32 },
+    #[error("Invalid expression in component {component_id}: {error}")]
+    InvalidExpression { component_id: u32, error: ExprError },
+    #[error("Component order map entry {0} has no IONode")]
+    UnknownComponentOrderEntry(u32),
+    #[error("Input {input_index} of component {component_id} is connected to missing component {missing_id}")]
+    MissingComponent {
+        component_id: u32,
+        input_index: usize,
+        missing_id: u32,
+    },
+    #[error("Input {input_index} of component {component_id} is connected to output {output_index} of component {source_id}, which doesn't exist")]
+    InvalidOutputIndex {
+        component_id: u32,
+        input_index: usize,
+        source_id: u32,
+        output_index: u8,
+    },
+    #[error("Input {input_index} of component {component_id} expects {expected:?} but is connected to {found:?}")]
+    TypeMismatch {
+        component_id: u32,
+        input_index: usize,
+        expected: Type,
+        found: Type,
+    },
+    #[error("IONode {node_id} position ({x}, {y}) is outside the microcontroller")]
+    IONodeOutOfBounds { node_id: u32, x: f32, y: f32 },
+    #[error("IONode {node_id} position ({x}, {y}) is already used by another node")]
+    DuplicateIONodePosition { node_id: u32, x: f32, y: f32 },
+    #[error("Composite write component {component_id} has count {count}, must be 1..=32")]
+    InvalidCompositeCount { component_id: u32, count: u8 },
+    #[error("Composite component {component_id} uses channel {channel}, must be 1..=32")]
+    InvalidCompositeChannel { component_id: u32, channel: i16 },
+}
+
+#[allow(missing_docs)]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ConnectError {
+    #[error("Unknown component id {0}")]
+    UnknownComponent(u32),
+    #[error("Component {component_id} has no output {index} (it has {count})")]
+    InvalidOutputIndex {
+        component_id: u32,
+        index: u8,
+        count: usize,
+    },
+    #[error("Component {component_id} has no input {index} (it has {count})")]
+    InvalidInputIndex {
+        component_id: u32,
+        index: u8,
+        count: usize,
+    },
+    #[error("Cannot connect a {output:?} output to a {input:?} input")]
+    TypeMismatch { output: Type, input: Type },
+    #[error("Input {index} of component {component_id} is already connected to {existing:?}")]
+    InputAlreadyConnected {
+        component_id: u32,
+        index: u8,
+        existing: ComponentConnection,
+    },
 }
 
 impl Microcontroller {
@@ -108,11 +201,21 @@ impl Microcontroller {
     /// # Errors
     /// Returns an [`Err(MCSerDeError)`] if the deserialization failed, or if the microcontroller was invalid.
     pub fn from_xml_str(xml: &str) -> Result<Self, MCSerDeError> {
-        let mc: Self = quick_xml::de::from_str(xml)?;
+        let mc = Self::from_xml_str_unvalidated(xml)?;
         mc.validate()?;
         Ok(mc)
     }
 
+    /// Like [`from_xml_str`][Self::from_xml_str], but doesn't check the microcontroller is valid.
+    ///
+    /// Use [`validate_all`][Self::validate_all] to find every problem with it.
+    ///
+    /// # Errors
+    /// Returns an [`Err(MCSerDeError)`] if the deserialization failed.
+    pub fn from_xml_str_unvalidated(xml: &str) -> Result<Self, MCSerDeError> {
+        quick_xml::de::from_str(xml).map_err(|e| locate_de_error::<MCSerDeError>(e, xml))
+    }
+
     /// Creates a new blank Microcontroller with the given name, description, and size.
     ///
     /// # Errors
@@ -132,9 +235,11 @@ impl Microcontroller {
             id_counter: 0,
             id_counter_node: None,
             icon: [0; 16],
-            data_type: None,
+            data: GroupData::default(),
             components: Vec::new(),
             components_bridge_order: Vec::new(),
+            groups: Vec::new(),
+            group_states: Vec::new(),
         };
         mc.validate()?;
         Ok(mc)
@@ -145,19 +250,34 @@ impl Microcontroller {
     /// Ideally, there should be no (safe) action that you can make to turn a valid [`Microcontroller`] invalid.
     ///
     /// # Errors
-    /// Returns an [`Err(MCValidationError)`] if the microcontroller was invalid.
+    /// Returns an [`Err(MCValidationError)`] with the first problem found if the microcontroller was invalid,
+    /// see [`Microcontroller::validate_all`] to get all of them.
     pub fn validate(&self) -> Result<(), MCValidationError> {
+        match self.validate_all().into_iter().next() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks this microcontroller for problems, collecting every error instead of stopping at the first.
+    ///
+    /// Returns an empty [`Vec`] if it is valid.
+    #[must_use]
+    pub fn validate_all(&self) -> Vec<MCValidationError> {
+        let mut errors = vec![];
+
         // check size in range
         if !(1..=6).contains(&self.width) || !(1..=6).contains(&self.length) {
-            return Err(MCValidationError::InvalidSize { w: self.width, h: self.length });
+            errors.push(MCValidationError::InvalidSize { w: self.width, h: self.length });
         }
 
         // check io nodes
         let mut unique = HashSet::new();
+        let mut positions: Vec<&PositionXY> = vec![];
         for ion in &self.io {
             // check all io node ids are unique
             if !unique.insert(ion.design.node_id) {
-                return Err(MCValidationError::DuplicateIONodeId(ion.design.node_id));
+                errors.push(MCValidationError::DuplicateIONodeId(ion.design.node_id));
             }
 
             // check components_bridge_order contains all io logic ids
@@ -166,36 +286,146 @@ impl Microcontroller {
                 .iter()
                 .any(|c| *c == ion.logic.id)
             {
-                return Err(MCValidationError::MissingIONodeComponentOrder(ion.logic.id));
+                errors.push(MCValidationError::MissingIONodeComponentOrder(ion.logic.id));
             }
 
             // check node ids aren't higher than max
             if ion.design.node_id > self.id_counter_node.unwrap_or(0) {
-                return Err(MCValidationError::NodeIdTooHigh {
+                errors.push(MCValidationError::NodeIdTooHigh {
                     found_id: ion.design.node_id,
                     max: self.id_counter_node.unwrap_or(0),
                 });
             }
+
+            // check node is on the grid and not on top of another
+            let pos = &ion.design.position;
+            if !(0.0..f32::from(self.width)).contains(&pos.x)
+                || !(0.0..f32::from(self.length)).contains(&pos.y)
+            {
+                errors.push(MCValidationError::IONodeOutOfBounds {
+                    node_id: ion.design.node_id,
+                    x: pos.x,
+                    y: pos.y,
+                });
+            }
+            if positions.contains(&pos) {
+                errors.push(MCValidationError::DuplicateIONodePosition {
+                    node_id: ion.design.node_id,
+                    x: pos.x,
+                    y: pos.y,
+                });
+            }
+            positions.push(pos);
         }
 
-        // check components
+        // check components_bridge_order doesn't contain anything else
+        for id in &self.components_bridge_order {
+            if !self.io.iter().any(|ion| ion.logic.id == *id) {
+                errors.push(MCValidationError::UnknownComponentOrderEntry(*id));
+            }
+        }
+
+        // check components, including the ones in nested groups since ids are shared
         let mut unique = HashSet::new();
-        for c in &self.components {
-            // check all io component ids are unique
-            if !unique.insert(c.id) {
-                return Err(MCValidationError::DuplicateComponentId(c.id));
+        let nested = self.groups.iter().flat_map(Group::ids);
+        for id in self.components().map(|c| c.id()).chain(nested) {
+            // check all component ids are unique
+            if !unique.insert(id) {
+                errors.push(MCValidationError::DuplicateComponentId(id));
             }
 
             // check component ids aren't higher than max
-            if c.id > self.id_counter {
-                return Err(MCValidationError::NodeIdTooHigh {
-                    found_id: c.id,
+            if id > self.id_counter {
+                errors.push(MCValidationError::ComponentIdTooHigh {
+                    found_id: id,
                     max: self.id_counter,
                 });
             }
         }
 
-        Ok(())
+        for c in &self.components {
+            // check function node expressions parse
+            if let Some(Err(error)) = expr::parse_component(&c.component) {
+                errors.push(MCValidationError::InvalidExpression { component_id: c.id, error });
+            }
+
+            Self::validate_composite(c, &mut errors);
+        }
+
+        self.validate_connections(&mut errors);
+
+        errors
+    }
+
+    fn validate_composite(c: &Component, errors: &mut Vec<MCValidationError>) {
+        let (count, channel) = match &c.component {
+            ComponentType::CompositeReadNum { channel, .. }
+            | ComponentType::CompositeReadOnOff { channel, .. } => (None, *channel),
+            ComponentType::_OldCompositeWriteNum { channel, .. }
+            | ComponentType::_OldCompositeWriteOnOff { channel, .. } => {
+                (None, CompositeChannel::Fixed(*channel))
+            },
+            ComponentType::CompositeWriteNum { count, offset, .. }
+            | ComponentType::CompositeWriteOnOff { count, offset, .. } => (Some(*count), *offset),
+            _ => return,
+        };
+
+        if let Some(count) = count {
+            if !(1..=32).contains(&count) {
+                errors.push(MCValidationError::InvalidCompositeCount { component_id: c.id, count });
+            }
+        }
+
+        if let CompositeChannel::Fixed(ch) = channel {
+            if ch >= 32 {
+                errors.push(MCValidationError::InvalidCompositeChannel {
+                    component_id: c.id,
+                    channel: i16::from(ch) + 1,
+                });
+            }
+        }
+    }
+
+    fn validate_connections(&self, errors: &mut Vec<MCValidationError>) {
+        let outputs: HashMap<u32, Vec<Type>> = self
+            .all_components()
+            .map(|c| (c.id(), c.io_def().outputs))
+            .collect();
+
+        for c in self.all_components() {
+            let inputs = c.io_def().inputs;
+            for (input_index, conn) in c.inputs().into_iter().enumerate() {
+                let Some(conn) = conn else { continue };
+
+                let Some(src) = outputs.get(&conn.component_id) else {
+                    errors.push(MCValidationError::MissingComponent {
+                        component_id: c.id(),
+                        input_index,
+                        missing_id: conn.component_id,
+                    });
+                    continue;
+                };
+
+                let Some(&found) = src.get(conn.node_index as usize) else {
+                    errors.push(MCValidationError::InvalidOutputIndex {
+                        component_id: c.id(),
+                        input_index,
+                        source_id: conn.component_id,
+                        output_index: conn.node_index,
+                    });
+                    continue;
+                };
+
+                if found != inputs[input_index] {
+                    errors.push(MCValidationError::TypeMismatch {
+                        component_id: c.id(),
+                        input_index,
+                        expected: inputs[input_index],
+                        found,
+                    });
+                }
+            }
+        }
     }
 
     /// Access the list of [`IONode`]s.
@@ -214,6 +444,8 @@ impl Microcontroller {
     }
 
     /// Adds a new [`IONode`] with the given properties and returns a mutable reference to it.
+    ///
+    /// The node is placed on the first free position of the microcontroller's grid.
     pub fn add_io(
         &mut self,
         label: Option<String>,
@@ -221,6 +453,12 @@ impl Microcontroller {
         typ: Type,
         mode: IONodeType,
     ) -> &mut IONode {
+        // put the node in the first free spot on the grid, if there is one
+        let position = (0..self.length)
+            .flat_map(|y| (0..self.width).map(move |x| PositionXY { x: x.into(), y: y.into() }))
+            .find(|p| self.io.iter().all(|ion| ion.design.position != *p))
+            .unwrap_or_default();
+
         let id_counter_node = self.id_counter_node.get_or_insert(0);
         *id_counter_node += 1;
         let node_id = *id_counter_node;
@@ -236,7 +474,7 @@ impl Microcontroller {
                     .unwrap_or_else(|| "The input signal to be processed.".into()),
                 typ,
                 mode,
-                position: PositionXY { x: 0.0, y: 0.0 },
+                position,
             },
             logic: {
                 #[allow(clippy::wildcard_in_or_patterns)]
@@ -337,6 +575,18 @@ impl Microcontroller {
         )
     }
 
+    /// Access every [`Component`] and [`BridgeComponent`], including the ones in nested
+    /// [`groups`][`Self::groups`].
+    ///
+    /// Starts with [`components`][`Self::components`], then the nested groups in storage order.
+    #[must_use]
+    pub fn all_components(&self) -> Box<dyn Iterator<Item = AnyComponentRef<'_>> + '_> {
+        Box::new(
+            self.components()
+                .chain(self.groups.iter().flat_map(Group::all_components)),
+        )
+    }
+
     /// Mutably access the list of [`Component`]s.
     ///
     /// The actual list is kept private so that the [`Microcontroller`] has full control over ids.
@@ -354,6 +604,25 @@ impl Microcontroller {
         )
     }
 
+    /// Access the [`Group`]s nested in this microcontroller.
+    ///
+    /// Their components aren't included in [`components`][`Self::components`], see
+    /// [`all_components`][`Self::all_components`].
+    #[must_use]
+    pub fn groups(&self) -> &[Group] {
+        &self.groups
+    }
+
+    /// Mutably access the [`Group`]s nested in this microcontroller.
+    ///
+    /// Groups can't be added or removed, but their components are public, so nothing stops their ids
+    /// from clashing with other components.
+    /// Use [`validate`][`Self::validate`] after adding or renumbering any.
+    #[must_use]
+    pub fn groups_mut(&mut self) -> &mut [Group] {
+        &mut self.groups
+    }
+
     /// Find a [`Component`] by its id.
     #[allow(clippy::must_use_candidate)]
     pub fn get_component(&self, id: u32) -> Option<AnyComponentRef> {
@@ -427,24 +696,85 @@ impl Microcontroller {
         }
     }
 
-    /// Connects two [`ComponentConnection`]s together, if possible.
+    /// Connects output `src.node_index` of `src.component_id` to input `dst.node_index` of `dst.component_id`.
+    ///
+    /// Both indices are checked against the components' [`io_def`][`ComponentType::io_def`] and the [`Type`]s must match.
     ///
     /// # Errors
-    /// Returns an [`Err`] if the connection could not be made.
-    // TODO: better return type
-    #[allow(clippy::result_unit_err)]
+    /// Returns an [`Err(ConnectError)`] if either end does not exist, the types differ,
+    /// or the input is already connected (see [`Microcontroller::disconnect`]).
     pub fn connect(
         &mut self,
         src: &ComponentConnection,
         dst: &ComponentConnection,
-    ) -> Result<(), ()> {
-        // TODO: valiate modes/types
-        if let Some(dst) = self.get_connection_mut(dst) {
-            *dst = Some(src.clone());
-            Ok(())
-        } else {
-            Err(())
+    ) -> Result<(), ConnectError> {
+        let outputs = self
+            .get_component(src.component_id)
+            .ok_or(ConnectError::UnknownComponent(src.component_id))?
+            .io_def()
+            .outputs;
+        let output =
+            *outputs
+                .get(src.node_index as usize)
+                .ok_or(ConnectError::InvalidOutputIndex {
+                    component_id: src.component_id,
+                    index: src.node_index,
+                    count: outputs.len(),
+                })?;
+
+        let inputs = self
+            .get_component(dst.component_id)
+            .ok_or(ConnectError::UnknownComponent(dst.component_id))?
+            .io_def()
+            .inputs;
+        let bad_input = ConnectError::InvalidInputIndex {
+            component_id: dst.component_id,
+            index: dst.node_index,
+            count: inputs.len(),
+        };
+        let input = *inputs
+            .get(dst.node_index as usize)
+            .ok_or(bad_input.clone())?;
+
+        if output != input {
+            return Err(ConnectError::TypeMismatch { output, input });
         }
+
+        let conn = self.get_connection_mut(dst).ok_or(bad_input)?;
+        if let Some(existing) = conn {
+            return Err(ConnectError::InputAlreadyConnected {
+                component_id: dst.component_id,
+                index: dst.node_index,
+                existing: existing.clone(),
+            });
+        }
+        *conn = Some(src.clone());
+
+        Ok(())
+    }
+
+    /// Disconnects input `dst.node_index` of `dst.component_id`, returning what it was connected to.
+    ///
+    /// # Errors
+    /// Returns an [`Err(ConnectError)`] if the component or input does not exist.
+    pub fn disconnect(
+        &mut self,
+        dst: &ComponentConnection,
+    ) -> Result<Option<ComponentConnection>, ConnectError> {
+        let count = self
+            .get_component(dst.component_id)
+            .ok_or(ConnectError::UnknownComponent(dst.component_id))?
+            .io_def()
+            .inputs
+            .len();
+
+        self.get_connection_mut(dst)
+            .map(Option::take)
+            .ok_or(ConnectError::InvalidInputIndex {
+                component_id: dst.component_id,
+                index: dst.node_index,
+                count,
+            })
     }
 }
 
@@ -0,0 +1,334 @@
+//! Module containing typed access to a microcontroller's property components.
+//!
+//! Properties are the values a player can change on a placed microcontroller, so
+//! [`Microcontroller::set_property`] is enough to stamp out variants of one controller.
+
+use thiserror::Error;
+
+use super::{
+    components::{Component, ComponentType, TextValue},
+    group::Group,
+    Microcontroller,
+};
+
+/// Error when setting a property, see [`Microcontroller::set_property`].
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum PropertyError {
+    /// No property has the given name.
+    #[error("No property named {0:?}")]
+    NotFound(String),
+    /// More than one property has the given name.
+    #[error("{count} properties are named {name:?}")]
+    Ambiguous {
+        /// The property name.
+        name: String,
+        /// How many properties have it.
+        count: usize,
+    },
+    /// The value is the wrong type for the property.
+    #[error("Can't set {component} {name:?} to {value:?}")]
+    WrongType {
+        /// The property name.
+        name: String,
+        /// Name of the component type.
+        component: &'static str,
+        /// The value.
+        value: PropertyValue,
+    },
+    /// The number is infinite or NaN.
+    #[error("Value {value} for property {name:?} isn't a finite number")]
+    NotFinite {
+        /// The property name.
+        name: String,
+        /// The value.
+        value: f64,
+    },
+    /// The number is outside a slider's range.
+    #[error("Value {value} for property {name:?} is outside {min}..={max}")]
+    OutOfRange {
+        /// The property name.
+        name: String,
+        /// The value.
+        value: f64,
+        /// The slider's minimum.
+        min: f64,
+        /// The slider's maximum.
+        max: f64,
+    },
+    /// The number isn't a multiple of a slider's rounding.
+    #[error("Value {value} for property {name:?} isn't a multiple of {rounding}")]
+    NotRounded {
+        /// The property name.
+        name: String,
+        /// The value.
+        value: f64,
+        /// The slider's rounding.
+        rounding: f64,
+    },
+    /// A dropdown has no option with the given label or value.
+    #[error("Property {name:?} has no option {option:?}")]
+    UnknownOption {
+        /// The property name.
+        name: String,
+        /// The label or value that was looked for.
+        option: PropertyValue,
+    },
+}
+
+/// Kind of a [`Property`], with the values it allows.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PropertyKind {
+    /// A [`ComponentType::PropertySlider`], rounded to multiples of `rounding` ingame.
+    #[allow(missing_docs)]
+    Slider { min: f64, max: f64, rounding: f64 },
+    /// A [`ComponentType::PropertyDropdown`] with `(label, value)` options.
+    Dropdown {
+        /// The options, in order.
+        options: Vec<(String, f64)>,
+    },
+    /// A [`ComponentType::PropertyToggle`] with labels for its two states.
+    #[allow(missing_docs)]
+    Toggle { on: String, off: String },
+    /// A [`ComponentType::PropertyNumber`], any finite number.
+    Number,
+    /// A [`ComponentType::PropertyText`], any text.
+    Text,
+}
+
+/// Value of a [`Property`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum PropertyValue {
+    /// Value of a slider or number.
+    Number(f64),
+    /// State of a toggle.
+    Bool(bool),
+    /// Value of a text property, or the label of a dropdown's selected option.
+    Text(String),
+}
+
+impl From<f64> for PropertyValue {
+    fn from(v: f64) -> Self {
+        Self::Number(v)
+    }
+}
+
+impl From<bool> for PropertyValue {
+    fn from(v: bool) -> Self {
+        Self::Bool(v)
+    }
+}
+
+impl From<&str> for PropertyValue {
+    fn from(v: &str) -> Self {
+        Self::Text(v.into())
+    }
+}
+
+impl From<String> for PropertyValue {
+    fn from(v: String) -> Self {
+        Self::Text(v)
+    }
+}
+
+/// A property component, see [`Microcontroller::properties`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Property {
+    /// Id of the component.
+    pub component_id: u32,
+    /// The name shown ingame.
+    pub name: String,
+    /// What kind of property it is.
+    pub kind: PropertyKind,
+    /// The current value.
+    pub value: PropertyValue,
+}
+
+impl Property {
+    fn from_component(c: &Component) -> Option<Self> {
+        let (name, kind, value) = match &c.component {
+            ComponentType::PropertySlider { name, min, max, int, v, .. } => (
+                name,
+                PropertyKind::Slider {
+                    min: min.value(),
+                    max: max.value(),
+                    rounding: int.value(),
+                },
+                PropertyValue::Number(v.value()),
+            ),
+            ComponentType::PropertyDropdown { name, selected, items, .. } => (
+                name,
+                PropertyKind::Dropdown {
+                    options: items
+                        .iter()
+                        .map(|i| (i.label.clone(), i.value.value()))
+                        .collect(),
+                },
+                PropertyValue::Text(
+                    items
+                        .get(*selected as usize)
+                        .map(|i| i.label.clone())
+                        .unwrap_or_default(),
+                ),
+            ),
+            ComponentType::PropertyToggle { name, on, off, value, .. } => (
+                name,
+                PropertyKind::Toggle { on: on.clone(), off: off.clone() },
+                PropertyValue::Bool(*value),
+            ),
+            ComponentType::PropertyNumber { name, value, .. } => (
+                name,
+                PropertyKind::Number,
+                PropertyValue::Number(value.value()),
+            ),
+            ComponentType::PropertyText { name, val } => {
+                (name, PropertyKind::Text, PropertyValue::Text(val.clone()))
+            },
+            _ => return None,
+        };
+        Some(Self {
+            component_id: c.id(),
+            name: name.clone(),
+            kind,
+            value,
+        })
+    }
+}
+
+/// Stores `value`, keeping the text as entered if it already has that value.
+fn set_number(name: &str, tv: &mut TextValue, value: f64) -> Result<(), PropertyError> {
+    if !value.is_finite() {
+        return Err(PropertyError::NotFinite { name: name.into(), value });
+    }
+    #[allow(clippy::float_cmp)]
+    if tv.value() != value {
+        *tv = TextValue::from_value(value);
+    }
+    Ok(())
+}
+
+fn set_value(component: &mut ComponentType, value: PropertyValue) -> Result<(), PropertyError> {
+    let component_name = component.name();
+    match (component, value) {
+        (
+            ComponentType::PropertySlider { name, min, max, int, v, .. },
+            PropertyValue::Number(x),
+        ) => {
+            let (min, max, rounding) = (min.value(), max.value(), int.value());
+            if x.is_finite() && !(min..=max).contains(&x) {
+                return Err(PropertyError::OutOfRange { name: name.clone(), value: x, min, max });
+            }
+            // allow for the steps not being exact in binary, like 0.1
+            let steps = x / rounding;
+            if x.is_finite() && rounding > 0.0 && (steps - steps.round()).abs() > 1e-9 {
+                return Err(PropertyError::NotRounded { name: name.clone(), value: x, rounding });
+            }
+            set_number(name, v, x)
+        },
+        (ComponentType::PropertyDropdown { name, selected, items, .. }, option) => {
+            let i = match &option {
+                #[allow(clippy::float_cmp)]
+                PropertyValue::Number(x) => items.iter().position(|i| i.value.value() == *x),
+                PropertyValue::Text(label) => items.iter().position(|i| i.label == *label),
+                PropertyValue::Bool(_) => {
+                    return Err(PropertyError::WrongType {
+                        name: name.clone(),
+                        component: component_name,
+                        value: option,
+                    })
+                },
+            };
+            let i = i.ok_or_else(|| PropertyError::UnknownOption { name: name.clone(), option })?;
+            #[allow(clippy::cast_possible_truncation)]
+            let i = i as u32;
+            *selected = i;
+            Ok(())
+        },
+        (ComponentType::PropertyToggle { value, .. }, PropertyValue::Bool(b)) => {
+            *value = b;
+            Ok(())
+        },
+        (ComponentType::PropertyNumber { name, value, .. }, PropertyValue::Number(x)) => {
+            set_number(name, value, x)
+        },
+        (ComponentType::PropertyText { val, .. }, PropertyValue::Text(s)) => {
+            *val = s;
+            Ok(())
+        },
+        (c, value) => Err(PropertyError::WrongType {
+            name: property_name(c).unwrap_or_default().into(),
+            component: component_name,
+            value,
+        }),
+    }
+}
+
+fn property_name(c: &ComponentType) -> Option<&str> {
+    match c {
+        ComponentType::PropertySlider { name, .. }
+        | ComponentType::PropertyDropdown { name, .. }
+        | ComponentType::PropertyToggle { name, .. }
+        | ComponentType::PropertyNumber { name, .. }
+        | ComponentType::PropertyText { name, .. } => Some(name),
+        _ => None,
+    }
+}
+
+fn collect<'a>(components: &'a [Component], groups: &'a [Group], out: &mut Vec<&'a Component>) {
+    out.extend(components);
+    for g in groups {
+        collect(&g.components, &g.groups, out);
+    }
+}
+
+fn collect_mut<'a>(
+    components: &'a mut [Component],
+    groups: &'a mut [Group],
+    out: &mut Vec<&'a mut Component>,
+) {
+    out.extend(components);
+    for Group { components, groups, .. } in groups {
+        collect_mut(components, groups, out);
+    }
+}
+
+impl Microcontroller {
+    /// Lists every property component, including the ones in nested [`groups`][`Self::groups`].
+    #[must_use]
+    pub fn properties(&self) -> Vec<Property> {
+        let mut components = vec![];
+        collect(&self.components, &self.groups, &mut components);
+        components
+            .into_iter()
+            .filter_map(Property::from_component)
+            .collect()
+    }
+
+    /// Sets the value of the property with the given name.
+    ///
+    /// Sliders and numbers take a [`PropertyValue::Number`], which for sliders has to be in range
+    /// and a multiple of the rounding. Toggles take a [`PropertyValue::Bool`] and text properties a
+    /// [`PropertyValue::Text`]. Dropdowns select the first option with the given label (or value,
+    /// for a number).
+    ///
+    /// # Errors
+    /// Returns a [`PropertyError`] if there isn't exactly one property with that name, or the
+    /// value isn't allowed for it.
+    pub fn set_property(
+        &mut self,
+        name: &str,
+        value: impl Into<PropertyValue>,
+    ) -> Result<(), PropertyError> {
+        let mut components = vec![];
+        collect_mut(&mut self.components, &mut self.groups, &mut components);
+        let mut found: Vec<_> = components
+            .into_iter()
+            .filter(|c| property_name(&c.component) == Some(name))
+            .collect();
+
+        match found.len() {
+            0 => Err(PropertyError::NotFound(name.into())),
+            1 => set_value(&mut found[0].component, value.into()),
+            count => Err(PropertyError::Ambiguous { name: name.into(), count }),
+        }
+    }
+}
@@ -0,0 +1,710 @@
+//! Module containing a headless, tick-based logic simulator for microcontrollers.
+//!
+//! Every component reads the values its inputs had on the previous tick, so each component
+//! (including the IO bridge components) adds one tick of propagation delay, just like ingame.
+
+use std::collections::HashMap;
+
+use thiserror::Error;
+
+use super::{
+    components::{
+        state::ComponentState, BridgeComponentType, ComponentConnection, ComponentType,
+        CompositeChannel, PulseMode, TimerUnits, UpDownCounterMode,
+    },
+    expr::{self, Expr},
+    mc_serde::microcontroller::IONodeType,
+    types::Type,
+    IONode, Microcontroller,
+};
+use crate::util::AnyComponentRef;
+
+/// Number of logic ticks per second ingame.
+pub const TICKS_PER_SECOND: u32 = 60;
+
+/// Number of channels of each kind in a composite signal.
+pub const COMPOSITE_CHANNELS: usize = 32;
+
+/// A composite signal, made of 32 number channels and 32 on/off channels.
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct Composite {
+    /// The number channels.
+    pub numbers: [f32; COMPOSITE_CHANNELS],
+    /// The on/off channels.
+    pub bools: [bool; COMPOSITE_CHANNELS],
+}
+
+/// A value carried by a logic connection.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Value {
+    /// On/Off value.
+    OnOff(bool),
+    /// Number value.
+    Number(f32),
+    /// Composite value.
+    Composite(Composite),
+    /// Video value (not simulated).
+    Video,
+    /// Audio value (not simulated).
+    Audio,
+}
+
+impl Value {
+    /// Gets the value an unconnected input/output of the given [`Type`] has.
+    #[must_use]
+    pub fn default_for(typ: Type) -> Self {
+        match typ {
+            Type::Number => Self::Number(0.0),
+            Type::Composite => Self::Composite(Composite::default()),
+            Type::Video => Self::Video,
+            Type::Audio => Self::Audio,
+            _ => Self::OnOff(false),
+        }
+    }
+
+    /// Gets the [`Type`] of this value.
+    #[must_use]
+    pub fn typ(&self) -> Type {
+        match self {
+            Self::OnOff(_) => Type::OnOff,
+            Self::Number(_) => Type::Number,
+            Self::Composite(_) => Type::Composite,
+            Self::Video => Type::Video,
+            Self::Audio => Type::Audio,
+        }
+    }
+
+    /// Gets the on/off value, or `false` if this is not an on/off value.
+    #[must_use]
+    pub fn as_bool(&self) -> bool {
+        match self {
+            Self::OnOff(b) => *b,
+            _ => false,
+        }
+    }
+
+    /// Gets the number value, or `0` if this is not a number value.
+    #[must_use]
+    pub fn as_number(&self) -> f32 {
+        match self {
+            Self::Number(n) => *n,
+            _ => 0.0,
+        }
+    }
+
+    /// Gets the composite value, or an empty composite if this is not a composite value.
+    #[must_use]
+    pub fn as_composite(&self) -> Composite {
+        match self {
+            Self::Composite(c) => *c,
+            _ => Composite::default(),
+        }
+    }
+}
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum SimError {
+    #[error("No IONode with id {0}")]
+    UnknownNode(u32),
+    #[error("No IONode with label {0:?}")]
+    UnknownLabel(String),
+    #[error("IONode {0} is not an input")]
+    NotAnInput(u32),
+    #[error("IONode {0} is not an output")]
+    NotAnOutput(u32),
+    #[error("IONode {node_id} has type {expected:?} but the value was {found:?}")]
+    TypeMismatch {
+        node_id: u32,
+        expected: Type,
+        found: Type,
+    },
+}
+
+/// Runtime state of a single component.
+///
+/// Each stateful component only uses the fields it needs.
+#[derive(Clone, Default, Debug)]
+struct State {
+    /// Stored number (memory register, counter, PID total error, delta/capacitor level).
+    num: f32,
+    /// Secondary stored number (PID previous error).
+    num2: f32,
+    /// Stored on/off (latches, toggles, initialization flags).
+    flag: bool,
+    /// The on/off input from the previous tick (edge detection).
+    prev: bool,
+    /// Elapsed ticks (blinker, timers).
+    ticks: u32,
+}
+
+/// Headless simulator for a [`Microcontroller`].
+///
+/// Values are fed into input [`IONode`]s with [`Simulator::set_input()`], the logic is advanced with
+/// [`Simulator::tick()`], and results are read with [`Simulator::output()`].
+/// Components in nested [`groups`][`Microcontroller::groups`] are simulated along with the rest.
+///
+/// Function nodes are evaluated with [`expr`]. Not simulated: [`ComponentType::Lua`] scripts, video, and audio.
+pub struct Simulator<'a> {
+    mc: &'a Microcontroller,
+    tick: u64,
+    /// Current output values of every component, by component id.
+    outputs: HashMap<u32, Vec<Value>>,
+    /// Runtime state of every component, by component id.
+    states: HashMap<u32, State>,
+    /// Values fed into the input bridge components, by component id.
+    inputs: HashMap<u32, Value>,
+    /// Parsed function node expressions, by component id.
+    exprs: HashMap<u32, Expr>,
+    /// Input types of every component, by component id.
+    input_types: HashMap<u32, Vec<Type>>,
+}
+
+impl<'a> Simulator<'a> {
+    /// Creates a new [`Simulator`] with every value at its default.
+    #[must_use]
+    pub fn new(mc: &'a Microcontroller) -> Self {
+        let mut outputs = HashMap::new();
+        let mut input_types = HashMap::new();
+        for c in mc.all_components() {
+            let io = c.io_def();
+            let outs = io.outputs.into_iter().map(Value::default_for).collect();
+            outputs.insert(c.id(), outs);
+            input_types.insert(c.id(), io.inputs);
+        }
+
+        let exprs = mc
+            .all_components()
+            .filter_map(|c| match c {
+                AnyComponentRef::Component(c) => {
+                    Some((c.id, expr::parse_component(&c.component)?.ok()?))
+                },
+                AnyComponentRef::BridgeComponent(_) => None,
+            })
+            .collect();
+
+        Self {
+            mc,
+            tick: 0,
+            outputs,
+            states: HashMap::new(),
+            inputs: HashMap::new(),
+            exprs,
+            input_types,
+        }
+    }
+
+    /// Gets the number of ticks simulated so far.
+    #[allow(clippy::must_use_candidate)]
+    pub fn tick_count(&self) -> u64 {
+        self.tick
+    }
+
+    /// Sets the value fed into the input [`IONode`] with the given node id.
+    ///
+    /// The value is picked up on the next [`tick`][`Simulator::tick()`].
+    ///
+    /// # Errors
+    /// Returns an [`Err(SimError)`] if the node does not exist, is not an input, or has a different type.
+    pub fn set_input(&mut self, node_id: u32, value: Value) -> Result<(), SimError> {
+        let ion = self.find_node(node_id)?;
+        if ion.design.mode != IONodeType::Input {
+            return Err(SimError::NotAnInput(node_id));
+        }
+        if ion.design.typ != value.typ() {
+            return Err(SimError::TypeMismatch {
+                node_id,
+                expected: ion.design.typ,
+                found: value.typ(),
+            });
+        }
+
+        self.inputs.insert(ion.logic.id, value);
+        Ok(())
+    }
+
+    /// Sets the value fed into the input [`IONode`] with the given label.
+    ///
+    /// # Errors
+    /// Returns an [`Err(SimError)`] if the node does not exist, is not an input, or has a different type.
+    pub fn set_input_by_label(&mut self, label: &str, value: Value) -> Result<(), SimError> {
+        let node_id = self.find_label(label, IONodeType::Input)?;
+        self.set_input(node_id, value)
+    }
+
+    /// Gets the value currently output by the output [`IONode`] with the given node id.
+    ///
+    /// # Errors
+    /// Returns an [`Err(SimError)`] if the node does not exist or is not an output.
+    pub fn output(&self, node_id: u32) -> Result<Value, SimError> {
+        let ion = self.find_node(node_id)?;
+        if ion.design.mode != IONodeType::Output {
+            return Err(SimError::NotAnOutput(node_id));
+        }
+
+        Ok(self
+            .component_output(ion.logic.id, 0)
+            .unwrap_or_else(|| Value::default_for(ion.design.typ)))
+    }
+
+    /// Gets the value currently output by the output [`IONode`] with the given label.
+    ///
+    /// # Errors
+    /// Returns an [`Err(SimError)`] if the node does not exist or is not an output.
+    pub fn output_by_label(&self, label: &str) -> Result<Value, SimError> {
+        self.output(self.find_label(label, IONodeType::Output)?)
+    }
+
+    /// Gets the value currently on the given output of a component.
+    #[allow(clippy::must_use_candidate)]
+    pub fn component_output(&self, component_id: u32, index: u8) -> Option<Value> {
+        self.outputs
+            .get(&component_id)
+            .and_then(|o| o.get(index as usize))
+            .copied()
+    }
+
+    /// Advances the simulation by one tick.
+    pub fn tick(&mut self) {
+        let mut next = HashMap::with_capacity(self.outputs.len());
+
+        for c in self.mc.all_components() {
+            let ins: Vec<Value> = c
+                .inputs()
+                .into_iter()
+                .zip(&self.input_types[&c.id()])
+                .map(|(conn, &typ)| self.read(conn.as_ref(), typ))
+                .collect();
+
+            let state = self.states.entry(c.id()).or_default();
+            let outs = match c {
+                AnyComponentRef::Component(c) => {
+                    step_component(&c.component, &ins, state, self.exprs.get(&c.id))
+                },
+                AnyComponentRef::BridgeComponent(bc) => match bc.component {
+                    BridgeComponentType::OnOffIn { .. }
+                    | BridgeComponentType::NumberIn { .. }
+                    | BridgeComponentType::CompositeIn { .. }
+                    | BridgeComponentType::VideoIn { .. }
+                    | BridgeComponentType::AudioIn { .. } => {
+                        // a nested group's input passes on whatever its input is wired to
+                        vec![self.inputs.get(&bc.id).copied().unwrap_or(ins[0])]
+                    },
+                    BridgeComponentType::OnOffOut { .. }
+                    | BridgeComponentType::NumberOut { .. }
+                    | BridgeComponentType::CompositeOut { .. }
+                    | BridgeComponentType::VideoOut { .. }
+                    | BridgeComponentType::AudioOut { .. } => vec![ins[0]],
+                },
+            };
+
+            next.insert(c.id(), outs);
+        }
+
+        self.outputs = next;
+        self.tick += 1;
+    }
+
+    /// Advances the simulation by the given number of ticks.
+    pub fn run(&mut self, ticks: u64) {
+        for _ in 0..ticks {
+            self.tick();
+        }
+    }
+
+    fn read(&self, conn: Option<&ComponentConnection>, typ: Type) -> Value {
+        conn.and_then(|conn| self.component_output(conn.component_id, conn.node_index))
+            .unwrap_or_else(|| Value::default_for(typ))
+    }
+
+    fn find_node(&self, node_id: u32) -> Result<&'a IONode, SimError> {
+        self.mc
+            .io
+            .iter()
+            .find(|ion| ion.design.node_id == node_id)
+            .ok_or(SimError::UnknownNode(node_id))
+    }
+
+    fn find_label(&self, label: &str, mode: IONodeType) -> Result<u32, SimError> {
+        self.mc
+            .io
+            .iter()
+            .find(|ion| ion.design.mode == mode && ion.design.label == label)
+            .map(|ion| ion.design.node_id)
+            .ok_or_else(|| SimError::UnknownLabel(label.into()))
+    }
+}
+
+/// Converts a duration into ticks.
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    clippy::cast_precision_loss
+)]
+pub(crate) fn duration_ticks(duration: f32, units: TimerUnits) -> u32 {
+    let ticks = if units == TimerUnits::Ticks {
+        duration
+    } else {
+        duration * TICKS_PER_SECOND as f32
+    };
+    ticks.max(0.0).round() as u32
+}
+
+/// Converts a 1-based channel number into a channel index, if it is in range.
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    clippy::cast_precision_loss
+)]
+fn channel_index(channel: f32) -> Option<usize> {
+    let ch = channel.round();
+    (1.0..=COMPOSITE_CHANNELS as f32)
+        .contains(&ch)
+        .then(|| ch as usize - 1)
+}
+
+/// Resolves the channel setting of a composite read node.
+fn read_channel(channel: CompositeChannel, variable: f32) -> Option<usize> {
+    match channel {
+        CompositeChannel::Variable => channel_index(variable),
+        CompositeChannel::Fixed(ch) => Some(usize::from(ch)).filter(|ch| *ch < COMPOSITE_CHANNELS),
+    }
+}
+
+/// Resolves the first channel written by a composite write node.
+fn write_start(offset: CompositeChannel, start: f32) -> Option<usize> {
+    match offset {
+        CompositeChannel::Variable => channel_index(start),
+        CompositeChannel::Fixed(ch) => Some(usize::from(ch)),
+    }
+}
+
+#[allow(
+    clippy::too_many_lines,
+    clippy::float_cmp,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    clippy::cast_precision_loss,
+    clippy::cast_possible_wrap
+)]
+fn step_component(
+    component: &ComponentType,
+    ins: &[Value],
+    state: &mut State,
+    expr: Option<&Expr>,
+) -> Vec<Value> {
+    let b = |i: usize| ins[i].as_bool();
+    let n = |i: usize| ins[i].as_number();
+    let on = Value::OnOff;
+    let num = Value::Number;
+
+    match component {
+        ComponentType::NOT { .. } => vec![on(!b(0))],
+        ComponentType::AND { .. } => vec![on(b(0) && b(1))],
+        ComponentType::OR { .. } => vec![on(b(0) || b(1))],
+        ComponentType::XOR { .. } => vec![on(b(0) != b(1))],
+        ComponentType::NAND { .. } => vec![on(!(b(0) && b(1)))],
+        ComponentType::NOR { .. } => vec![on(!(b(0) || b(1)))],
+        ComponentType::Add { .. } => vec![num(n(0) + n(1))],
+        ComponentType::Subtract { .. } => vec![num(n(0) - n(1))],
+        ComponentType::Multiply { .. } => vec![num(n(0) * n(1))],
+        ComponentType::Divide { .. } => {
+            if n(1) == 0.0 {
+                vec![num(0.0), on(true)]
+            } else {
+                vec![num(n(0) / n(1)), on(false)]
+            }
+        },
+        ComponentType::Modulo { .. } => {
+            if n(1) == 0.0 {
+                vec![num(0.0)]
+            } else {
+                vec![num(n(0) % n(1))]
+            }
+        },
+        ComponentType::Abs { .. } => vec![num(n(0).abs())],
+        ComponentType::Clamp { min, max, .. } => {
+            let (min, max) = (min.value() as f32, max.value() as f32);
+            vec![num(n(0).max(min).min(max))]
+        },
+        ComponentType::Threshold { min, max, .. } => {
+            let (min, max) = (min.value() as f32, max.value() as f32);
+            vec![on(min <= n(0) && n(0) <= max)]
+        },
+        ComponentType::GreaterThan { .. } => vec![on(n(0) > n(1))],
+        ComponentType::LessThan { .. } => vec![on(n(0) < n(1))],
+        ComponentType::Equal { epsilon, .. } => {
+            vec![on((n(0) - n(1)).abs() <= epsilon.value() as f32)]
+        },
+        ComponentType::ConstantNum { n: c, .. } => vec![num(c.value() as f32)],
+        ComponentType::ConstantOn { .. } => vec![on(true)],
+        ComponentType::PropertySlider { v, .. } => vec![num(v.value() as f32)],
+        ComponentType::PropertyDropdown { items, selected, .. } => {
+            let item = items.get(*selected as usize);
+            vec![num(item.map_or(0.0, |i| i.value.value() as f32))]
+        },
+        ComponentType::PropertyToggle { value, .. } => vec![on(*value)],
+        ComponentType::PropertyNumber { value, .. } => vec![num(value.value() as f32)],
+        ComponentType::PropertyText { .. }
+        | ComponentType::TooltipNum { .. }
+        | ComponentType::TooltipOnOff { .. } => vec![],
+        ComponentType::NumericalJunction { .. } => {
+            if b(1) {
+                vec![num(n(0)), num(0.0)]
+            } else {
+                vec![num(0.0), num(n(0))]
+            }
+        },
+        ComponentType::NumericalSwitchbox { .. }
+        | ComponentType::CompositeSwitchbox { .. }
+        | ComponentType::VideoSwitchbox { .. }
+        | ComponentType::AudioSwitchbox { .. } => {
+            vec![if b(2) { ins[0] } else { ins[1] }]
+        },
+        ComponentType::MemoryRegister { reset_value, .. } => {
+            if !state.flag {
+                state.flag = true;
+                if let Ok(Some(ComponentState::MemoryRegister(m))) = component.state() {
+                    state.num = m.value;
+                }
+            }
+            if b(1) {
+                state.num = reset_value.value() as f32;
+            } else if b(0) {
+                state.num = n(2);
+            }
+            vec![num(state.num)]
+        },
+        ComponentType::SRLatch { .. } => {
+            if b(0) {
+                state.flag = true;
+            }
+            if b(1) {
+                state.flag = false;
+            }
+            vec![on(state.flag), on(!state.flag)]
+        },
+        ComponentType::JKFlipFlop { .. } => {
+            match (b(0), b(1)) {
+                (true, true) => state.flag = !state.flag,
+                (true, false) => state.flag = true,
+                (false, true) => state.flag = false,
+                (false, false) => {},
+            }
+            vec![on(state.flag), on(!state.flag)]
+        },
+        ComponentType::Capacitor { ct, dt, .. } => {
+            // `num` is the charge level in 0..=1
+            if b(0) {
+                state.num = if *ct <= 0.0 {
+                    1.0
+                } else {
+                    (state.num + 1.0 / (ct * TICKS_PER_SECOND as f32)).min(1.0)
+                };
+                vec![on(state.num >= 1.0)]
+            } else {
+                state.num = if *dt <= 0.0 {
+                    0.0
+                } else {
+                    (state.num - 1.0 / (dt * TICKS_PER_SECOND as f32)).max(0.0)
+                };
+                vec![on(state.num > 0.0)]
+            }
+        },
+        ComponentType::Blinker { on: on_secs, off: off_secs, .. } => {
+            if b(0) {
+                let on_ticks = duration_ticks(*on_secs, TimerUnits::Seconds).max(1);
+                let period = on_ticks + duration_ticks(*off_secs, TimerUnits::Seconds).max(1);
+                let out = state.ticks % period < on_ticks;
+                state.ticks = (state.ticks + 1) % period;
+                vec![on(out)]
+            } else {
+                state.ticks = 0;
+                vec![on(false)]
+            }
+        },
+        ComponentType::PushToToggle { .. } => {
+            if b(0) && !state.prev {
+                state.flag = !state.flag;
+            }
+            state.prev = b(0);
+            vec![on(state.flag)]
+        },
+        ComponentType::Pulse { mode, .. } => {
+            let out = match mode {
+                PulseMode::OffToOn => b(0) && !state.prev,
+                PulseMode::OnToOff => !b(0) && state.prev,
+                PulseMode::Always => b(0) != state.prev,
+            };
+            state.prev = b(0);
+            vec![on(out)]
+        },
+        ComponentType::TimerTON { units, .. } => {
+            if b(0) {
+                state.ticks = state.ticks.saturating_add(1);
+            } else {
+                state.ticks = 0;
+            }
+            vec![on(b(0) && state.ticks >= duration_ticks(n(1), *units))]
+        },
+        ComponentType::TimerTOF { units, .. } => {
+            // only times after enable has been on, so it doesn't start out on
+            if b(0) {
+                state.ticks = 0;
+                state.flag = true;
+                vec![on(false)]
+            } else {
+                state.ticks = state.ticks.saturating_add(1);
+                vec![on(state.flag && state.ticks <= duration_ticks(n(1), *units))]
+            }
+        },
+        ComponentType::TimerRTO { units, .. } => {
+            if b(2) {
+                state.ticks = 0;
+            } else if b(0) {
+                state.ticks = state.ticks.saturating_add(1);
+            }
+            vec![on(state.ticks >= duration_ticks(n(1), *units))]
+        },
+        ComponentType::TimerRTF { units, .. } => {
+            if b(2) {
+                state.ticks = 0;
+            } else if !b(0) {
+                state.ticks = state.ticks.saturating_add(1);
+            }
+            vec![on(!b(0)
+                && !b(2)
+                && state.ticks <= duration_ticks(n(1), *units))]
+        },
+        ComponentType::PIDController { kp, ki, kd, .. } => vec![num(step_pid(
+            state,
+            n(0) - n(1),
+            b(2),
+            (kp.value() as f32, ki.value() as f32, kd.value() as f32),
+        ))],
+        ComponentType::PIDControllerAdvanced { .. } => {
+            vec![num(step_pid(state, n(0) - n(1), b(5), (n(2), n(3), n(4))))]
+        },
+        ComponentType::Delta { .. } => {
+            let out = n(0) - state.num;
+            state.num = n(0);
+            vec![num(out)]
+        },
+        ComponentType::UpDownCounter { mode, reset_val, increment, min, max, .. } => {
+            if !state.flag || b(2) {
+                state.flag = true;
+                state.num = reset_val.value() as f32;
+            } else {
+                if b(0) {
+                    state.num += increment.value() as f32;
+                }
+                if b(1) {
+                    state.num -= increment.value() as f32;
+                }
+            }
+            if *mode == UpDownCounterMode::Clamped {
+                state.num = state.num.max(min.value() as f32).min(max.value() as f32);
+            }
+            vec![num(state.num)]
+        },
+        ComponentType::CompositeReadOnOff { channel, .. } => {
+            let c = ins[0].as_composite();
+            vec![on(
+                read_channel(*channel, n(1)).is_some_and(|ch| c.bools[ch])
+            )]
+        },
+        ComponentType::CompositeReadNum { channel, .. } => {
+            let c = ins[0].as_composite();
+            vec![num(
+                read_channel(*channel, n(1)).map_or(0.0, |ch| c.numbers[ch])
+            )]
+        },
+        ComponentType::_OldCompositeWriteOnOff { channel, .. } => {
+            let mut c = ins[0].as_composite();
+            if let Some(v) = c.bools.get_mut(*channel as usize) {
+                *v = b(1);
+            }
+            vec![Value::Composite(c)]
+        },
+        ComponentType::_OldCompositeWriteNum { channel, .. } => {
+            let mut c = ins[0].as_composite();
+            if let Some(v) = c.numbers.get_mut(*channel as usize) {
+                *v = n(1);
+            }
+            vec![Value::Composite(c)]
+        },
+        ComponentType::CompositeWriteNum { count, offset, .. } => {
+            let mut c = ins[0].as_composite();
+            if let Some(start) = write_start(*offset, n(33)) {
+                for i in 0..(*count as usize).min(COMPOSITE_CHANNELS) {
+                    if let Some(v) = c.numbers.get_mut(start + i) {
+                        *v = n(1 + i);
+                    }
+                }
+            }
+            vec![Value::Composite(c)]
+        },
+        ComponentType::CompositeWriteOnOff { count, offset, .. } => {
+            let mut c = ins[0].as_composite();
+            if let Some(start) = write_start(*offset, n(33)) {
+                for i in 0..(*count as usize).min(COMPOSITE_CHANNELS) {
+                    if let Some(v) = c.bools.get_mut(start + i) {
+                        *v = b(1 + i);
+                    }
+                }
+            }
+            vec![Value::Composite(c)]
+        },
+        ComponentType::NumToCompositeBin { .. } => {
+            let bits = n(0) as i32 as u32;
+            let mut c = Composite::default();
+            for (i, v) in c.bools.iter_mut().enumerate() {
+                *v = bits & (1 << i) != 0;
+            }
+            vec![Value::Composite(c)]
+        },
+        ComponentType::CompositeBinToNum { .. } => {
+            let c = ins[0].as_composite();
+            let bits = c
+                .bools
+                .iter()
+                .enumerate()
+                .fold(0u32, |acc, (i, v)| acc | (u32::from(*v) << i));
+            vec![num(bits as i32 as f32)]
+        },
+        ComponentType::Func1n { .. }
+        | ComponentType::Func3n { .. }
+        | ComponentType::Func8n { .. } => {
+            let vars: Vec<f64> = ins.iter().map(|v| f64::from(v.as_number())).collect();
+            vec![num(expr.map_or(0.0, |e| e.eval_number(&vars) as f32))]
+        },
+        ComponentType::Func4b { .. } | ComponentType::Func8b { .. } => {
+            let vars: Vec<bool> = ins.iter().map(Value::as_bool).collect();
+            vec![on(expr.is_some_and(|e| e.eval_bool(&vars)))]
+        },
+        // not simulated
+        ComponentType::Lua { .. } => component
+            .io_def()
+            .outputs
+            .into_iter()
+            .map(Value::default_for)
+            .collect(),
+    }
+}
+
+/// Advances a PID controller by one tick and returns its output.
+///
+/// `num` holds the total error and `num2` the previous error, `prev` is whether `num2` is valid.
+fn step_pid(state: &mut State, error: f32, active: bool, (kp, ki, kd): (f32, f32, f32)) -> f32 {
+    if !active {
+        *state = State::default();
+        return 0.0;
+    }
+
+    state.num += error;
+    let derivative = if state.prev { error - state.num2 } else { 0.0 };
+    state.num2 = error;
+    state.prev = true;
+
+    kp * error + ki * state.num + kd * derivative
+}
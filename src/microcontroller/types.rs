@@ -1,10 +1,25 @@
 //! Module containing things related to the game's types
 
+use std::{fmt, str::FromStr};
+
 use serde::{Deserialize, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
+use thiserror::Error;
 
 /// An enum representing the types of data available in the game.
-#[derive(Serialize_repr, Deserialize_repr, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[derive(
+    Serialize_repr,
+    Deserialize_repr,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Debug,
+    Default,
+)]
 #[repr(u8)]
 pub enum Type {
     /// On/Off (bool) value.
@@ -28,6 +43,60 @@ pub enum Type {
     _Rope = 8,
 }
 
+/// Every [`Type`] with its name, as used by [`Display`][fmt::Display] and [`FromStr`].
+const NAMES: [(Type, &str); 9] = [
+    (Type::OnOff, "on_off"),
+    (Type::Number, "number"),
+    (Type::_Power, "power"),
+    (Type::_Fluid, "fluid"),
+    (Type::_Electric, "electric"),
+    (Type::Composite, "composite"),
+    (Type::Video, "video"),
+    (Type::Audio, "audio"),
+    (Type::_Rope, "rope"),
+];
+
+/// Error for a type name or number that isn't a [`Type`].
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("unknown type {value:?}")]
+pub struct UnknownType {
+    /// The name or number that was given.
+    pub value: String,
+}
+
+impl fmt::Display for Type {
+    /// Writes the snake case name of the type, like `on_off`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (_, name) = NAMES.iter().find(|(t, _)| t == self).ok_or(fmt::Error)?;
+        f.write_str(name)
+    }
+}
+
+impl FromStr for Type {
+    type Err = UnknownType;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        NAMES
+            .iter()
+            .find(|(_, n)| *n == s)
+            .map(|(t, _)| *t)
+            .ok_or_else(|| UnknownType { value: s.into() })
+    }
+}
+
+impl TryFrom<u32> for Type {
+    type Error = UnknownType;
+
+    /// Converts the number the game stores the type as.
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        NAMES
+            .iter()
+            .find(|(t, _)| u32::from(*t as u8) == value)
+            .map(|(t, _)| *t)
+            .ok_or_else(|| UnknownType { value: value.to_string() })
+    }
+}
+
 /// Trait that represents a [`Type`] at compile-time.
 ///
 /// Used in ser/de code to do different things depending on IO Type.
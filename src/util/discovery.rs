@@ -0,0 +1,306 @@
+//! Module for finding the game install and the user's data folder.
+//!
+//! The install is found through Steam's `libraryfolders.vdf`, the user data folder in `%appdata%`
+//! or, on Linux, in the game's Proton prefix. Both can be overridden with environment variables,
+//! see [`INSTALL_DIR_VAR`], [`DATA_DIR_VAR`] and [`STEAM_DIR_VAR`].
+//!
+//! ```no_run
+//! use sw_rs::util::discovery::Discovery;
+//!
+//! let paths = Discovery::from_env().find();
+//! if let Some(meshes) = paths.meshes() {
+//!     println!("meshes are in {}", meshes.display());
+//! }
+//! ```
+
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
+
+/// Steam app id of the game.
+pub const APP_ID: &str = "573090";
+
+/// Environment variable with the game install folder (the one containing `rom`).
+pub const INSTALL_DIR_VAR: &str = "STORMWORKS_INSTALL_DIR";
+/// Environment variable with the user data folder (the one containing `data/microprocessors`).
+pub const DATA_DIR_VAR: &str = "STORMWORKS_DATA_DIR";
+/// Environment variable with an extra Steam folder to look for libraries in.
+pub const STEAM_DIR_VAR: &str = "STORMWORKS_STEAM_DIR";
+
+/// Where the user data is relative to the Windows user folder.
+const APPDATA_PATH: &str = "AppData/Roaming/Stormworks";
+
+/// Where to look for the game and its data, see [`Discovery::find`].
+///
+/// Use [`Discovery::from_env`] for the real system, or [`Discovery::with_env`] to point it at
+/// another directory tree.
+#[derive(Clone, Debug, Default)]
+pub struct Discovery {
+    /// Game install folder to use instead of searching for it.
+    pub install_override: Option<PathBuf>,
+    /// User data folder to use instead of searching for it.
+    pub data_override: Option<PathBuf>,
+    /// Steam folders to read `libraryfolders.vdf` from, in order of preference.
+    pub steam_roots: Vec<PathBuf>,
+    /// Folders that may contain the user data folder as `Stormworks`, like `%appdata%`.
+    pub data_dirs: Vec<PathBuf>,
+}
+
+/// Paths found by [`Discovery::find`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GamePaths {
+    /// The game install folder, containing `rom`.
+    pub install: Option<PathBuf>,
+    /// The user data folder, containing `data`.
+    pub user_data: Option<PathBuf>,
+}
+
+impl GamePaths {
+    /// Gets the `rom/data/definitions` folder with the component definitions.
+    #[must_use]
+    pub fn definitions(&self) -> Option<PathBuf> {
+        Some(
+            self.install
+                .as_ref()?
+                .join("rom")
+                .join("data")
+                .join("definitions"),
+        )
+    }
+
+    /// Gets the `rom/meshes` folder.
+    #[must_use]
+    pub fn meshes(&self) -> Option<PathBuf> {
+        Some(self.install.as_ref()?.join("rom").join("meshes"))
+    }
+
+    /// Gets the `data/vehicles` folder with the user's saved vehicles.
+    #[must_use]
+    pub fn vehicles(&self) -> Option<PathBuf> {
+        Some(self.user_data.as_ref()?.join("data").join("vehicles"))
+    }
+
+    /// Gets the `data/microprocessors` folder with the user's saved microcontrollers.
+    #[must_use]
+    pub fn microprocessors(&self) -> Option<PathBuf> {
+        Some(
+            self.user_data
+                .as_ref()?
+                .join("data")
+                .join("microprocessors"),
+        )
+    }
+}
+
+/// Joins a `/` separated relative path.
+fn join(base: &Path, rel: &str) -> PathBuf {
+    rel.split('/').fold(base.to_path_buf(), |p, c| p.join(c))
+}
+
+impl Discovery {
+    /// Creates a [`Discovery`] for this system, using the environment variable overrides, the
+    /// home folder and the data folder from [`dirs`].
+    #[must_use]
+    pub fn from_env() -> Self {
+        Self::with_env(
+            |name| std::env::var_os(name),
+            dirs::home_dir().as_deref(),
+            dirs::data_dir().as_deref(),
+        )
+    }
+
+    /// Creates a [`Discovery`] from the given environment variable lookup, home folder and data
+    /// folder (`%appdata%` on Windows, `~/.local/share` on Linux).
+    ///
+    /// Steam is looked for in its usual places under `home` (native, Flatpak and Snap installs),
+    /// the default Windows install folder, and [`STEAM_DIR_VAR`].
+    pub fn with_env(
+        var: impl Fn(&str) -> Option<OsString>,
+        home: Option<&Path>,
+        data_dir: Option<&Path>,
+    ) -> Self {
+        let var = |name| var(name).filter(|v| !v.is_empty()).map(PathBuf::from);
+
+        let mut steam_roots: Vec<PathBuf> = var(STEAM_DIR_VAR).into_iter().collect();
+        if let Some(home) = home {
+            for rel in [
+                ".steam/steam",
+                ".local/share/Steam",
+                ".var/app/com.valvesoftware.Steam/.local/share/Steam",
+                "snap/steam/common/.local/share/Steam",
+            ] {
+                steam_roots.push(join(home, rel));
+            }
+        }
+        if let Some(data_dir) = data_dir {
+            steam_roots.push(data_dir.join("Steam"));
+        }
+        if cfg!(windows) {
+            steam_roots.push(PathBuf::from(r"C:\Program Files (x86)\Steam"));
+        }
+
+        Self {
+            install_override: var(INSTALL_DIR_VAR),
+            data_override: var(DATA_DIR_VAR),
+            steam_roots,
+            data_dirs: data_dir.map(Path::to_path_buf).into_iter().collect(),
+        }
+    }
+
+    /// Gets the Steam library folders, the ones listing the game first.
+    ///
+    /// Libraries that can't be read are skipped.
+    #[must_use]
+    pub fn steam_libraries(&self) -> Vec<PathBuf> {
+        let mut with_game = vec![];
+        let mut others = vec![];
+        for root in &self.steam_roots {
+            for vdf in ["steamapps/libraryfolders.vdf", "config/libraryfolders.vdf"] {
+                let Ok(src) = std::fs::read_to_string(join(root, vdf)) else {
+                    continue;
+                };
+                for (path, has_game) in library_folders(&src).unwrap_or_default() {
+                    if !with_game.contains(&path) && !others.contains(&path) {
+                        if has_game {
+                            with_game.push(path);
+                        } else {
+                            others.push(path);
+                        }
+                    }
+                }
+            }
+            // the Steam folder is a library itself, even if it isn't listed
+            if root.join("steamapps").is_dir()
+                && !with_game.contains(root)
+                && !others.contains(root)
+            {
+                others.push(root.clone());
+            }
+        }
+        with_game.extend(others);
+        with_game
+    }
+
+    /// Finds the game install and user data folders.
+    ///
+    /// Overrides are used as-is, everything else is only returned if it exists.
+    #[must_use]
+    pub fn find(&self) -> GamePaths {
+        let libraries = self.steam_libraries();
+
+        let install = self.install_override.clone().or_else(|| {
+            libraries
+                .iter()
+                .map(|lib| join(lib, "steamapps/common/Stormworks"))
+                .find(|p| p.join("rom").is_dir())
+        });
+
+        let user_data = self.data_override.clone().or_else(|| {
+            let native = self.data_dirs.iter().map(|d| d.join("Stormworks"));
+            let proton = libraries.iter().map(|lib| {
+                let prefix = join(lib, &format!("steamapps/compatdata/{APP_ID}/pfx"));
+                join(&prefix, &format!("drive_c/users/steamuser/{APPDATA_PATH}"))
+            });
+            native.chain(proton).find(|p| p.join("data").is_dir())
+        });
+
+        GamePaths { install, user_data }
+    }
+}
+
+/// A value in a Valve KeyValues (`.vdf`) file.
+enum Vdf {
+    String(String),
+    Map(Vec<(String, Vdf)>),
+}
+
+impl Vdf {
+    fn get(&self, key: &str) -> Option<&Vdf> {
+        match self {
+            Vdf::Map(m) => m
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(key))
+                .map(|(_, v)| v),
+            Vdf::String(_) => None,
+        }
+    }
+}
+
+/// Splits a `.vdf` file into quoted strings and braces.
+fn vdf_tokens(src: &str) -> Option<Vec<Result<String, char>>> {
+    let mut tokens = vec![];
+    let mut chars = src.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' | '}' => tokens.push(Err(c)),
+            '"' => {
+                let mut s = String::new();
+                loop {
+                    match chars.next()? {
+                        '"' => break,
+                        '\\' => match chars.next()? {
+                            'n' => s.push('\n'),
+                            't' => s.push('\t'),
+                            c => s.push(c),
+                        },
+                        c => s.push(c),
+                    }
+                }
+                tokens.push(Ok(s));
+            },
+            '/' if chars.peek() == Some(&'/') => {
+                chars.find(|&c| c == '\n');
+            },
+            c if c.is_whitespace() => {},
+            _ => return None,
+        }
+    }
+    Some(tokens)
+}
+
+/// Parses `key value` pairs until a `}` or the end of the tokens.
+fn vdf_map(tokens: &mut impl Iterator<Item = Result<String, char>>) -> Option<Vdf> {
+    let mut map = vec![];
+    loop {
+        let key = match tokens.next() {
+            Some(Ok(key)) => key,
+            Some(Err('}')) | None => return Some(Vdf::Map(map)),
+            Some(Err(_)) => return None,
+        };
+        let value = match tokens.next()? {
+            Ok(s) => Vdf::String(s),
+            Err('{') => vdf_map(tokens)?,
+            Err(_) => return None,
+        };
+        map.push((key, value));
+    }
+}
+
+/// Gets the library folders listed in a `libraryfolders.vdf`, with whether each one lists the game.
+///
+/// Supports both the current format (`"0" { "path" "..." "apps" { ... } }`) and the old one
+/// (`"1" "..."`), where which library has the game isn't known.
+fn library_folders(src: &str) -> Option<Vec<(PathBuf, bool)>> {
+    let root = vdf_map(&mut vdf_tokens(src)?.into_iter())?;
+    let Some(Vdf::Map(folders)) = root.get("libraryfolders") else {
+        return None;
+    };
+
+    Some(
+        folders
+            .iter()
+            .filter_map(|(key, v)| match v {
+                Vdf::String(path) if key.parse::<u32>().is_ok() => Some((path.into(), false)),
+                Vdf::Map(_) => match v.get("path") {
+                    Some(Vdf::String(path)) => {
+                        let has_game = v.get("apps").and_then(|apps| apps.get(APP_ID)).is_some();
+                        Some((path.into(), has_game))
+                    },
+                    _ => None,
+                },
+                Vdf::String(_) => None,
+            })
+            .collect(),
+    )
+}
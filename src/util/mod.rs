@@ -2,32 +2,27 @@
 
 use std::path::PathBuf;
 
-use crate::microcontroller::components::{BridgeComponent, Component, ComponentConnection};
+use crate::microcontroller::components::{
+    BridgeComponent, Component, ComponentConnection, ComponentIODef,
+};
 
 use self::serde_utils::PositionXY;
 
-pub(crate) mod fakemap_hack;
+pub mod discovery;
 pub mod serde_utils;
 
 /// Finds the path of the user's microcontroller data folder.
 ///
-/// It should be at `%appdata%/Stormworks/data/microprocessors/`.
+/// It should be at `%appdata%/Stormworks/data/microprocessors/`, or in the game's Proton prefix on
+/// Linux. See [`discovery`] for the places that are searched and how to override them.
 ///
 /// # Errors
 ///
 /// Will return an [`Err`] if the path cannot be found.
 pub fn find_microcontroller_folder() -> Result<PathBuf, &'static str> {
-    if let Some(data_dir) = dirs::data_dir() {
-        let mcs = data_dir.join(
-            "Stormworks/data/microprocessors/".replace('/', &std::path::MAIN_SEPARATOR.to_string()),
-        );
-        if mcs.exists() {
-            Ok(mcs)
-        } else {
-            Err("Could not find folder at %appdata%/Stormworks/data/microprocessors/, please specify full path to microprocessors folder.")
-        }
-    } else {
-        Err("Could not find %appdata%, please specify full path to microprocessors folder.")
+    match discovery::Discovery::from_env().find().microprocessors() {
+        Some(mcs) if mcs.exists() => Ok(mcs),
+        _ => Err("Could not find the Stormworks/data/microprocessors/ folder, please specify full path to microprocessors folder."),
     }
 }
 
@@ -56,6 +51,14 @@ impl AnyComponentRef<'_> {
         }
     }
 
+    #[must_use]
+    pub fn io_def(&self) -> ComponentIODef {
+        match self {
+            AnyComponentRef::Component(c) => c.component.io_def(),
+            AnyComponentRef::BridgeComponent(bc) => bc.component.io_def(),
+        }
+    }
+
     #[must_use]
     pub fn inputs(&self) -> Vec<&Option<ComponentConnection>> {
         match self {
@@ -98,6 +101,14 @@ impl<'a> AnyComponentMut<'a> {
         }
     }
 
+    #[must_use]
+    pub fn io_def(&self) -> ComponentIODef {
+        match self {
+            AnyComponentMut::Component(c) => c.component.io_def(),
+            AnyComponentMut::BridgeComponent(bc) => bc.component.io_def(),
+        }
+    }
+
     #[must_use]
     pub fn inputs(&self) -> Vec<&Option<ComponentConnection>> {
         match self {
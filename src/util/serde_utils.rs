@@ -1,14 +1,21 @@
 //! Module containing some utility types for ser/de
 
+use std::cell::RefCell;
+
 use fakemap::FakeMap;
 use serde::{Deserialize, Serialize};
 
 use crate::microcontroller::{components::de_from_str, mc_serde::is_default};
 
+/// An XML element kept as it appears in the file, for parts of the format that aren't modelled.
+///
+/// Attributes are stored with an `@` prefix and text content as `$text`.
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(untagged)]
-pub(crate) enum RecursiveStringMap {
+pub enum RecursiveStringMap {
+    /// An element with only text (or nothing) in it.
     String(String),
+    /// An element with attributes or children, in order.
     Map(FakeMap<String, RecursiveStringMap>),
 }
 
@@ -19,6 +26,7 @@ impl Default for RecursiveStringMap {
 }
 
 impl RecursiveStringMap {
+    /// Gets the attributes and children, or [`None`] if this element only has text.
     #[must_use]
     pub fn into_map(self) -> Option<FakeMap<String, RecursiveStringMap>> {
         match self {
@@ -28,6 +36,48 @@ impl RecursiveStringMap {
     }
 }
 
+/// (De)serializes a [`Vec`] of `(tag, value)` pairs as child elements with those tags, keeping
+/// their order.
+pub(crate) mod tagged {
+    use serde::{de::MapAccess, Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer, T: Serialize>(
+        items: &[(String, T)],
+        ser: S,
+    ) -> Result<S::Ok, S::Error> {
+        ser.collect_map(items.iter().map(|(tag, v)| (tag, v)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, T: Deserialize<'de>>(
+        de: D,
+    ) -> Result<Vec<(String, T)>, D::Error> {
+        struct V<T>(std::marker::PhantomData<T>);
+
+        impl<'de, T: Deserialize<'de>> serde::de::Visitor<'de> for V<T> {
+            type Value = Vec<(String, T)>;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a list of elements")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, _: &str) -> Result<Self::Value, E> {
+                // empty element
+                Ok(Vec::new())
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut items = Vec::new();
+                while let Some(entry) = map.next_entry()? {
+                    items.push(entry);
+                }
+                Ok(items)
+            }
+        }
+
+        de.deserialize_map(V(std::marker::PhantomData))
+    }
+}
+
 /// A 2D f32 position that (de)serializes to/from "x" and "y".
 #[derive(Serialize, Deserialize, Default, PartialEq, Clone, Debug)]
 #[serde(rename = "node")]
@@ -74,19 +124,6 @@ impl From<PositionXY> for PositionXZ {
     }
 }
 
-impl From<PositionXY> for RecursiveStringMap {
-    fn from(val: PositionXY) -> Self {
-        let mut m = FakeMap::new();
-        if val.x != 0.0 {
-            m.insert("@x".into(), RecursiveStringMap::String(val.x.to_string()));
-        }
-        if val.y != 0.0 {
-            m.insert("@y".into(), RecursiveStringMap::String(val.y.to_string()));
-        }
-        RecursiveStringMap::Map(m)
-    }
-}
-
 /// A 2D i32 position that (de)serializes to/from "x", "y", and "z".
 #[derive(Serialize, Deserialize, Default, PartialEq, Clone, Debug)]
 #[serde(rename = "node")]
@@ -243,3 +280,186 @@ where
 {
     format!("{n:.6}").serialize(ser)
 }
+
+/// An element (or one of its attributes) that failed to deserialize.
+///
+/// Returned by the `from_xml_str` functions instead of a plain [`quick_xml::DeError`] when the
+/// problem could be pinned to a specific element.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XmlElementError {
+    /// Name of the element, `object` for microcontroller components.
+    pub tag: String,
+    /// Id of the component, if the element belongs to one.
+    pub component_id: Option<u32>,
+    /// Name of the offending attribute, if it's known.
+    pub attribute: Option<String>,
+    /// 1-based line of the element (or attribute) in the XML, if it could be found.
+    pub line: Option<usize>,
+    /// 1-based column of the element (or attribute) in the XML, if it could be found.
+    pub column: Option<usize>,
+    /// What went wrong.
+    pub message: String,
+}
+
+impl std::fmt::Display for XmlElementError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<{}>", self.tag)?;
+        if let Some(id) = self.component_id {
+            write!(f, " component {id}")?;
+        }
+        if let Some(attribute) = &self.attribute {
+            write!(f, " attribute {attribute:?}")?;
+        }
+        if let (Some(line), Some(column)) = (self.line, self.column) {
+            write!(f, " at line {line}, column {column}")?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+impl std::error::Error for XmlElementError {}
+
+/// Where an [`XmlElementError`] happened, before it's been found in the XML.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ElementContext {
+    tag: String,
+    component_id: Option<u32>,
+    /// Name and value of the offending attribute.
+    attribute: Option<(String, String)>,
+    message: String,
+}
+
+thread_local! {
+    /// The last [`ElementContext`] turned into a deserialization error on this thread.
+    ///
+    /// Serde errors can only be made from a message, so the context is kept here for
+    /// [`locate_de_error`] to pick up again.
+    static LAST_CONTEXT: RefCell<Option<ElementContext>> = const { RefCell::new(None) };
+}
+
+impl std::fmt::Display for ElementContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<{}>", self.tag)?;
+        if let Some(id) = self.component_id {
+            write!(f, " component {id}")?;
+        }
+        if let Some((attribute, value)) = &self.attribute {
+            write!(f, " attribute {attribute}=\"{value}\"")?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+impl ElementContext {
+    /// Context for an error in the component with the given id.
+    pub(crate) fn component(tag: &str, id: Option<u32>, err: impl std::fmt::Display) -> Self {
+        Self {
+            tag: tag.into(),
+            component_id: id,
+            attribute: None,
+            message: err.to_string(),
+        }
+    }
+
+    /// Context for an error in the given attribute.
+    pub(crate) fn attribute(
+        tag: &str,
+        id: Option<u32>,
+        attribute: &str,
+        value: &str,
+        err: impl std::fmt::Display,
+    ) -> Self {
+        Self {
+            attribute: Some((attribute.into(), value.into())),
+            ..Self::component(tag, id, err)
+        }
+    }
+
+    /// Turns this into a deserialization error, remembering it for [`locate_de_error`].
+    pub(crate) fn into_de<E: serde::de::Error>(self) -> E {
+        let err = E::custom(&self);
+        LAST_CONTEXT.with_borrow_mut(|last| *last = Some(self));
+        err
+    }
+}
+
+impl XmlElementError {
+    /// Gets the context `err` was made from by [`ElementContext::into_de`] and finds it in `xml`.
+    fn from_de(err: &quick_xml::DeError, xml: &str) -> Option<Self> {
+        let quick_xml::DeError::Custom(msg) = err else {
+            return None;
+        };
+        // the last context might be from an error that was recovered from, so check it's this one
+        let context = LAST_CONTEXT
+            .with_borrow_mut(Option::take)
+            .filter(|c| c.to_string() == *msg)?;
+
+        let attribute = context
+            .attribute
+            .as_ref()
+            .map(|(a, v)| (a.as_str(), v.as_str()));
+        let (line, column) = find_element(xml, &context.tag, context.component_id, attribute)
+            .map_or((None, None), |(l, c)| (Some(l), Some(c)));
+
+        Some(Self {
+            tag: context.tag,
+            component_id: context.component_id,
+            attribute: context.attribute.map(|(a, _)| a),
+            line,
+            column,
+            message: context.message,
+        })
+    }
+}
+
+/// Converts a deserialization error, using an [`XmlElementError`] if it can be pinned to an element.
+pub(crate) fn locate_de_error<E>(err: quick_xml::DeError, xml: &str) -> E
+where
+    E: From<quick_xml::DeError> + From<XmlElementError>,
+{
+    match XmlElementError::from_de(&err, xml) {
+        Some(e) => e.into(),
+        None => err.into(),
+    }
+}
+
+/// Finds the line and column of the first `<tag>` with the given id and/or attribute value.
+///
+/// Points at the attribute if there is one, otherwise at the start of the element.
+fn find_element(
+    xml: &str,
+    tag: &str,
+    id: Option<u32>,
+    attribute: Option<(&str, &str)>,
+) -> Option<(usize, usize)> {
+    let has_attr = |open: &str, needle: &str| {
+        open.match_indices(needle)
+            .find(|(i, _)| open[..*i].ends_with(char::is_whitespace))
+            .map(|(i, _)| i)
+    };
+
+    let pos = xml
+        .match_indices(&format!("<{tag}"))
+        .find_map(|(start, m)| {
+            let open = &xml[start..];
+            let open = &open[..open.find('>')?];
+            if !open[m.len()..].starts_with(|c: char| c.is_whitespace() || c == '/') && open != m {
+                return None;
+            }
+            if let Some(id) = id {
+                has_attr(open, &format!("id=\"{id}\""))?;
+            }
+            match attribute {
+                Some((a, v)) => has_attr(open, &format!("{a}=\"{v}\"")).map(|i| start + i),
+                None => Some(start),
+            }
+        })?;
+
+    let before = &xml[..pos];
+    let line = before.matches('\n').count() + 1;
+    let column = before[before.rfind('\n').map_or(0, |i| i + 1)..]
+        .chars()
+        .count()
+        + 1;
+    Some((line, column))
+}
@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use crate::util::serde_utils::RecursiveStringMap;
+use crate::util::serde_utils::{locate_de_error, RecursiveStringMap, XmlElementError};
 
 use self::body::Body;
 
@@ -39,6 +39,8 @@ impl<C: Default + PartialEq> Bodies<C> {
 pub enum VehicleSerDeError {
     #[error(transparent)]
     SerDeError(#[from] quick_xml::DeError),
+    #[error(transparent)]
+    ElementError(#[from] XmlElementError),
 }
 
 impl<'a, C: Default + PartialEq + Serialize + Deserialize<'a>> Vehicle<C> {
@@ -55,7 +57,8 @@ impl<'a, C: Default + PartialEq + Serialize + Deserialize<'a>> Vehicle<C> {
     /// # Errors
     /// Returns an [`Err(VehicleSerDeError)`] if the deserialization failed, or if the microcontroller was invalid.
     pub fn from_xml_str(xml: &'a str) -> Result<Self, VehicleSerDeError> {
-        let mc: Self = quick_xml::de::from_str(xml)?;
+        let mc: Self = quick_xml::de::from_str(xml)
+            .map_err(|e| locate_de_error::<VehicleSerDeError>(e, xml))?;
         Ok(mc)
     }
 
@@ -0,0 +1,62 @@
+use sw_rs::microcontroller::{
+    builder::Builder,
+    simulator::{Simulator, Value},
+    types::{TComposite, TNumber, TOnOff},
+    Microcontroller,
+};
+
+#[test]
+fn test_builder_circuit() {
+    let mut mc = Microcontroller::default();
+    let mut b = Builder::new(&mut mc);
+
+    let x = b.input::<TNumber>("X");
+    let y = b.input::<TNumber>("Y");
+    let sum = b.add(x, y);
+    let two = b.constant(2);
+    let doubled = b.mul(sum, two);
+    let big = b.greater_than(doubled, y);
+    b.output::<TNumber>("Doubled", doubled);
+    b.output::<TOnOff>("Big", big);
+
+    assert!(b.out::<TNumber>(big.component_id(), 0).is_none());
+    assert!(b.out::<TOnOff>(big.component_id(), 0).is_some());
+    assert!(b.out::<TOnOff>(big.component_id(), 1).is_none());
+
+    mc.validate().unwrap();
+    let mc = Microcontroller::from_xml_str(&mc.to_xml_string().unwrap()).unwrap();
+
+    let mut sim = Simulator::new(&mc);
+    sim.set_input_by_label("X", Value::Number(1.0)).unwrap();
+    sim.set_input_by_label("Y", Value::Number(3.0)).unwrap();
+    sim.run(10);
+    assert_eq!(sim.output_by_label("Doubled").unwrap(), Value::Number(8.0));
+    assert_eq!(sim.output_by_label("Big").unwrap(), Value::OnOff(true));
+}
+
+#[test]
+fn test_builder_composite_channels() {
+    let mut mc = Microcontroller::default();
+    let mut b = Builder::new(&mut mc);
+    let c = b.input::<TComposite>("C");
+    b.composite_read_number(c, 1);
+    b.composite_read_on_off(c, 32);
+
+    for channel in [0, 33] {
+        let read_number = std::panic::catch_unwind(|| {
+            let mut mc = Microcontroller::default();
+            let mut b = Builder::new(&mut mc);
+            let c = b.input::<TComposite>("C");
+            b.composite_read_number(c, channel);
+        });
+        assert!(read_number.is_err(), "channel {channel}");
+
+        let read_on_off = std::panic::catch_unwind(|| {
+            let mut mc = Microcontroller::default();
+            let mut b = Builder::new(&mut mc);
+            let c = b.input::<TComposite>("C");
+            b.composite_read_on_off(c, channel);
+        });
+        assert!(read_on_off.is_err(), "channel {channel}");
+    }
+}
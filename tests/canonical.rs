@@ -0,0 +1,74 @@
+use sw_rs::microcontroller::{
+    builder::Builder,
+    types::{TNumber, TOnOff},
+    Microcontroller,
+};
+
+/// Builds the same logic, adding things in a different order when `flip` is set.
+fn build(flip: bool) -> Microcontroller {
+    let mut mc = Microcontroller::default();
+    let mut b = Builder::new(&mut mc);
+
+    let (x, y) = if flip {
+        let y = b.input::<TNumber>("Y");
+        (b.input::<TNumber>("X"), y)
+    } else {
+        let x = b.input::<TNumber>("X");
+        (x, b.input::<TNumber>("Y"))
+    };
+    // leave some gaps in the ids
+    let unused = b.constant(0).component_id();
+    let c = b.constant(2);
+    let (a, d) = if flip {
+        let d = b.mul(y, c);
+        (b.mul(x, c), d)
+    } else {
+        let a = b.mul(x, c);
+        (a, b.mul(y, c))
+    };
+    let big = b.greater_than(a, d);
+    b.output::<TOnOff>("Big", big);
+    mc.remove_component_id(unused);
+
+    // nodes are placed in the order they're added, so put the inputs back where they'd be unflipped
+    if flip {
+        let io = mc.io_nodes_mut();
+        let pos = io[0].design.position.clone();
+        io[0].design.position = io[1].design.position.clone();
+        io[1].design.position = pos;
+    }
+    mc
+}
+
+#[test]
+fn test_canonicalize() {
+    let mut a = build(false);
+    let mut b = build(true);
+    assert_ne!(a.to_xml_string().unwrap(), b.to_xml_string().unwrap());
+
+    a.canonicalize();
+    b.canonicalize();
+    a.validate().unwrap();
+    assert_eq!(a.to_xml_string().unwrap(), b.to_xml_string().unwrap());
+
+    // ids are dense and follow the data flow
+    let mut ids: Vec<u32> = a.components().map(|c| c.id()).collect();
+    ids.sort_unstable();
+    assert_eq!(ids, (1..=7).collect::<Vec<_>>());
+    let io_id = |label: &str| {
+        a.io_nodes()
+            .iter()
+            .find(|ion| ion.design.label == label)
+            .map(|ion| ion.logic.id())
+            .unwrap()
+    };
+    assert!(io_id("X") < io_id("Big") && io_id("Y") < io_id("Big"));
+
+    // canonicalizing is idempotent
+    let xml = a.to_xml_string().unwrap();
+    a.canonicalize();
+    assert_eq!(a.to_xml_string().unwrap(), xml);
+
+    // and new ids continue from the end
+    assert_eq!(Builder::new(&mut a).constant_on().component_id(), 8);
+}
@@ -0,0 +1,272 @@
+#![cfg(feature = "cli")]
+
+mod common;
+
+use std::process::Command;
+
+use serde_json::Value;
+
+use common::temp_dir;
+
+/// Runs `sw` with `args`, returning its exit code and stdout.
+fn sw(args: &[&str]) -> (i32, String) {
+    let out = Command::new(env!("CARGO_BIN_EXE_sw"))
+        .args(args)
+        .output()
+        .unwrap();
+    (
+        out.status.code().unwrap(),
+        String::from_utf8(out.stdout).unwrap(),
+    )
+}
+
+/// Runs `sw` with `args` and `--json`, returning its exit code and parsed output.
+fn sw_json(args: &[&str]) -> (i32, Value) {
+    let (code, out) = sw(&[args, &["--json"]].concat());
+    (code, serde_json::from_str(&out).unwrap())
+}
+
+#[test]
+fn test_cli_mc_validate() {
+    let (code, out) = sw(&["mc", "validate", "samples/microcontroller/mul_const.xml"]);
+    assert_eq!(code, 0, "{out}");
+    assert!(out.ends_with("mul_const.xml: ok\n"), "{out}");
+
+    let dir = temp_dir("cli-validate");
+    let src = std::fs::read_to_string("samples/microcontroller/mul_const.xml").unwrap();
+    let invalid = dir.join("invalid.xml");
+    std::fs::write(&invalid, src.replacen(r#"width="2""#, r#"width="9""#, 1)).unwrap();
+
+    let (code, reports) = sw_json(&[
+        "mc",
+        "validate",
+        "samples/microcontroller/mul_const.xml",
+        invalid.to_str().unwrap(),
+        "missing.xml",
+    ]);
+    assert_eq!(code, 1);
+    let reports = reports.as_array().unwrap();
+    assert_eq!(reports.len(), 3);
+    assert_eq!(reports[0]["valid"], true);
+    assert_eq!(reports[1]["valid"], false);
+    assert_eq!(reports[1]["errors"][0], "Invalid size 9x1, max is 6x6");
+    assert_eq!(reports[2]["valid"], false);
+    let missing = std::fs::read_to_string("missing.xml").unwrap_err();
+    assert_eq!(reports[2]["errors"][0], missing.to_string());
+
+    // errors are prefixed with their path exactly once
+    let broken = dir.join("broken.xml");
+    std::fs::write(&broken, "<microprocessor").unwrap();
+    let broken = broken.to_str().unwrap();
+    let (code, out) = sw(&[
+        "mc",
+        "validate",
+        invalid.to_str().unwrap(),
+        "missing.xml",
+        broken,
+    ]);
+    assert_eq!(code, 1);
+    let lines: Vec<_> = out.lines().filter(|l| l.contains(": error: ")).collect();
+    assert_eq!(
+        lines[0],
+        format!("{}: error: Invalid size 9x1, max is 6x6", invalid.display())
+    );
+    assert_eq!(lines[1], format!("missing.xml: error: {missing}"));
+    assert!(lines[2].starts_with(&format!("{broken}: error: ")), "{out}");
+    assert_eq!(lines[2].matches(broken).count(), 1, "{out}");
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_cli_mc_fmt() {
+    let dir = temp_dir("cli-fmt");
+    let path = dir.join("mc.xml");
+    std::fs::copy("samples/microcontroller/Seaplane Controller.xml", &path).unwrap();
+    let path = path.to_str().unwrap();
+
+    let (code, _) = sw(&["mc", "fmt", path]);
+    assert_eq!(code, 0);
+    let formatted = std::fs::read_to_string(path).unwrap();
+
+    let (code, reports) = sw_json(&["mc", "fmt", "--check", path]);
+    assert_eq!(code, 0);
+    assert_eq!(reports[0]["changed"], false);
+
+    // --check reports files that would change without touching them
+    std::fs::write(path, formatted.replacen('\t', "  ", 1)).unwrap();
+    let (code, out) = sw(&["mc", "fmt", "--check", path]);
+    assert_eq!(code, 1);
+    assert!(out.ends_with("would reformat\n"), "{out}");
+    assert_ne!(std::fs::read_to_string(path).unwrap(), formatted);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_cli_mc_info() {
+    let (code, info) = sw_json(&["mc", "info", "samples/microcontroller/mul_const.xml"]);
+    assert_eq!(code, 0);
+    assert_eq!(info["name"], "Multiply Const");
+    assert_eq!(info["io"][0]["mode"], "input");
+    assert_eq!(info["io"][1]["type"], "number");
+    assert_eq!(info["component_types"]["Multiply"], 1);
+    assert_eq!(info["properties"][0]["kind"], "number");
+    assert_eq!(info["properties"][0]["value"], 1.0);
+
+    let (code, out) = sw(&["mc", "info", "samples/microcontroller/mul_const.xml"]);
+    assert_eq!(code, 0);
+    assert!(out.starts_with("Multiply Const (2x1)\n"), "{out}");
+}
+
+#[test]
+fn test_cli_vehicle_info() {
+    let (code, info) = sw_json(&["vehicle", "info", "samples/vehicle/sweditor3.xml"]);
+    assert_eq!(code, 0);
+    assert_eq!(info["bodies"].as_array().unwrap().len(), 1);
+    assert_eq!(info["components"], 5);
+    assert_eq!(info["definitions"]["01_block"], 4);
+    assert_eq!(info["definitions"]["02_wedge"], 1);
+}
+
+#[test]
+fn test_cli_def_show() {
+    let dir = temp_dir("cli-def");
+    let def = r#"<?xml version="1.0" encoding="UTF-8"?>
+<definition name="Push Button" category="1" type="5" mass="1" value="10" flags="64" tags="basic,buttons" mesh_data_name="meshes/button.mesh">
+	<surfaces/>
+	<buoyancy_surfaces/>
+	<logic_nodes>
+		<logic_node label="Pressed" mode="0" type="0" description="On while pressed.">
+			<position/>
+		</logic_node>
+	</logic_nodes>
+	<voxels/>
+	<voxel_min/>
+	<voxel_max/>
+	<voxel_physics_min/>
+	<voxel_physics_max/>
+	<bb_physics_min/>
+	<bb_physics_max/>
+	<constraint_pos_parent/>
+	<constraint_pos_child/>
+	<voxel_location_child/>
+	<force_dir/>
+	<light_position/>
+	<light_color/>
+	<door_size/>
+	<dynamic_body_position/>
+	<dynamic_rotation_axes/>
+	<dynamic_side_axis/>
+	<magnet_offset/>
+	<tooltip_properties description="A button."/>
+</definition>
+"#;
+    std::fs::write(dir.join("button_push.xml"), def).unwrap();
+    let defs = dir.to_str().unwrap();
+
+    let (code, by_file) = sw_json(&["def", "show", "--defs", defs, "button_push"]);
+    assert_eq!(code, 0);
+    assert_eq!(by_file["name"], "Push Button");
+    assert_eq!(by_file["flags"][0], "Parent");
+    assert_eq!(by_file["logic_nodes"][0]["type"], "on_off");
+
+    let (code, by_name) = sw_json(&["def", "show", "--defs", defs, "push button"]);
+    assert_eq!(code, 0);
+    assert_eq!(by_name, by_file);
+
+    assert_eq!(sw(&["def", "show", "--defs", defs, "lever"]).0, 1);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_cli_mesh_info() {
+    // a single triangle in one submesh
+    let mut mesh = b"mesh\x07\x00\x01\x00".to_vec();
+    mesh.extend(3u16.to_le_bytes());
+    mesh.extend([0x13, 0, 0, 0]);
+    for position in [[0f32, 0., 0.], [1., 0., 0.], [0., 2., -1.]] {
+        mesh.extend(position.iter().flat_map(|f| f.to_le_bytes()));
+        mesh.extend([255, 255, 255, 255]);
+        mesh.extend([0f32, 1., 0.].iter().flat_map(|f| f.to_le_bytes()));
+    }
+    mesh.extend(3u32.to_le_bytes());
+    mesh.extend([0u16, 1, 2].iter().flat_map(|i| i.to_le_bytes()));
+    mesh.extend(1u16.to_le_bytes());
+    mesh.extend(0u32.to_le_bytes());
+    mesh.extend(3u32.to_le_bytes());
+    mesh.extend([0, 0]);
+    mesh.extend(2u16.to_le_bytes());
+    mesh.extend([0; 24]);
+    mesh.extend(0u16.to_le_bytes());
+    mesh.extend(2u16.to_le_bytes());
+    mesh.extend([0; 14]);
+
+    let dir = temp_dir("cli-mesh");
+    let path = dir.join("triangle.mesh");
+    std::fs::write(&path, mesh).unwrap();
+
+    let (code, info) = sw_json(&["mesh", "info", path.to_str().unwrap()]);
+    assert_eq!(code, 0);
+    assert_eq!(info["vertices"], 3);
+    assert_eq!(info["triangles"], 1);
+    assert_eq!(info["bounds"]["min"]["z"], -1.0);
+    assert_eq!(info["bounds"]["max"]["y"], 2.0);
+    assert_eq!(info["submeshes"][0]["material"], "emissive");
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_cli_json_errors() {
+    let dir = temp_dir("cli-json-errors");
+    let broken = dir.join("broken.xml");
+    std::fs::write(&broken, "<microprocessor").unwrap();
+
+    // with --json nothing goes to stderr, even when files can't be loaded
+    let run = |args: &[&str]| {
+        let out = Command::new(env!("CARGO_BIN_EXE_sw"))
+            .args(args)
+            .arg("--json")
+            .output()
+            .unwrap();
+        assert!(
+            out.stderr.is_empty(),
+            "{}",
+            String::from_utf8_lossy(&out.stderr)
+        );
+        let json: Value = serde_json::from_slice(&out.stdout).unwrap();
+        (out.status.code().unwrap(), json)
+    };
+
+    let (code, reports) = run(&["mc", "fmt", broken.to_str().unwrap(), "missing.xml"]);
+    assert_eq!(code, 1);
+    for report in reports.as_array().unwrap() {
+        assert_eq!(report["changed"], false);
+        assert!(report["error"].is_string(), "{report}");
+    }
+
+    let (code, error) = run(&["mc", "info", "missing.xml"]);
+    assert_eq!(code, 1);
+    assert!(error["error"]
+        .as_str()
+        .unwrap()
+        .starts_with("missing.xml: "));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_cli_usage_errors() {
+    assert_eq!(sw(&[]).0, 2);
+    assert_eq!(sw(&["mc", "compile", "a.xml"]).0, 2);
+    assert_eq!(sw(&["mc", "validate"]).0, 2);
+    assert_eq!(sw(&["mc", "info", "--check", "a.xml"]).0, 2);
+    assert_eq!(sw(&["mc", "info", "a.xml", "b.xml"]).0, 2);
+    assert_eq!(sw(&["mc", "info", "missing.xml"]).0, 1);
+
+    let (code, out) = sw(&["help"]);
+    assert_eq!(code, 0);
+    assert!(out.starts_with("Usage: sw"));
+}
@@ -0,0 +1,19 @@
+// each test crate only uses some of these
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+
+use sw_rs::microcontroller::components::ComponentConnection;
+
+/// Makes an empty folder unique to this test run, for files a test writes.
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("sw-rs-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Shorthand for a [`ComponentConnection`].
+pub fn conn(component_id: u32, node_index: u8) -> ComponentConnection {
+    ComponentConnection { component_id, node_index }
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use sw_rs::microcontroller::{
+    builder::Builder,
+    components::{ComponentType, CompositeChannel, TypedInputConnection, TypedOutputConnection},
+    composite::{ChannelAccess, CompositeIssue},
+    types::{TComposite, Type},
+    Microcontroller,
+};
+
+use common::conn;
+
+fn num(component_id: u32, channel: u8) -> ChannelAccess {
+    ChannelAccess {
+        component_id,
+        typ: Type::Number,
+        channel: CompositeChannel::Fixed(channel),
+    }
+}
+
+#[test]
+fn test_composite_usage() {
+    // composite reads 1, 2, 6, 7 and writes 3, 4, 8, 9, 10, 11, nothing connected
+    let src = std::fs::read_to_string("samples/microcontroller/composite_test.xml").unwrap();
+    let mut mc = Microcontroller::from_xml_str(&src).unwrap();
+    for (from, to) in [(3, 8), (8, 2), (4, 6), (4, 1), (9, 7)] {
+        mc.connect(&conn(from, 0), &conn(to, 0)).unwrap();
+    }
+
+    let mut b = Builder::new(&mut mc);
+    let out = |b: &Builder, id| b.out::<TComposite>(id, 0).unwrap();
+    let read_8 = b.composite_read_number(out(&b, 8), 2).component_id();
+    let read_missing = b.composite_read_number(out(&b, 8), 10).component_id();
+    let overwrite = b.component(ComponentType::_OldCompositeWriteNum {
+        composite: out(&b, 8).into(),
+        val: TypedInputConnection::empty(),
+        out: TypedOutputConnection::default(),
+        channel: 2,
+    });
+    // 9 writes variable channels, so any channel could be there
+    b.composite_read_number(out(&b, 9), 5);
+    let o10 = out(&b, 10);
+    b.output::<TComposite>("Bus", o10);
+    let input = b.input::<TComposite>("In");
+    b.composite_read_on_off(input, 5);
+
+    let usage = mc.composite_usage();
+    assert_eq!(
+        usage.issues,
+        vec![
+            CompositeIssue::WrittenNotRead(num(8, 2)),
+            CompositeIssue::WrittenNotRead(num(8, 3)),
+            CompositeIssue::WrittenNotRead(ChannelAccess {
+                component_id: 11,
+                typ: Type::OnOff,
+                channel: CompositeChannel::Variable
+            }),
+            CompositeIssue::ReadNotWritten(num(read_missing, 9)),
+            CompositeIssue::WrittenTwice { first: num(8, 2), second: num(overwrite, 2) },
+            CompositeIssue::WrittenNotRead(num(overwrite, 2)),
+        ]
+    );
+
+    let wire = usage.wire(&conn(8, 0)).unwrap();
+    assert!(!wire.external);
+    assert_eq!(wire.written, [num(3, 0), num(8, 1), num(8, 2), num(8, 3)]);
+    assert_eq!(wire.read, [num(2, 0), num(read_8, 1), num(read_missing, 9)]);
+
+    let wire = usage.wire(&input.connection()).unwrap();
+    assert!(wire.external && wire.written.is_empty());
+    assert_eq!(wire.read.len(), 1);
+
+    assert_eq!(
+        usage.issues[3].to_string(),
+        format!("number channel 10 ({read_missing}) is read but never written")
+    );
+}
@@ -0,0 +1,56 @@
+mod common;
+
+use sw_rs::microcontroller::{
+    components::{ComponentType, TypedInputConnection},
+    types::Type,
+    ConnectError, Microcontroller,
+};
+
+use common::conn;
+
+#[test]
+fn test_connect_checks() {
+    let mut mc = Microcontroller::default();
+    let div = mc
+        .add_component(ComponentType::Divide {
+            input_a: TypedInputConnection::empty(),
+            input_b: TypedInputConnection::empty(),
+            out: Default::default(),
+            div_by_zero: Default::default(),
+        })
+        .id();
+    let not = mc
+        .add_component(ComponentType::NOT {
+            input: TypedInputConnection::empty(),
+            out: Default::default(),
+        })
+        .id();
+
+    assert_eq!(
+        mc.connect(&conn(99, 0), &conn(not, 0)),
+        Err(ConnectError::UnknownComponent(99))
+    );
+    assert_eq!(
+        mc.connect(&conn(div, 2), &conn(not, 0)),
+        Err(ConnectError::InvalidOutputIndex { component_id: div, index: 2, count: 2 })
+    );
+    assert_eq!(
+        mc.connect(&conn(div, 1), &conn(not, 1)),
+        Err(ConnectError::InvalidInputIndex { component_id: not, index: 1, count: 1 })
+    );
+    assert_eq!(
+        mc.connect(&conn(div, 0), &conn(not, 0)),
+        Err(ConnectError::TypeMismatch { output: Type::Number, input: Type::OnOff })
+    );
+
+    // div_by_zero is OnOff
+    mc.connect(&conn(div, 1), &conn(not, 0)).unwrap();
+    assert!(matches!(
+        mc.connect(&conn(div, 1), &conn(not, 0)),
+        Err(ConnectError::InputAlreadyConnected { .. })
+    ));
+
+    assert_eq!(mc.disconnect(&conn(not, 0)), Ok(Some(conn(div, 1))));
+    assert_eq!(mc.disconnect(&conn(not, 0)), Ok(None));
+    mc.connect(&conn(div, 1), &conn(not, 0)).unwrap();
+}
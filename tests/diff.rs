@@ -0,0 +1,138 @@
+use sw_rs::{
+    microcontroller::{
+        builder::Builder,
+        components::{ComponentConnection, ComponentType, TextValue},
+        diff,
+        diff::{diff_with, Change, DiffOptions},
+        layout::LayoutMode,
+        types::{TNumber, TOnOff},
+        Microcontroller,
+    },
+    util::AnyComponentMut,
+};
+
+/// Builds the same logic, adding the components in a different order when `flip` is set.
+fn build(flip: bool) -> Microcontroller {
+    let mut mc = Microcontroller::default();
+    let mut b = Builder::new(&mut mc);
+
+    let x = b.input::<TNumber>("X");
+    let y = b.input::<TNumber>("Y");
+    let (two, three) = if flip {
+        let three = b.constant(3);
+        (b.constant(2), three)
+    } else {
+        let two = b.constant(2);
+        (two, b.constant(3))
+    };
+    let (a, c) = if flip {
+        let c = b.mul(y, three);
+        (b.mul(x, two), c)
+    } else {
+        let a = b.mul(x, two);
+        (a, b.mul(y, three))
+    };
+    let big = b.greater_than(a, c);
+    b.output::<TOnOff>("Big", big);
+    mc
+}
+
+#[test]
+fn test_diff_ignores_ids() {
+    let a = build(false);
+    let b = build(true);
+    let d = diff(&a, &b);
+    assert!(d.is_empty(), "{d}");
+    assert_eq!(d.matches.len(), a.components().count());
+
+    let src = std::fs::read_to_string("samples/microcontroller/one_of_every_default.xml").unwrap();
+    let mc = Microcontroller::from_xml_str(&src).unwrap();
+    assert!(diff(&mc, &mc.clone()).is_empty());
+}
+
+#[test]
+fn test_diff_changes() {
+    let a = build(false);
+    let mut b = build(false);
+    b.name = "Renamed".into();
+
+    // rewire the second multiply to a new constant
+    let mul = b
+        .components()
+        .filter(|c| c.inputs().len() == 2)
+        .map(|c| c.id())
+        .nth(1)
+        .unwrap();
+    let input_b = ComponentConnection { component_id: mul, node_index: 1 };
+    let four = Builder::new(&mut b).constant(4);
+    b.disconnect(&input_b).unwrap();
+    b.connect(&four.connection(), &input_b).unwrap();
+
+    let d = diff(&a, &b);
+    assert!(d.changes.contains(&Change::Name {
+        old: "New microcontroller".into(),
+        new: "Renamed".into()
+    }));
+    assert!(d
+        .changes
+        .iter()
+        .any(|c| matches!(c, Change::ComponentAdded { name: "ConstantNum", .. })));
+    assert!(d
+        .changes
+        .iter()
+        .any(|c| matches!(c, Change::Rewired { input: "input_b", .. })));
+
+    // moving things around is ignored if asked
+    let mut moved = build(false);
+    moved.layout(LayoutMode::All);
+    assert!(diff(&a, &moved)
+        .changes
+        .iter()
+        .all(|c| matches!(c, Change::Moved { .. })));
+    assert!(diff_with(&a, &moved, &DiffOptions { ignore_positions: true }).is_empty());
+}
+
+#[test]
+fn test_diff_settings() {
+    let a = build(false);
+    let mut b = build(false);
+    // edit a constant in place
+    for c in b.components_mut() {
+        if let AnyComponentMut::Component(c) = c {
+            if let ComponentType::ConstantNum { n, .. } = &mut c.component {
+                if n.value() == 3.0 {
+                    *n = TextValue::from_value(5);
+                }
+            }
+        }
+    }
+
+    let d = diff(&a, &b);
+    assert_eq!(d.changes.len(), 1, "{d}");
+    assert!(matches!(
+        &d.changes[0],
+        Change::SettingChanged { setting: "n", .. }
+    ));
+}
+
+#[test]
+fn test_diff_identical_components() {
+    // nothing tells the two constants apart
+    let mut a = Microcontroller::default();
+    let mut b = Builder::new(&mut a);
+    b.constant(1);
+    b.constant(1);
+    let d = diff(&a, &a.clone());
+    assert!(d.is_empty(), "{d}");
+    assert_eq!(d.matches.len(), 2);
+
+    // a third one is the only change
+    let mut c = a.clone();
+    Builder::new(&mut c).constant(1);
+    let d = diff(&a, &c);
+    assert_eq!(d.changes.len(), 1, "{d}");
+    assert!(matches!(
+        d.changes[0],
+        Change::ComponentAdded { name: "ConstantNum", .. }
+    ));
+}
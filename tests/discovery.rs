@@ -0,0 +1,133 @@
+mod common;
+
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
+
+use sw_rs::util::discovery::{Discovery, GamePaths, DATA_DIR_VAR, INSTALL_DIR_VAR, STEAM_DIR_VAR};
+
+use common::temp_dir;
+
+fn mkdir(base: &Path, rel: &str) -> PathBuf {
+    let p = base.join(rel);
+    std::fs::create_dir_all(&p).unwrap();
+    p
+}
+
+/// Installs the game and its Proton prefix in a library.
+fn install_game(lib: &Path) -> (PathBuf, PathBuf) {
+    let install = mkdir(lib, "steamapps/common/Stormworks");
+    mkdir(&install, "rom/data/definitions");
+    mkdir(&install, "rom/meshes");
+    let user_data = mkdir(
+        lib,
+        "steamapps/compatdata/573090/pfx/drive_c/users/steamuser/AppData/Roaming/Stormworks",
+    );
+    mkdir(&user_data, "data/microprocessors");
+    (install, user_data)
+}
+
+fn no_vars(_: &str) -> Option<OsString> {
+    None
+}
+
+#[test]
+fn test_discover_proton() {
+    let home = temp_dir("discovery-proton");
+    let steam = mkdir(&home, ".local/share/Steam");
+    let lib = mkdir(&home, "Games/SteamLibrary");
+    // a library that doesn't list the game, but has a stale copy of it
+    install_game(&steam);
+    let (install, user_data) = install_game(&lib);
+
+    let vdf = format!(
+        r#"// comment
+"libraryfolders"
+{{
+	"0"
+	{{
+		"path"		"{}"
+		"apps"
+		{{
+			"228980"		"123"
+		}}
+	}}
+	"1"
+	{{
+		"path"		"{}"
+		"label"		"with \"quotes\""
+		"apps"
+		{{
+			"573090"		"456"
+		}}
+	}}
+}}"#,
+        steam.display(),
+        lib.display()
+    );
+    std::fs::write(steam.join("steamapps/libraryfolders.vdf"), vdf).unwrap();
+
+    let d = Discovery::with_env(no_vars, Some(&home), Some(&home.join(".local/share")));
+    assert_eq!(d.steam_libraries(), [lib.clone(), steam]);
+
+    let paths = d.find();
+    assert_eq!(
+        paths,
+        GamePaths {
+            install: Some(install.clone()),
+            user_data: Some(user_data.clone())
+        }
+    );
+    assert_eq!(
+        paths.definitions(),
+        Some(install.join("rom/data/definitions"))
+    );
+    assert_eq!(paths.meshes(), Some(install.join("rom/meshes")));
+    assert_eq!(paths.vehicles(), Some(user_data.join("data/vehicles")));
+    assert!(paths.microprocessors().unwrap().is_dir());
+
+    std::fs::remove_dir_all(home).unwrap();
+}
+
+#[test]
+fn test_discover_overrides() {
+    let home = temp_dir("discovery-overrides");
+    let steam = mkdir(&home, "custom/Steam");
+    let lib = mkdir(&home, "SteamLibrary");
+    let (install, _) = install_game(&lib);
+    // the native data folder wins over the Proton prefix
+    let appdata = mkdir(&home, "AppData/Roaming");
+    mkdir(&appdata, "Stormworks/data");
+
+    // the old format, and only found through the variable
+    let vdf = format!(
+        "\"LibraryFolders\"\n{{\n\t\"TimeNextStatsReport\"\t\"1\"\n\t\"1\"\t\"{}\"\n}}\n",
+        lib.display()
+    );
+    mkdir(&steam, "steamapps");
+    std::fs::write(steam.join("steamapps/libraryfolders.vdf"), vdf).unwrap();
+
+    let steam_var = steam.clone().into_os_string();
+    let vars = |name: &str| (name == STEAM_DIR_VAR).then(|| steam_var.clone());
+    let paths = Discovery::with_env(vars, Some(&home), Some(&appdata)).find();
+    assert_eq!(paths.install, Some(install));
+    assert_eq!(paths.user_data, Some(appdata.join("Stormworks")));
+
+    // overrides are used even if nothing else is found
+    let vars = |name: &str| match name {
+        INSTALL_DIR_VAR => Some("/games/stormworks".into()),
+        DATA_DIR_VAR => Some("/data/stormworks".into()),
+        _ => None,
+    };
+    let paths = Discovery::with_env(vars, None, None).find();
+    assert_eq!(paths.install, Some(PathBuf::from("/games/stormworks")));
+    assert_eq!(paths.user_data, Some(PathBuf::from("/data/stormworks")));
+
+    assert_eq!(
+        Discovery::with_env(no_vars, None, None).find(),
+        GamePaths::default()
+    );
+
+    std::fs::remove_dir_all(home).unwrap();
+}
@@ -0,0 +1,34 @@
+use sw_rs::microcontroller::{
+    builder::Builder,
+    types::{TNumber, TOnOff},
+    Microcontroller,
+};
+
+#[test]
+fn test_to_dot() {
+    let mut mc = Microcontroller::default();
+    let mut b = Builder::new(&mut mc);
+
+    let x = b.input::<TNumber>("X");
+    let half = b.func(x, "x/2");
+    let c = b.constant(3);
+    let big = b.greater_than(half, c);
+    b.output::<TOnOff>("Big", big);
+
+    let dot = mc.to_dot();
+    assert!(dot.starts_with("digraph \"New microcontroller\" {"));
+    assert!(dot.contains("subgraph cluster_inputs"));
+    assert!(dot.contains("subgraph cluster_outputs"));
+    assert!(dot.contains("X (Number)"));
+    assert!(dot.contains("Func1n\\nx/2"));
+    assert!(dot.contains("ConstantNum\\n3"));
+    assert!(dot.contains("{<input_a> input_a|<input_b> input_b}|GreaterThan|{<out> out}"));
+
+    let edge = format!(
+        "c{}:out:e -> c{}:input_a:w [color=",
+        half.component_id(),
+        big.component_id()
+    );
+    assert!(dot.contains(&edge));
+    assert_eq!(dot.matches("->").count(), 4);
+}
@@ -0,0 +1,117 @@
+use sw_rs::microcontroller::{
+    components::{ComponentType, TypedInputConnection},
+    expr::{self, ExprErrorKind, ExprKind, MAX_DEPTH},
+    MCValidationError, Microcontroller,
+};
+
+#[test]
+fn test_parse_eval_number() {
+    let e = expr::parse("(x*y)*(1-(abs(z)/0.083)^2)", ExprKind::Number, 3).unwrap();
+    assert!((e.eval_number(&[2.0, 3.0, 0.0]) - 6.0).abs() < 1e-9);
+
+    let e = expr::parse("y*z + x*(1.0-z)", ExprKind::Number, 3).unwrap();
+    assert!((e.eval_number(&[1.0, 3.0, 0.5]) - 2.0).abs() < 1e-9);
+
+    // unary minus binds looser than ^
+    let e = expr::parse("-x^2", ExprKind::Number, 1).unwrap();
+    assert!((e.eval_number(&[3.0]) + 9.0).abs() < 1e-9);
+
+    let e = expr::parse("clamp(x, 0, 1) + max(pi, 0)", ExprKind::Number, 1).unwrap();
+    assert!((e.eval_number(&[5.0]) - 1.0 - std::f64::consts::PI).abs() < 1e-9);
+
+    assert_eq!(
+        expr::parse("", ExprKind::Number, 1)
+            .unwrap()
+            .eval_number(&[1.0]),
+        0.0
+    );
+}
+
+#[test]
+fn test_parse_eval_bool() {
+    let e = expr::parse("x & !y | z ^ w", ExprKind::OnOff, 4).unwrap();
+    assert!(e.eval_bool(&[true, false, false, false]));
+    assert!(!e.eval_bool(&[true, true, false, false]));
+    assert!(e.eval_bool(&[false, false, true, false]));
+    assert!(!e.eval_bool(&[false, false, true, true]));
+}
+
+#[test]
+fn test_parse_errors() {
+    let err = expr::parse("x + * y", ExprKind::Number, 3).unwrap_err();
+    assert_eq!(err.position, 4);
+    assert_eq!(err.kind, ExprErrorKind::UnexpectedToken("*".into()));
+
+    let err = expr::parse("x + w", ExprKind::Number, 3).unwrap_err();
+    assert_eq!(err.position, 4);
+    assert!(matches!(err.kind, ExprErrorKind::VariableNotAllowed { .. }));
+
+    let err = expr::parse("foo(x)", ExprKind::Number, 1).unwrap_err();
+    assert_eq!(err.kind, ExprErrorKind::UnknownFunction("foo".into()));
+
+    let err = expr::parse("min(x)", ExprKind::Number, 1).unwrap_err();
+    assert!(matches!(
+        err.kind,
+        ExprErrorKind::WrongArgumentCount { expected: 2, found: 1, .. }
+    ));
+
+    let err = expr::parse("(x", ExprKind::Number, 1).unwrap_err();
+    assert_eq!((err.position, err.kind), (2, ExprErrorKind::UnexpectedEnd));
+
+    let err = expr::parse("x + y", ExprKind::OnOff, 2).unwrap_err();
+    assert_eq!(err.kind, ExprErrorKind::OperatorNotAllowed('+'));
+}
+
+#[test]
+fn test_parse_too_deep() {
+    const DEPTH: usize = 100_000;
+    let number = [
+        "(".repeat(DEPTH) + "x" + &")".repeat(DEPTH),
+        "-".repeat(DEPTH) + "x",
+        "x^".repeat(DEPTH) + "x",
+        "x+".repeat(DEPTH) + "x",
+        "min(".repeat(DEPTH) + "x" + &",x)".repeat(DEPTH),
+    ];
+    for src in &number {
+        let err = expr::parse(src, ExprKind::Number, 1).unwrap_err();
+        assert_eq!(err.kind, ExprErrorKind::TooDeep, "{}", &src[..10]);
+    }
+    for src in ["!".repeat(DEPTH) + "x", "x|".repeat(DEPTH) + "x"] {
+        let err = expr::parse(&src, ExprKind::OnOff, 1).unwrap_err();
+        assert_eq!(err.kind, ExprErrorKind::TooDeep, "{}", &src[..10]);
+    }
+
+    // right up to the limit is fine
+    let src = "-".repeat(MAX_DEPTH) + "x";
+    let e = expr::parse(&src, ExprKind::Number, 1).unwrap();
+    assert_eq!(e.eval_number(&[2.0]), 2.0);
+
+    // and loading a microcontroller with one is an error, not a crash
+    let mut mc = Microcontroller::default();
+    mc.add_component(ComponentType::Func1n {
+        input: TypedInputConnection::empty(),
+        out: Default::default(),
+        expr: number[0].clone(),
+    });
+    assert!(matches!(
+        mc.validate(),
+        Err(MCValidationError::InvalidExpression { error, .. }) if error.kind == ExprErrorKind::TooDeep
+    ));
+}
+
+#[test]
+fn test_validate_rejects_invalid_expression() {
+    let mut mc = Microcontroller::default();
+    let id = mc
+        .add_component(ComponentType::Func1n {
+            input: TypedInputConnection::empty(),
+            out: Default::default(),
+            expr: "x +".into(),
+        })
+        .id();
+
+    assert!(matches!(
+        mc.validate(),
+        Err(MCValidationError::InvalidExpression { component_id, .. }) if component_id == id
+    ));
+}
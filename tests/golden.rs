@@ -0,0 +1,135 @@
+use sw_rs::microcontroller::{
+    builder::Builder,
+    golden::{assert_golden, golden_path, Golden, GoldenError, Mismatch},
+    simulator::Value,
+    types::{TComposite, TOnOff, Type},
+    Microcontroller,
+};
+
+fn mul_const() -> Microcontroller {
+    let src = std::fs::read_to_string("samples/microcontroller/mul_const.xml").unwrap();
+    Microcontroller::from_xml_str(&src).unwrap()
+}
+
+#[test]
+fn test_golden_samples() {
+    let samples = std::fs::read_dir("samples/microcontroller").unwrap();
+    let mut checked = 0;
+    for f in samples {
+        let path = f.unwrap().path();
+        if path.extension().is_some_and(|ext| ext == "xml") && golden_path(&path).is_file() {
+            println!("CHECKING {}...", path.display());
+            assert_golden(&path);
+            checked += 1;
+        }
+    }
+    assert!(checked > 0);
+}
+
+#[test]
+fn test_golden_mismatches() {
+    // without the property override it multiplies by 1
+    let golden = Golden::parse(
+        "tick | Input | Output\n\
+         0    | 3     |\n\
+         2    |       | 6\n\
+         3    | on    |\n",
+    );
+    assert!(matches!(
+        golden.unwrap().run(&mul_const()),
+        Err(GoldenError::TypeMismatch {
+            line: 4,
+            expected: Type::Number,
+            found: Type::OnOff,
+            ..
+        })
+    ));
+
+    let golden = Golden::parse(
+        "# comment\n\
+         tolerance 0.5\n\
+         \n\
+         tick | Output | Input\n\
+         0    |        | 3\n\
+         2    | 6      |\n\
+         3    | 3.4    |\n",
+    )
+    .unwrap();
+    let mismatches = golden.run(&mul_const()).unwrap();
+    assert_eq!(
+        mismatches,
+        [Mismatch {
+            line: 6,
+            tick: 2,
+            label: "Output".into(),
+            expected: Value::Number(6.0),
+            actual: Value::Number(3.0),
+        }]
+    );
+    assert_eq!(
+        mismatches[0].to_string(),
+        r#"line 6: tick 2, "Output" expected 6 but got 3"#
+    );
+}
+
+#[test]
+fn test_golden_on_off_and_composite() {
+    let mut mc = Microcontroller::default();
+    let mut b = Builder::new(&mut mc);
+    let a = b.input::<TOnOff>("A");
+    let not_a = b.not(a);
+    b.output("Not A", not_a);
+    let c = b.input::<TComposite>("C");
+    let n = b.composite_read_number(c, 2);
+    b.output("N", n);
+    b.output::<TComposite>("C Out", c);
+
+    let golden = Golden::parse(
+        "tick | A   | C          | Not A | N   | C Out\n\
+         0    | on  | {n2=4 b1=on} |       |     |\n\
+         1    |     |            | on    |     | {n2=4, b1=on}\n\
+         2    |     |            | off   | 4   |\n",
+    )
+    .unwrap();
+    assert_eq!(golden.run(&mc).unwrap(), []);
+
+    let golden = Golden::parse(
+        "tick | C     | C Out\n\
+         0    | {n1=1} |\n\
+         1    |       | {b3=on}\n",
+    )
+    .unwrap();
+    let mismatches = golden.run(&mc).unwrap();
+    assert_eq!(
+        mismatches[0].to_string(),
+        r#"line 3: tick 1, "C Out" expected {b3=on} but got {n1=1}"#
+    );
+}
+
+#[test]
+fn test_golden_errors() {
+    let parse_line = |src: &str| match Golden::parse(src) {
+        Err(GoldenError::Parse { line, .. }) => line,
+        r => panic!("expected a parse error, got {r:?}"),
+    };
+    assert_eq!(parse_line("property number\ntick | Input"), 1);
+    assert_eq!(parse_line("tick | Input\n0 | 1 | 2"), 2);
+    assert_eq!(parse_line("tick | Input\n1 | 1\n1 | 2"), 3);
+    assert_eq!(parse_line("tick | Input\n0 | {n33=1}"), 2);
+    assert_eq!(parse_line("tick | Input\n0 | {=1}"), 2);
+    assert_eq!(parse_line("# no table\n"), 1);
+
+    let run = |src: &str| Golden::parse(src).unwrap().run(&mul_const());
+    assert!(matches!(
+        run("tick | Input | Missing"),
+        Err(GoldenError::UnknownLabel { line: 1, label }) if label == "Missing"
+    ));
+    assert!(matches!(
+        run("property nope = 2\ntick | Input"),
+        Err(GoldenError::Property { line: 1, .. })
+    ));
+    assert!(matches!(
+        run("property number = off\ntick | Input"),
+        Err(GoldenError::Property { line: 1, .. })
+    ));
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use sw_rs::microcontroller::{
+    builder::Builder,
+    types::{TNumber, TOnOff},
+    Microcontroller,
+};
+
+use common::conn;
+
+#[test]
+fn test_graph_queries() {
+    let mut mc = Microcontroller::default();
+    let mut b = Builder::new(&mut mc);
+
+    let x = b.input::<TNumber>("X");
+    let y = b.input::<TNumber>("Y");
+    let sum = b.add(x, y);
+    let (quot, div_by_zero) = b.div(sum, y);
+    let out = b.output::<TNumber>("Out", quot);
+    let x = x.component_id();
+    let y = y.component_id();
+    let sum = sum.component_id();
+    let div = quot.component_id();
+
+    let g = mc.graph();
+    assert_eq!(g.consumers(&conn(y, 0)), &[conn(sum, 1), conn(div, 1)]);
+    assert_eq!(g.consumers(&conn(div, 0)), &[conn(out, 0)]);
+    assert!(g.consumers(&div_by_zero.connection()).is_empty());
+
+    let mut up = g.upstream(out);
+    up.sort_unstable();
+    assert_eq!(up, vec![x, y, sum, div]);
+    let mut down = g.downstream(x);
+    down.sort_unstable();
+    assert_eq!(down, vec![sum, div, out]);
+
+    let order = g.topological_order().unwrap();
+    let pos = |id| order.iter().position(|&c| c == id).unwrap();
+    assert!(pos(x) < pos(sum) && pos(y) < pos(sum) && pos(sum) < pos(div) && pos(div) < pos(out));
+
+    assert!(g.unconnected_inputs().is_empty());
+    assert_eq!(g.unconnected_outputs(), vec![div_by_zero.connection()]);
+}
+
+#[test]
+fn test_graph_loops() {
+    let mut mc = Microcontroller::default();
+    let mut b = Builder::new(&mut mc);
+
+    let set = b.input::<TOnOff>("Set");
+    let one = b.constant(1);
+    let reg = b.memory_register(set, None, one, 0);
+    let next = b.add(reg, one);
+    b.output::<TNumber>("Count", reg);
+    let reg = reg.component_id();
+    let next = next.component_id();
+
+    // feed the register back into itself through the add
+    b.mc().disconnect(&conn(reg, 2)).unwrap();
+    b.mc().connect(&conn(next, 0), &conn(reg, 2)).unwrap();
+
+    let g = mc.graph();
+    assert!(g.topological_order().is_none());
+    assert!(g.upstream(reg).contains(&reg));
+
+    let sccs = g.strongly_connected_components();
+    assert_eq!(sccs.len(), g.ids().len() - 1);
+    let mut cycle = sccs.into_iter().find(|scc| scc.len() > 1).unwrap();
+    cycle.sort_unstable();
+    assert_eq!(cycle, vec![reg, next]);
+
+    assert_eq!(g.unconnected_inputs(), vec![conn(reg, 1)]);
+}
+
+#[test]
+fn test_graph_long_chain() {
+    // deep enough to overflow the stack with a recursive search
+    let mut mc = Microcontroller::default();
+    let mut b = Builder::new(&mut mc);
+    let mut x = b.input::<TNumber>("X");
+    for _ in 0..50_000 {
+        x = b.abs(x);
+    }
+    b.output::<TNumber>("Out", x);
+
+    let g = mc.graph();
+    let order = g.topological_order().unwrap();
+    assert_eq!(order.len(), 50_002);
+    assert_eq!(order[0], mc.io_nodes()[0].logic.id());
+    assert_eq!(order[50_001], mc.io_nodes()[1].logic.id());
+}
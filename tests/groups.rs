@@ -0,0 +1,259 @@
+use sw_rs::{
+    microcontroller::{
+        builder::Builder,
+        components::state::{ComponentState, MemoryRegisterState},
+        simulator::{Simulator, Value},
+        types::Type,
+        MCSerDeError, MCValidationError, Microcontroller,
+    },
+    util::{serde_utils::RecursiveStringMap, AnyComponentRef},
+};
+
+/// A group with its own components, bridge, data and a nested group of its own.
+const GROUP: &str = r#"<g>
+	<data type="1">
+		<inputs>
+			<i label="In" type="1"/>
+		</inputs>
+		<outputs>
+			<o label="Out" type="1">
+				<position x="1"/>
+			</o>
+		</outputs>
+	</data>
+	<components>
+		<c type="34">
+			<object id="8">
+				<pos x="0.5"/>
+				<out1/>
+				<v text="2" value="2"/>
+			</object>
+		</c>
+	</components>
+	<components_bridge>
+		<c type="3">
+			<object id="9">
+				<in1 component_id="8"/>
+				<out1/>
+			</object>
+		</c>
+	</components_bridge>
+	<groups>
+		<g>
+			<data>
+				<inputs/>
+				<outputs/>
+			</data>
+			<components/>
+			<components_bridge/>
+			<groups/>
+			<component_states/>
+			<component_bridge_states/>
+			<group_states/>
+		</g>
+	</groups>
+	<component_states>
+		<c0 id="8">
+			<pos x="0.5"/>
+			<out1/>
+			<v text="2" value="2"/>
+		</c0>
+	</component_states>
+	<component_bridge_states>
+		<c0 id="9">
+			<in1 component_id="8"/>
+			<out1/>
+		</c0>
+	</component_bridge_states>
+	<group_states>
+		<g0 id="1"/>
+	</group_states>
+</g>"#;
+
+fn with_group() -> String {
+    let src = std::fs::read_to_string("samples/microcontroller/mul_const.xml").unwrap();
+    let group: Vec<_> = GROUP.lines().map(|l| format!("\t\t\t{l}")).collect();
+    // the main group's states and data first, so these don't match inside the nested group
+    src.replace(r#"id_counter="7""#, r#"id_counter="9""#)
+        .replace(
+            "\t\t<group_states/>",
+            "\t\t<group_states>\n\t\t\t<g0/>\n\t\t</group_states>",
+        )
+        .replace(
+            "\t\t\t<inputs/>",
+            "\t\t\t<inputs>\n\t\t\t\t<i label=\"x\"/>\n\t\t\t</inputs>",
+        )
+        .replace(
+            "\t\t<groups/>",
+            &format!("\t\t<groups>\n{}\n\t\t</groups>", group.join("\n")),
+        )
+}
+
+#[test]
+fn test_nested_groups_roundtrip() {
+    let src = with_group();
+    let mc = Microcontroller::from_xml_str(&src).unwrap();
+    assert_eq!(mc.to_xml_string().unwrap(), src);
+
+    assert_eq!(mc.data.inputs.len(), 1);
+    assert_eq!(mc.group_states.len(), 1);
+
+    let [g] = mc.groups() else {
+        panic!("expected one group")
+    };
+    assert_eq!(g.tag, "g");
+    assert_eq!(g.data.typ.as_deref(), Some("1"));
+    assert_eq!(g.data.inputs[0].label, "In");
+    assert_eq!(g.data.inputs[0].typ, Type::Number);
+    assert_eq!(g.data.outputs[0].label, "Out");
+    assert_eq!(g.data.outputs[0].position.x, 1.0);
+    assert_eq!(mc.data.inputs[0].label, "x");
+    assert_eq!(mc.data.inputs[0].typ, Type::OnOff);
+    assert!(RecursiveStringMap::String("x".into()).into_map().is_none());
+    assert!(RecursiveStringMap::Map(Default::default())
+        .into_map()
+        .is_some());
+    assert_eq!(g.components[0].id(), 8);
+    assert_eq!(g.bridge_components[0].id(), 9);
+    assert_eq!(g.groups.len(), 1);
+    assert_eq!(g.group_states[0].id, 1);
+    assert_eq!(mc.group_states[0].id, 0);
+
+    // nested components aren't part of the main group
+    assert!(mc.get_component(8).is_none());
+}
+
+#[test]
+fn test_edit_nested_group() {
+    let mut mc = Microcontroller::from_xml_str(&with_group()).unwrap();
+    mc.groups_mut()[0].components[0].pos.y = 1.0;
+
+    // the component's state is kept in sync
+    let out = mc.to_xml_string().unwrap();
+    assert_eq!(out.matches(r#"<pos x="0.5" y="1"/>"#).count(), 2);
+
+    let mc = Microcontroller::from_xml_str(&out).unwrap();
+    assert!((mc.groups()[0].components[0].pos.y - 1.0).abs() < f32::EPSILON);
+}
+
+#[test]
+fn test_canonicalize_nested_group() {
+    // nested ids below the number of top level components
+    let src = with_group()
+        .replace(r#"id="8""#, r#"id="1""#)
+        .replace(r#"component_id="8""#, r#"component_id="1""#)
+        .replace(r#"id="9""#, r#"id="2""#);
+    let mut mc = Microcontroller::from_xml_str(&src).unwrap();
+    assert_eq!(mc.components().count(), 4);
+    mc.canonicalize();
+    mc.validate().unwrap();
+
+    let g = &mc.groups()[0];
+    let mut ids: Vec<u32> = mc.components().map(|c| c.id()).collect();
+    ids.extend([g.components[0].id(), g.bridge_components[0].id()]);
+    ids.sort_unstable();
+    assert_eq!(ids, (1..=6).collect::<Vec<_>>());
+    assert_eq!(
+        g.bridge_components[0].component.inputs()[0]
+            .as_ref()
+            .map(|c| c.component_id),
+        Some(g.components[0].id())
+    );
+
+    let mc = Microcontroller::from_xml_str(&mc.to_xml_string().unwrap()).unwrap();
+    assert_eq!(mc.groups()[0].components[0].id(), 5);
+}
+
+#[test]
+fn test_validate_nested_group_ids() {
+    let mut mc = Microcontroller::from_xml_str(&with_group()).unwrap();
+
+    // ids are shared with the top level
+    let c = mc.get_component(6).unwrap();
+    let AnyComponentRef::Component(c) = c else {
+        panic!("expected a component")
+    };
+    let c = c.clone();
+    mc.groups_mut()[0].components.push(c);
+    assert!(matches!(
+        mc.validate(),
+        Err(MCValidationError::DuplicateComponentId(6))
+    ));
+
+    // and the id counter has to stay above them
+    let src = with_group().replace(r#"id_counter="9""#, r#"id_counter="7""#);
+    assert!(matches!(
+        Microcontroller::from_xml_str(&src),
+        Err(MCSerDeError::ValidationError(
+            MCValidationError::ComponentIdTooHigh { found_id: 8, max: 7 }
+        ))
+    ));
+}
+
+#[test]
+fn test_nested_group_logic() {
+    let mc = Microcontroller::from_xml_str(&with_group()).unwrap();
+
+    let g = mc.graph();
+    assert_eq!(g.successors(8), [9]);
+    assert!(g.is_nested(9) && !g.is_nested(5));
+    assert_eq!(mc.all_components().count(), 6);
+
+    let mut sim = Simulator::new(&mc);
+    sim.run(2);
+    assert_eq!(sim.component_output(9, 0), Some(Value::Number(2.0)));
+
+    // the nested constant is used and the nested output bridge has nothing to feed
+    assert!(mc
+        .lint()
+        .iter()
+        .all(|l| !l.component_ids.contains(&8) && !l.component_ids.contains(&9)));
+
+    // connections inside nested groups are checked too
+    let src = with_group().replace(r#"<in1 component_id="8"/>"#, r#"<in1 component_id="4"/>"#);
+    let mc = Microcontroller::from_xml_str_unvalidated(&src).unwrap();
+    assert!(matches!(
+        mc.validate(),
+        Err(MCValidationError::MissingComponent { component_id: 9, missing_id: 4, .. })
+    ));
+}
+
+#[test]
+fn test_reset_nested_states() {
+    let mut scratch = Microcontroller::default();
+    let mut b = Builder::new(&mut scratch);
+    let on = b.constant_on();
+    let n = b.constant(3);
+    let reg = b.memory_register(on, None, n, 0).component_id();
+    let Some(AnyComponentRef::Component(c)) = scratch.get_component(reg) else {
+        panic!("expected a component")
+    };
+    let mut component = c.component.clone();
+    component
+        .set_state(&ComponentState::MemoryRegister(MemoryRegisterState {
+            value: 42.0,
+        }))
+        .unwrap();
+
+    let mut mc = Microcontroller::from_xml_str(&with_group()).unwrap();
+    mc.groups_mut()[0].components[0].component = component;
+    assert_eq!(mc.reset_states(), vec![8]);
+    assert_eq!(
+        mc.groups()[0].components[0].component.state().unwrap(),
+        Some(ComponentState::MemoryRegister(MemoryRegisterState {
+            value: 0.0
+        }))
+    );
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_nested_groups_json_roundtrip() {
+    let src = with_group();
+    let mc = Microcontroller::from_xml_str(&src).unwrap();
+    let json = mc.to_json_string().unwrap();
+    assert!(json.contains(r#""label": "Out""#));
+
+    let mc = Microcontroller::from_json_str(&json).unwrap();
+    assert_eq!(mc.to_xml_string().unwrap(), src);
+}
@@ -0,0 +1,84 @@
+#![cfg(feature = "json")]
+
+use pretty_assertions::assert_str_eq;
+use sw_rs::microcontroller::{
+    builder::Builder,
+    json::{JsonError, VERSION},
+    types::TNumber,
+    Microcontroller,
+};
+
+#[test]
+fn test_samples_json_roundtrip() {
+    let samples = std::fs::read_dir("samples/microcontroller").unwrap();
+    for f in samples {
+        let entry = f.unwrap();
+        let fname = entry.file_name().into_string().unwrap();
+        if fname.ends_with(".xml") {
+            println!("CHECKING {fname}...");
+            let src = std::fs::read_to_string(entry.path()).unwrap();
+            let mc = Microcontroller::from_xml_str(&src).unwrap();
+
+            let json = mc.to_json_string().unwrap();
+            #[allow(clippy::expect_fun_call)]
+            let from_json = Microcontroller::from_json_str(&json)
+                .expect(&format!("Failed to read {fname} back from JSON"));
+
+            assert_str_eq!(
+                mc.to_xml_string().unwrap(),
+                from_json.to_xml_string().unwrap(),
+                "{fname}:\n{json}"
+            );
+            assert_str_eq!(json, from_json.to_json_string().unwrap(), "{fname}");
+        }
+    }
+}
+
+#[test]
+fn test_built_json_roundtrip() {
+    let mut mc = Microcontroller::default();
+    let mut b = Builder::new(&mut mc);
+    let x = b.input::<TNumber>("X");
+    let y = b.constant(2.5);
+    let product = b.mul(x, y);
+    b.output("Y", product);
+
+    let json = mc.to_json_string().unwrap();
+    // unconnected slots of built components aren't written to the XML
+    assert!(json.contains("\"hidden\""));
+    let from_json = Microcontroller::from_json_str(&json).unwrap();
+    assert_str_eq!(
+        mc.to_xml_string().unwrap(),
+        from_json.to_xml_string().unwrap()
+    );
+}
+
+#[test]
+fn test_json_errors() {
+    let src = std::fs::read_to_string("samples/microcontroller/mul_const.xml").unwrap();
+    let json = Microcontroller::from_xml_str(&src)
+        .unwrap()
+        .to_json_string()
+        .unwrap();
+
+    let newer = json.replacen(
+        &format!("\"version\": {VERSION}"),
+        &format!("\"version\": {}", VERSION + 1),
+        1,
+    );
+    assert!(matches!(
+        Microcontroller::from_json_str(&newer),
+        Err(JsonError::UnsupportedVersion(v)) if v == VERSION + 1
+    ));
+
+    assert!(matches!(
+        Microcontroller::from_json_str("{}"),
+        Err(JsonError::WrongFormat(_))
+    ));
+
+    let unknown = json.replacen("\"Multiply\"", "\"Exponentiate\"", 1);
+    assert!(matches!(
+        Microcontroller::from_json_str(&unknown),
+        Err(JsonError::UnknownComponentType { typ, .. }) if typ == "Exponentiate"
+    ));
+}
@@ -0,0 +1,106 @@
+mod common;
+
+use sw_rs::microcontroller::{
+    builder::Builder,
+    simulator::{Simulator, Value},
+    types::{TNumber, TOnOff},
+    Microcontroller,
+};
+
+use common::conn;
+
+#[test]
+fn test_latency_matches_simulator() {
+    let src = std::fs::read_to_string("samples/microcontroller/mul_const.xml").unwrap();
+    let mut mc = Microcontroller::from_xml_str(&src).unwrap();
+    mc.set_property("number", 2.0).unwrap();
+    let (input, output) = (mc.io_nodes()[0].get_id(), mc.io_nodes()[1].get_id());
+
+    let latency = mc.latency();
+    assert_eq!(latency.paths.len(), 1);
+    let path = latency.get(input, output).unwrap();
+    assert_eq!((path.shortest, path.longest), (3, Some(3)));
+    assert_eq!(path.critical_path.len(), 3);
+    assert_eq!(path.critical_path[0], mc.io_nodes()[0].logic.id());
+    assert_eq!(path.critical_path[2], mc.io_nodes()[1].logic.id());
+    assert!(latency.loops.is_empty());
+
+    let mut sim = Simulator::new(&mc);
+    sim.set_input(input, Value::Number(3.0)).unwrap();
+    sim.run(u64::from(path.shortest) - 1);
+    assert_eq!(sim.output(output).unwrap(), Value::Number(0.0));
+    sim.tick();
+    assert_eq!(sim.output(output).unwrap(), Value::Number(6.0));
+}
+
+#[test]
+fn test_latency_paths() {
+    let mut mc = Microcontroller::default();
+    let mut b = Builder::new(&mut mc);
+    let x = b.input::<TNumber>("X");
+    let y = b.input::<TNumber>("Y");
+    let slow = b.abs(x);
+    let slow = b.abs(slow);
+    let sum = b.add(x, slow);
+    b.output("Sum", sum);
+    b.output("Y", y);
+    let (slow, sum) = (slow.component_id(), sum.component_id());
+
+    let ids: Vec<u32> = mc.io_nodes().iter().map(|ion| ion.get_id()).collect();
+    let bridges: Vec<u32> = mc.io_nodes().iter().map(|ion| ion.logic.id()).collect();
+    let latency = mc.latency();
+
+    // X doesn't reach Y, and Y doesn't reach Sum
+    assert_eq!(latency.paths.len(), 2);
+    assert!(latency.get(ids[0], ids[3]).is_none());
+    assert!(latency.get(ids[1], ids[2]).is_none());
+
+    let path = latency.get(ids[0], ids[2]).unwrap();
+    assert_eq!((path.shortest, path.longest), (3, Some(5)));
+    assert_eq!(path.critical_path[2..], [slow, sum, bridges[2]]);
+
+    // straight through the bridges
+    let path = latency.get(ids[1], ids[3]).unwrap();
+    assert_eq!((path.shortest, path.longest), (2, Some(2)));
+    assert_eq!(path.critical_path, [bridges[1], bridges[3]]);
+}
+
+#[test]
+fn test_latency_loops() {
+    let mut mc = Microcontroller::default();
+    let mut b = Builder::new(&mut mc);
+
+    // a counter, buffered by its register
+    let set = b.input::<TOnOff>("Set");
+    let one = b.constant(1);
+    let reg = b.memory_register(set, None, one, 0);
+    let next = b.add(reg, one);
+    b.output("Count", reg);
+
+    // an OR gate holding itself on, with no memory element
+    let on = b.input::<TOnOff>("On");
+    let hold = b.or(on, on);
+    b.output("Held", hold);
+
+    let (reg, next, hold) = (reg.component_id(), next.component_id(), hold.component_id());
+    b.mc().disconnect(&conn(reg, 2)).unwrap();
+    b.mc().connect(&conn(next, 0), &conn(reg, 2)).unwrap();
+    b.mc().disconnect(&conn(hold, 1)).unwrap();
+    b.mc().connect(&conn(hold, 0), &conn(hold, 1)).unwrap();
+
+    let ids: Vec<u32> = mc.io_nodes().iter().map(|ion| ion.get_id()).collect();
+    let latency = mc.latency();
+    assert_eq!(latency.loops.len(), 2);
+
+    let count = latency.get(ids[0], ids[1]).unwrap();
+    assert_eq!((count.shortest, count.longest), (3, None));
+    let counter = &latency.loops[count.loops[0]];
+    assert!(counter.is_buffered());
+    assert_eq!(counter.memory, [reg]);
+
+    let held = latency.get(ids[2], ids[3]).unwrap();
+    assert_eq!((held.shortest, held.longest), (3, None));
+    let unbuffered: Vec<_> = latency.unbuffered_loops().collect();
+    assert_eq!(unbuffered, [&latency.loops[held.loops[0]]]);
+    assert_eq!(unbuffered[0].components, [hold]);
+}
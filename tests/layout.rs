@@ -0,0 +1,80 @@
+use sw_rs::{
+    microcontroller::{
+        builder::Builder,
+        layout::{footprint, LayoutMode},
+        types::{TNumber, TOnOff},
+        Microcontroller,
+    },
+    util::serde_utils::PositionXY,
+};
+
+fn assert_no_overlaps(mc: &Microcontroller) {
+    let cs: Vec<_> = mc.components().collect();
+    for (i, a) in cs.iter().enumerate() {
+        for b in &cs[i + 1..] {
+            let ((aw, ah), (bw, bh)) = (footprint(a), footprint(b));
+            let (pa, pb) = (a.pos(), b.pos());
+            assert!(
+                (pa.x - pb.x).abs() * 2.0 >= aw + bw || (pa.y - pb.y).abs() * 2.0 >= ah + bh,
+                "components {} and {} overlap",
+                a.id(),
+                b.id()
+            );
+        }
+    }
+}
+
+#[test]
+fn test_layout_all() {
+    let mut mc = Microcontroller::default();
+    let mut b = Builder::new(&mut mc);
+
+    let x = b.input::<TNumber>("X");
+    let y = b.input::<TNumber>("Y");
+    let c = b.constant(2);
+    let sum = b.add(x, y);
+    let prod = b.mul(sum, c);
+    let big = b.greater_than(prod, y);
+    let out = b.output::<TOnOff>("Big", big);
+
+    mc.layout(LayoutMode::All);
+    assert_no_overlaps(&mc);
+
+    let pos = |id| mc.get_component(id).unwrap().pos().clone();
+    assert_eq!(pos(x.component_id()).x, pos(y.component_id()).x);
+    assert!(pos(x.component_id()).x < pos(sum.component_id()).x);
+    assert!(pos(sum.component_id()).x < pos(prod.component_id()).x);
+    assert!(pos(prod.component_id()).x < pos(big.component_id()).x);
+    assert!(pos(big.component_id()).x < pos(out).x);
+    // constant sits right before its consumer
+    assert!(pos(c.component_id()).x < pos(prod.component_id()).x);
+    assert!(pos(c.component_id()).x > pos(x.component_id()).x);
+    assert!(mc.components().all(|c| *c.pos() != PositionXY::default()));
+}
+
+#[test]
+fn test_layout_unpositioned() {
+    let mut mc = Microcontroller::default();
+    let mut b = Builder::new(&mut mc);
+
+    let x = b.input::<TNumber>("X");
+    let abs = b.abs(x);
+    b.output::<TNumber>("Out", abs);
+    mc.layout(LayoutMode::All);
+
+    let before: Vec<_> = mc.components().map(|c| (c.id(), c.pos().clone())).collect();
+
+    let mut b = Builder::new(&mut mc);
+    let neg = b.func(abs, "-x");
+    let twice = b.func(neg, "x*2");
+    b.output::<TNumber>("Neg", twice);
+    mc.layout(LayoutMode::Unpositioned);
+
+    for (id, pos) in before {
+        assert_eq!(*mc.get_component(id).unwrap().pos(), pos);
+    }
+    let x_of = |id| mc.get_component(id).unwrap().pos().x;
+    assert!(x_of(abs.component_id()) < x_of(neg.component_id()));
+    assert!(x_of(neg.component_id()) < x_of(twice.component_id()));
+    assert_no_overlaps(&mc);
+}
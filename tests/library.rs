@@ -0,0 +1,100 @@
+mod common;
+
+use std::path::PathBuf;
+
+use sw_rs::microcontroller::{
+    library::{IoSignature, LibraryError, McLibrary},
+    Microcontroller,
+};
+
+use common::temp_dir;
+
+const SAMPLES: [&str; 5] = ["mul_const", "min_io", "min_io2", "not", "one_block"];
+
+/// Makes a fresh folder with some samples, a broken file and a file that isn't XML.
+fn library_dir(name: &str) -> PathBuf {
+    let dir = temp_dir(name);
+    for sample in SAMPLES {
+        std::fs::copy(
+            format!("samples/microcontroller/{sample}.xml"),
+            dir.join(format!("{sample}.xml")),
+        )
+        .unwrap();
+    }
+    std::fs::write(dir.join("broken.xml"), "<microprocessor").unwrap();
+    std::fs::write(dir.join("notes.txt"), "not a microcontroller").unwrap();
+    dir
+}
+
+fn file_names(entries: &[&sw_rs::microcontroller::library::LibraryEntry]) -> Vec<String> {
+    entries
+        .iter()
+        .map(|e| e.path.file_name().unwrap().to_string_lossy().into_owned())
+        .collect()
+}
+
+#[test]
+fn test_library_index() {
+    let dir = library_dir("library-index");
+    let lib = McLibrary::load(&dir).unwrap();
+
+    assert_eq!(lib.entries().len(), SAMPLES.len());
+    let [LibraryError::SerDe { path, .. }] = lib.errors() else {
+        panic!("expected one parse error, got {:?}", lib.errors())
+    };
+    assert!(path.ends_with("broken.xml"));
+
+    assert_eq!(
+        file_names(&lib.by_name("min_io")),
+        ["min_io.xml", "min_io2.xml"]
+    );
+    assert!(lib.by_name("missing").is_empty());
+
+    let mul_const = &lib.by_name("Multiply Const")[0].mc;
+    let signature = IoSignature::from(mul_const);
+    assert!(file_names(&lib.by_signature(&signature)).contains(&"mul_const.xml".into()));
+
+    let all = lib.by_component_count(..);
+    assert_eq!(all.len(), SAMPLES.len());
+    assert!(lib.by_component_count(1000..).is_empty());
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_library_save() {
+    let dir = library_dir("library-save");
+    let mut lib = McLibrary::load(&dir).unwrap();
+
+    let mut mc = Microcontroller::default();
+    mc.name = "Pitch/Roll: v2?".into();
+    let first = lib.save(&mc).unwrap().path.clone();
+    let second = lib.save(&mc).unwrap().path.clone();
+    assert_eq!(first, dir.join("Pitch_Roll_ v2_.xml"));
+    assert_eq!(second, dir.join("Pitch_Roll_ v2_ (2).xml"));
+    assert_eq!(lib.by_name("Pitch/Roll: v2?").len(), 2);
+
+    // never overwrites an existing file
+    mc.name = "not".into();
+    assert_eq!(lib.save(&mc).unwrap().path, dir.join("not (2).xml"));
+    mc.name = "con".into();
+    assert_eq!(lib.save(&mc).unwrap().path, dir.join("con_.xml"));
+    mc.name = "Con.v2".into();
+    assert_eq!(lib.save(&mc).unwrap().path, dir.join("Con_.v2.xml"));
+    mc.name = " . ".into();
+    assert_eq!(lib.save(&mc).unwrap().path, dir.join("microcontroller.xml"));
+
+    // long names are cut to a byte length, without splitting a character
+    mc.name = "é".repeat(150);
+    let path = lib.save(&mc).unwrap().path.clone();
+    assert_eq!(path, dir.join(format!("{}.xml", "é".repeat(100))));
+    mc.name = format!("x{}", "é".repeat(150));
+    let path = lib.save(&mc).unwrap().path.clone();
+    assert_eq!(path, dir.join(format!("x{}.xml", "é".repeat(99))));
+
+    let reloaded = McLibrary::load(&dir).unwrap();
+    assert_eq!(reloaded.entries().len(), SAMPLES.len() + 8);
+    assert_eq!(reloaded.by_name("not").len(), 2);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,119 @@
+use sw_rs::microcontroller::{
+    builder::Builder,
+    components::{ComponentType, TextValue, TypedInputConnection, TypedOutputConnection},
+    lint::{LintConfig, Rule, Severity},
+    mc_serde::microcontroller::IONodeType,
+    types::{TNumber, TOnOff, Type},
+    Microcontroller,
+};
+
+fn rules(mc: &Microcontroller, config: &LintConfig) -> Vec<(Rule, Vec<u32>)> {
+    mc.lint_with(config)
+        .into_iter()
+        .map(|l| (l.rule, l.component_ids))
+        .collect()
+}
+
+#[test]
+fn test_lint_rules() {
+    let mut mc = Microcontroller::default();
+    let mut b = Builder::new(&mut mc);
+
+    let x = b.input::<TNumber>("X");
+    let two = b.constant(2);
+    let (quotient, _) = b.div(x, two);
+    b.output::<TNumber>("Quotient", quotient);
+
+    let three = b.constant(3);
+    let sum = b.add(two, three).component_id();
+    let eq = b.equal(quotient, x, 0);
+    b.output::<TOnOff>("Equal", eq);
+    let slider = b.component(ComponentType::PropertySlider {
+        out: TypedOutputConnection::default(),
+        name: "Gain".into(),
+        min: TextValue::from_value(10),
+        max: TextValue::from_value(1),
+        int: TextValue::from_value(1),
+        v: TextValue::from_value(5),
+    });
+    let lua = b.component(ComponentType::Lua {
+        data_in: TypedInputConnection::empty(),
+        video_in: TypedInputConnection::empty(),
+        data_out: TypedOutputConnection::default(),
+        video_out: TypedOutputConnection::default(),
+        script: Some("-".repeat(9000)),
+    });
+    let unlabeled = mc
+        .add_io(None, None, Type::Number, IONodeType::Output)
+        .logic
+        .id();
+    let div = quotient.component_id();
+
+    assert_eq!(
+        rules(&mc, &LintConfig::default()),
+        vec![
+            (Rule::UnusedOutput, vec![sum]),
+            (Rule::UnusedOutput, vec![slider]),
+            (Rule::UnusedOutput, vec![lua]),
+            (Rule::ConstantArithmetic, vec![sum]),
+            (Rule::IgnoredDivByZero, vec![div]),
+            (Rule::SliderMinMax, vec![slider]),
+            (Rule::ZeroEpsilon, vec![eq.component_id()]),
+            (Rule::DefaultLabel, vec![unlabeled]),
+            (Rule::LuaTooLong, vec![lua]),
+        ]
+    );
+
+    let lints = mc.lint();
+    assert_eq!(lints[0].severity, Severity::Warning);
+    assert_eq!(
+        lints[0].to_string(),
+        format!("warning[unused-output]: outputs of Add {sum} are never used")
+    );
+}
+
+#[test]
+fn test_lint_config() {
+    let mut mc = Microcontroller::default();
+    let mut b = Builder::new(&mut mc);
+    let x = b.input::<TNumber>("X");
+    let (quotient, _) = b.div(x, x);
+    b.output::<TNumber>("Input", quotient);
+
+    let mut config = LintConfig::none();
+    assert!(mc.lint_with(&config).is_empty());
+
+    config
+        .set(
+            Rule::from_id("default-label").unwrap(),
+            Some(Severity::Error),
+        )
+        .set(Rule::IgnoredDivByZero, Some(Severity::Info));
+    let lints = mc.lint_with(&config);
+    assert_eq!(lints.len(), 2);
+    assert_eq!(
+        (lints[0].rule, lints[0].severity),
+        (Rule::IgnoredDivByZero, Severity::Info)
+    );
+    assert_eq!(
+        (lints[1].rule, lints[1].severity),
+        (Rule::DefaultLabel, Severity::Error)
+    );
+
+    config.set(Rule::IgnoredDivByZero, None);
+    assert_eq!(mc.lint_with(&config).len(), 1);
+
+    // the game's limit is configurable too
+    mc.add_component(ComponentType::Lua {
+        data_in: TypedInputConnection::empty(),
+        video_in: TypedInputConnection::empty(),
+        data_out: TypedOutputConnection::default(),
+        video_out: TypedOutputConnection::default(),
+        script: Some("function onTick() end".into()),
+    });
+    let mut config = LintConfig::none();
+    config.set(Rule::LuaTooLong, Some(Severity::Error));
+    assert!(mc.lint_with(&config).is_empty());
+    config.lua_char_limit = 10;
+    assert_eq!(mc.lint_with(&config)[0].rule, Rule::LuaTooLong);
+}
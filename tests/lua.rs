@@ -0,0 +1,197 @@
+use sw_rs::{
+    microcontroller::{
+        builder::Builder,
+        components::{ComponentConnection, ComponentType, TimerUnits, TypedInputConnection},
+        lua::{LuaChannel, LuaError, Unsupported, INPUT_LABEL, OUTPUT_LABEL},
+        mc_serde::microcontroller::IONodeType,
+        types::{TNumber, TOnOff, Type},
+        Microcontroller,
+    },
+    util::AnyComponentRef,
+};
+
+/// Gets the components that aren't IO bridges.
+fn components(mc: &Microcontroller) -> Vec<&ComponentType> {
+    mc.components()
+        .filter_map(|c| match c {
+            AnyComponentRef::Component(c) => Some(&c.component),
+            AnyComponentRef::BridgeComponent(_) => None,
+        })
+        .collect()
+}
+
+fn load(name: &str) -> Microcontroller {
+    let src = std::fs::read_to_string(format!("samples/microcontroller/{name}.xml")).unwrap();
+    Microcontroller::from_xml_str(&src).unwrap()
+}
+
+#[test]
+fn test_lua_mul_const() {
+    let mc = load("mul_const");
+    let lua = mc.to_lua().unwrap();
+
+    assert_eq!(
+        lua.inputs,
+        [LuaChannel {
+            label: "Input".into(),
+            typ: Type::Number,
+            channel: 1,
+        }]
+    );
+    assert_eq!(lua.outputs[0].label, "Output");
+    assert_eq!(lua.outputs[0].channel, 1);
+    assert!(lua.script.contains("function onTick()"));
+    assert!(lua.script.contains("=input.getNumber(1)"));
+    assert!(lua.script.contains("output.setNumber(1,"));
+    assert!(
+        lua.notes.iter().any(|n| n.contains("property")),
+        "{:?}",
+        lua.notes
+    );
+}
+
+#[test]
+fn test_lua_compile() {
+    let mc = load("mul_const");
+    let (compiled, lua) = mc.compile_to_lua().unwrap();
+    assert_eq!(compiled.name, mc.name);
+    assert_eq!((compiled.width, compiled.length), (mc.width, mc.length));
+
+    let io: Vec<_> = compiled
+        .io_nodes()
+        .iter()
+        .map(|ion| (ion.design.label.as_str(), ion.design.mode, ion.design.typ))
+        .collect();
+    assert_eq!(
+        io,
+        [
+            (INPUT_LABEL, IONodeType::Input, Type::Composite),
+            (OUTPUT_LABEL, IONodeType::Output, Type::Composite),
+        ]
+    );
+
+    let nodes = components(&compiled);
+    let [lua_node @ ComponentType::Lua { script, .. }] = nodes[..] else {
+        panic!("expected a single Lua node, got {nodes:?}");
+    };
+    assert_eq!(script.as_deref(), Some(lua.script.as_str()));
+    assert_eq!(
+        lua_node.inputs()[0].as_ref().map(|c| c.component_id),
+        Some(compiled.io_nodes()[0].logic.id())
+    );
+    assert!(compiled.io_nodes()[1].logic.component.inputs()[0].is_some());
+
+    // the script survives saving
+    let reloaded = Microcontroller::from_xml_str(&compiled.to_xml_string().unwrap()).unwrap();
+    let reloaded = components(&reloaded);
+    let [ComponentType::Lua { script: reloaded, .. }] = reloaded[..] else {
+        panic!("expected a single Lua node, got {reloaded:?}");
+    };
+    assert_eq!(reloaded, script);
+}
+
+#[test]
+fn test_lua_state() {
+    let mut mc = Microcontroller::default();
+    let mut b = Builder::new(&mut mc);
+    let set = b.input::<TOnOff>("Set");
+    let x = b.input::<TNumber>("X");
+    let stored = b.memory_register(set, None, x, 0);
+    b.output("Stored", stored);
+    let delta = b.delta(x);
+    b.output("Delta", delta);
+    let not_set = b.not(set);
+    b.output("Not Set", not_set);
+
+    let lua = mc.to_lua().unwrap();
+    let (upvalues, body) = lua.script.split_once("function onTick()").unwrap();
+    assert_eq!(upvalues.matches("\nlocal s").count(), 2, "{}", lua.script);
+    assert!(body.contains("=input.getBool(1)"));
+    assert!(body.contains("=input.getNumber(1)"));
+    assert!(body.contains("output.setBool(1,"));
+    assert!(body.contains("output.setNumber(2,"));
+    assert_eq!(lua.outputs.len(), 3);
+    assert!(lua.notes.is_empty(), "{:?}", lua.notes);
+}
+
+#[test]
+fn test_lua_timer_tof_startup() {
+    let mut mc = Microcontroller::default();
+    let mut b = Builder::new(&mut mc);
+    let enable = b.input::<TOnOff>("Enable").component_id();
+    let duration = b.constant(3).component_id();
+    let tof = mc
+        .add_component(ComponentType::TimerTOF {
+            enable: TypedInputConnection::new(ComponentConnection {
+                component_id: enable,
+                node_index: 0,
+            }),
+            duration: TypedInputConnection::new(ComponentConnection {
+                component_id: duration,
+                node_index: 0,
+            }),
+            timing: Default::default(),
+            units: TimerUnits::Ticks,
+            __t: None,
+        })
+        .id();
+    let out = mc
+        .add_io(Some("Out".into()), None, Type::OnOff, IONodeType::Output)
+        .logic
+        .id();
+    mc.connect(
+        &ComponentConnection { component_id: tof, node_index: 0 },
+        &ComponentConnection { component_id: out, node_index: 0 },
+    )
+    .unwrap();
+
+    // like the simulator, the output only turns on once enable has been on
+    let lua = mc.to_lua().unwrap();
+    let (upvalues, body) = lua.script.split_once("function onTick()").unwrap();
+    assert!(upvalues.contains(&format!("local s{tof},s{tof}b=0,false")));
+    assert!(body.contains(&format!("s{tof}b=true")));
+    assert!(
+        body.contains(&format!("=s{tof}b and s{tof}<=")),
+        "{}",
+        lua.script
+    );
+}
+
+#[test]
+fn test_lua_unsupported() {
+    let Err(LuaError::Unsupported(unsupported)) = load("one_of_every_default").to_lua() else {
+        panic!("expected unsupported components");
+    };
+    let components: Vec<_> = unsupported
+        .iter()
+        .filter_map(|u| match u {
+            Unsupported::Component { typ, .. } => Some(*typ),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(components, ["Lua", "VideoSwitchbox", "AudioSwitchbox"]);
+
+    let Err(LuaError::Unsupported(unsupported)) = load("Seaplane Controller").to_lua() else {
+        panic!("expected unsupported IO nodes");
+    };
+    assert!(
+        unsupported.contains(&Unsupported::IoNode { label: "HUD Out".into(), typ: Type::Video })
+    );
+
+    let mut mc = Microcontroller::default();
+    let mut b = Builder::new(&mut mc);
+    for i in 0..33 {
+        b.input::<TNumber>(&format!("In {i}"));
+    }
+    let Err(LuaError::Unsupported(unsupported)) = mc.to_lua() else {
+        panic!("expected too many channels");
+    };
+    assert_eq!(
+        unsupported,
+        [Unsupported::TooManyChannels {
+            mode: IONodeType::Input,
+            typ: Type::Number,
+            count: 33,
+        }]
+    );
+}
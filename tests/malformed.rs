@@ -0,0 +1,99 @@
+use std::io::Write;
+
+use sw_rs::{
+    mesh::{Mesh, MeshParseError},
+    microcontroller::{MCSerDeError, Microcontroller},
+    util::serde_utils::XmlElementError,
+    vehicle::{Vehicle, VehicleSerDeError},
+};
+
+fn mul_const_with(from: &str, to: &str) -> String {
+    let src = std::fs::read_to_string("samples/microcontroller/mul_const.xml").unwrap();
+    assert!(src.contains(from));
+    src.replacen(from, to, 1)
+}
+
+fn mc_element_error(xml: &str) -> XmlElementError {
+    match Microcontroller::from_xml_str(xml) {
+        Err(MCSerDeError::ElementError(e)) => e,
+        r => panic!("expected an element error, got {r:?}"),
+    }
+}
+
+#[test]
+fn test_malformed_component() {
+    let e = mc_element_error(&mul_const_with(
+        r#"<v text="1" value="1"/>"#,
+        r#"<v text="1" value="one"/>"#,
+    ));
+    assert_eq!(e.tag, "object");
+    assert_eq!(e.component_id, Some(6));
+    assert_eq!((e.line, e.column), (Some(20), Some(5)));
+    assert!(e.message.contains("\"one\""), "{e}");
+    assert!(e
+        .to_string()
+        .starts_with("<object> component 6 at line 20, column 5: "));
+
+    // nested values are reported on the component they're in
+    let e = mc_element_error(&mul_const_with(
+        r#"<pos x="1.5" y="0.25"/>"#,
+        r#"<pos x="1.5" y="0.25.0"/>"#,
+    ));
+    assert_eq!((e.component_id, e.line), (Some(7), Some(27)));
+}
+
+#[test]
+fn test_malformed_node() {
+    let e = mc_element_error(&mul_const_with(
+        r#"<n id="1" component_id="3">"#,
+        r#"<n id="1" component_id="9">"#,
+    ));
+    assert_eq!(e.tag, "n");
+    assert_eq!(e.attribute.as_deref(), Some("component_id"));
+    assert_eq!((e.line, e.column), (Some(4), Some(13)));
+}
+
+#[test]
+fn test_malformed_vehicle() {
+    let src = std::fs::read_to_string("samples/vehicle/sweditor3.xml").unwrap();
+
+    for (from, to, attribute) in [
+        (r#"r="1,0,0,0,0,-1,0,1,0""#, r#"r="1,0,0,0,0,-1""#, "r"),
+        (
+            r#"r="1,0,0,0,0,-1,0,1,0""#,
+            r#"r="1,0,0,0,0,-1,0,x,0""#,
+            "r",
+        ),
+        (r#"<c d="02_wedge">"#, r#"<c d="02_wedge" t="9">"#, "t"),
+    ] {
+        let xml = src.replacen(from, to, 1);
+        let column = xml.find(to).unwrap() + to.find(&format!("{attribute}=\"")).unwrap() + 1;
+        match Vehicle::<()>::from_xml_str(&xml) {
+            Err(VehicleSerDeError::ElementError(e)) => {
+                assert_eq!(e.attribute.as_deref(), Some(attribute));
+                assert_eq!((e.line, e.column), (Some(1), Some(column)), "{e}");
+            },
+            r => panic!("expected an element error for {to}, got {r:?}"),
+        }
+    }
+}
+
+#[test]
+fn test_truncated_mesh() {
+    let path = std::env::temp_dir().join("sw-rs-truncated.mesh");
+    let mut file = std::fs::File::create(&path).unwrap();
+    // valid header and one vertex, but the vertex data is cut off
+    file.write_all(&[0x6D, 0x65, 0x73, 0x68, 0x07, 0x00, 0x01, 0x00])
+        .unwrap();
+    file.write_all(&[0x01, 0x00, 0x13, 0x00, 0x00, 0x00, 0x00, 0x00])
+        .unwrap();
+    drop(file);
+
+    let r = Mesh::load_file(std::fs::File::open(&path).unwrap());
+    std::fs::remove_file(&path).unwrap();
+    assert!(
+        matches!(r, Err(MeshParseError::UnexpectedEof { offset: 16 })),
+        "{:?}",
+        r.err()
+    );
+}
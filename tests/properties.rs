@@ -0,0 +1,148 @@
+use sw_rs::microcontroller::{
+    builder::Builder,
+    components::{ComponentType, DropdownItem, TextValue, TypedOutputConnection},
+    properties::{PropertyError, PropertyKind, PropertyValue},
+    Microcontroller,
+};
+
+fn with_properties() -> Microcontroller {
+    let mut mc = Microcontroller::default();
+    let mut b = Builder::new(&mut mc);
+    b.component(ComponentType::PropertySlider {
+        out: TypedOutputConnection::default(),
+        name: "Gain".into(),
+        min: TextValue::from_value(0),
+        max: TextValue::from_value(10),
+        int: TextValue::from_value(0.5),
+        v: TextValue::from_value(5),
+    });
+    b.component(ComponentType::PropertyDropdown {
+        out: TypedOutputConnection::default(),
+        name: "Mode".into(),
+        selected: 0,
+        items: ["Off", "Slow", "Fast"]
+            .into_iter()
+            .zip(0..)
+            .map(|(label, v)| DropdownItem {
+                label: label.into(),
+                value: TextValue::from_value(v),
+            })
+            .collect(),
+    });
+    b.component(ComponentType::PropertyToggle {
+        out: TypedOutputConnection::default(),
+        name: "Enabled".into(),
+        on: "Yes".into(),
+        off: "No".into(),
+        value: false,
+    });
+    b.component(ComponentType::PropertyText { name: "Channel".into(), val: "a".into() });
+    mc
+}
+
+#[test]
+fn test_list_properties() {
+    let mc = with_properties();
+    let props = mc.properties();
+    let names: Vec<_> = props.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, ["Gain", "Mode", "Enabled", "Channel"]);
+
+    assert_eq!(
+        props[0].kind,
+        PropertyKind::Slider { min: 0.0, max: 10.0, rounding: 0.5 }
+    );
+    assert_eq!(props[0].value, PropertyValue::Number(5.0));
+    let PropertyKind::Dropdown { options } = &props[1].kind else {
+        panic!("expected a dropdown")
+    };
+    assert_eq!(options[2], ("Fast".into(), 2.0));
+    assert_eq!(props[1].value, PropertyValue::Text("Off".into()));
+    assert_eq!(props[2].value, PropertyValue::Bool(false));
+    assert_eq!(props[3].kind, PropertyKind::Text);
+}
+
+#[test]
+fn test_set_properties() {
+    let mut mc = with_properties();
+    mc.set_property("Gain", 7.5).unwrap();
+    mc.set_property("Mode", "Fast").unwrap();
+    mc.set_property("Enabled", true).unwrap();
+    mc.set_property("Channel", "b").unwrap();
+
+    let mc = Microcontroller::from_xml_str(&mc.to_xml_string().unwrap()).unwrap();
+    let values: Vec<_> = mc.properties().into_iter().map(|p| p.value).collect();
+    assert_eq!(
+        values,
+        [
+            PropertyValue::Number(7.5),
+            PropertyValue::Text("Fast".into()),
+            PropertyValue::Bool(true),
+            PropertyValue::Text("b".into()),
+        ]
+    );
+
+    // dropdowns can also be set by value
+    let mut mc = mc;
+    mc.set_property("Mode", 1.0).unwrap();
+    assert_eq!(mc.properties()[1].value, PropertyValue::Text("Slow".into()));
+}
+
+#[test]
+fn test_set_property_errors() {
+    let mut mc = with_properties();
+    assert!(matches!(
+        mc.set_property("Gain", 11.0),
+        Err(PropertyError::OutOfRange { max, .. }) if max == 10.0
+    ));
+    assert_eq!(
+        mc.set_property("Gain", 7.25),
+        Err(PropertyError::NotRounded { name: "Gain".into(), value: 7.25, rounding: 0.5 })
+    );
+    assert!(matches!(
+        mc.set_property("Gain", f64::NAN),
+        Err(PropertyError::NotFinite { .. })
+    ));
+    assert!(matches!(
+        mc.set_property("Gain", true),
+        Err(PropertyError::WrongType { component: "PropertySlider", .. })
+    ));
+    assert!(matches!(
+        mc.set_property("Mode", "Turbo"),
+        Err(PropertyError::UnknownOption { .. })
+    ));
+    assert_eq!(
+        mc.set_property("Missing", 1.0),
+        Err(PropertyError::NotFound("Missing".into()))
+    );
+
+    Builder::new(&mut mc)
+        .component(ComponentType::PropertyText { name: "Channel".into(), val: String::new() });
+    assert_eq!(
+        mc.set_property("Channel", "c"),
+        Err(PropertyError::Ambiguous { name: "Channel".into(), count: 2 })
+    );
+
+    // failed sets leave the values alone
+    assert_eq!(mc.properties()[0].value, PropertyValue::Number(5.0));
+}
+
+#[test]
+fn test_set_sample_property() {
+    let src = std::fs::read_to_string("samples/microcontroller/mul_const.xml").unwrap();
+    let mut mc = Microcontroller::from_xml_str(&src).unwrap();
+    let [p] = &mc.properties()[..] else {
+        panic!("expected one property")
+    };
+    assert_eq!(p.kind, PropertyKind::Number);
+    let name = p.name.clone();
+
+    // setting the same value doesn't change the file
+    mc.set_property(&name, 1.0).unwrap();
+    assert_eq!(mc.to_xml_string().unwrap(), src);
+
+    mc.set_property(&name, 3.0).unwrap();
+    assert!(mc
+        .to_xml_string()
+        .unwrap()
+        .contains(r#"<v text="3" value="3"/>"#));
+}
@@ -1,7 +1,13 @@
 use pretty_assertions::assert_str_eq;
 use std::io::Write;
 use sw_rs::{
-    component::definition::ComponentDefinition, mesh::Mesh, microcontroller::Microcontroller,
+    component::definition::ComponentDefinition,
+    mesh::Mesh,
+    microcontroller::{
+        library::{LibraryEntry, McLibrary},
+        Microcontroller,
+    },
+    util::discovery::Discovery,
     vehicle::Vehicle,
 };
 
@@ -31,28 +37,24 @@ fn test_samples_serde_matches() {
 
 #[test]
 fn test_sw_dir_serde_matches() {
-    let mc_dir = sw_rs::util::find_microcontroller_folder()
+    let lib = sw_rs::util::find_microcontroller_folder()
         .ok()
-        .and_then(|p| std::fs::read_dir(p).ok());
-    if let Some(mc_dir) = mc_dir {
-        for f in mc_dir {
-            let entry = f.unwrap();
-            let fname = entry.file_name().into_string().unwrap();
-            if fname.ends_with(".xml") {
-                println!("CHECKING {fname}...");
-                let src = std::fs::read_to_string(entry.path()).unwrap();
-
-                #[allow(clippy::expect_fun_call)]
-                let mc: Microcontroller = Microcontroller::from_xml_str(&src)
-                    .expect(&format!("Failed to deserialize {fname}"));
+        .and_then(|p| McLibrary::load(p).ok());
+    if let Some(lib) = lib {
+        if let Some(e) = lib.errors().first() {
+            panic!("Failed to load {e}");
+        }
+        for LibraryEntry { path, mc } in lib.entries() {
+            let fname = path.display();
+            println!("CHECKING {fname}...");
+            let src = std::fs::read_to_string(path).unwrap();
 
-                #[allow(clippy::expect_fun_call)]
-                let out = mc
-                    .to_xml_string()
-                    .expect(&format!("Failed to serialize {fname}"));
+            #[allow(clippy::expect_fun_call)]
+            let out = mc
+                .to_xml_string()
+                .expect(&format!("Failed to serialize {fname}"));
 
-                assert_str_eq!(src, out, "{fname}:\n{mc:#?}");
-            }
+            assert_str_eq!(src, out, "{fname}:\n{mc:#?}");
         }
     }
 }
@@ -84,9 +86,8 @@ fn test_vehicle_samples_serde_matches() {
 
 #[test]
 fn test_rom_component_definitions_serde_matches() {
-    if let Ok(samples) =
-        std::fs::read_dir("E:/SteamLibrary/steamapps/common/Stormworks/rom/data/definitions")
-    {
+    let definitions = Discovery::from_env().find().definitions();
+    if let Some(Ok(samples)) = definitions.map(std::fs::read_dir) {
         for f in samples {
             let entry = f.unwrap();
             let fname = entry.file_name().into_string().unwrap();
@@ -113,8 +114,8 @@ fn test_rom_component_definitions_serde_matches() {
 
 #[test]
 fn test_rom_meshes_serde_matches() {
-    if let Ok(samples) = std::fs::read_dir("E:/SteamLibrary/steamapps/common/Stormworks/rom/meshes")
-    {
+    let meshes = Discovery::from_env().find().meshes();
+    if let Some(Ok(samples)) = meshes.map(std::fs::read_dir) {
         for f in samples {
             let entry = f.unwrap();
             let fname = entry.file_name().into_string().unwrap();
@@ -0,0 +1,140 @@
+use sw_rs::microcontroller::{
+    components::{
+        ComponentType, CompositeChannel, PulseMode, TimerUnits, TooltipMode, UpDownCounterMode,
+    },
+    types::{Type, UnknownType},
+    Microcontroller,
+};
+
+/// Adds an attribute to component `id` (and its state) in the "one of every" sample.
+fn one_of_every_with(id: u32, attr: &str) -> String {
+    let mut src =
+        std::fs::read_to_string("samples/microcontroller/one_of_every_default.xml").unwrap();
+    for tag in ["object", &format!("c{}", id - 2)] {
+        let start = src.find(&format!("<{tag} id=\"{id}\"")).unwrap();
+        let end = start + src[start..].find('>').unwrap();
+        src.insert_str(end, &format!(" {attr}"));
+    }
+    src
+}
+
+fn component(mc: &Microcontroller, id: u32) -> &ComponentType {
+    mc.components()
+        .find_map(|c| match c {
+            sw_rs::util::AnyComponentRef::Component(c) if c.id() == id => Some(&c.component),
+            _ => None,
+        })
+        .unwrap()
+}
+
+/// Parses `src`, checks it serializes back to the same XML and returns component `id`.
+fn roundtrip(src: &str, id: u32) -> ComponentType {
+    let mc = Microcontroller::from_xml_str(src).unwrap();
+    assert_eq!(mc.to_xml_string().unwrap(), src);
+    component(&mc, id).clone()
+}
+
+#[test]
+fn test_setting_enums_roundtrip() {
+    let c = roundtrip(&one_of_every_with(43, r#"m="2""#), 43);
+    assert!(matches!(
+        c,
+        ComponentType::TooltipNum { mode: TooltipMode::IfNoError, .. }
+    ));
+
+    let c = roundtrip(&one_of_every_with(49, r#"u="1""#), 49);
+    assert!(matches!(
+        c,
+        ComponentType::TimerTON { units: TimerUnits::Ticks, .. }
+    ));
+
+    let c = roundtrip(&one_of_every_with(37, r#"m="1""#), 37);
+    assert!(matches!(
+        c,
+        ComponentType::UpDownCounter { mode: UpDownCounterMode::Clamped, .. }
+    ));
+
+    let c = roundtrip(&one_of_every_with(48, r#"m="0""#), 48);
+    assert!(matches!(
+        c,
+        ComponentType::Pulse { mode: PulseMode::OnToOff, .. }
+    ));
+    let c = roundtrip(&one_of_every_with(48, r#"m="2""#), 48);
+    assert!(matches!(
+        c,
+        ComponentType::Pulse { mode: PulseMode::Always, .. }
+    ));
+
+    // defaults are left out of the XML
+    let src = std::fs::read_to_string("samples/microcontroller/one_of_every_default.xml").unwrap();
+    let c = roundtrip(&src, 48);
+    assert!(matches!(
+        c,
+        ComponentType::Pulse { mode: PulseMode::OffToOn, .. }
+    ));
+}
+
+#[test]
+fn test_composite_channels() {
+    let src = std::fs::read_to_string("samples/microcontroller/composite_test.xml").unwrap();
+    let mc = Microcontroller::from_xml_str(&src).unwrap();
+
+    let channels: Vec<_> = mc
+        .components()
+        .filter_map(|c| match c {
+            sw_rs::util::AnyComponentRef::Component(c) => match &c.component {
+                ComponentType::CompositeReadNum { channel, .. }
+                | ComponentType::CompositeReadOnOff { channel, .. }
+                | ComponentType::CompositeWriteNum { offset: channel, .. }
+                | ComponentType::CompositeWriteOnOff { offset: channel, .. } => Some(*channel),
+                _ => None,
+            },
+            sw_rs::util::AnyComponentRef::BridgeComponent(_) => None,
+        })
+        .collect();
+    assert!(channels.contains(&CompositeChannel::Variable));
+    assert!(channels.contains(&CompositeChannel::Fixed(1)));
+
+    assert_eq!(
+        CompositeChannel::from_number(1),
+        Some(CompositeChannel::Fixed(0))
+    );
+    assert_eq!(CompositeChannel::from_number(33), None);
+    assert_eq!(CompositeChannel::Fixed(31).number(), Some(32));
+    assert_eq!(CompositeChannel::Variable.number(), None);
+}
+
+#[test]
+fn test_unknown_values_rejected() {
+    for (id, attr) in [
+        (43, r#"m="3""#),
+        (49, r#"u="2""#),
+        (37, r#"m="5""#),
+        (48, r#"m="1""#),
+        (48, r#"m="x""#),
+    ] {
+        let src = one_of_every_with(id, attr);
+        let err = Microcontroller::from_xml_str(&src).unwrap_err();
+        assert!(err.to_string().contains("unknown"), "{id} {attr}: {err}");
+    }
+
+    let src = std::fs::read_to_string("samples/microcontroller/composite_test.xml")
+        .unwrap()
+        .replace(r#"i="-1""#, r#"i="-2""#);
+    assert!(Microcontroller::from_xml_str(&src).is_err());
+}
+
+#[test]
+fn test_type_names() {
+    assert_eq!(Type::OnOff.to_string(), "on_off");
+    assert_eq!(Type::Composite.to_string(), "composite");
+    assert_eq!("video".parse(), Ok(Type::Video));
+    assert_eq!(Type::try_from(8), Ok(Type::_Rope));
+
+    for n in 0..=8 {
+        let typ = Type::try_from(n).unwrap();
+        assert_eq!(typ.to_string().parse(), Ok(typ));
+    }
+    assert_eq!(Type::try_from(9), Err(UnknownType { value: "9".into() }));
+    assert!("OnOff".parse::<Type>().is_err());
+}
@@ -0,0 +1,136 @@
+use sw_rs::microcontroller::{
+    components::{ComponentConnection, ComponentType, TextValue, TimerUnits, TypedInputConnection},
+    mc_serde::microcontroller::IONodeType,
+    simulator::{Simulator, Value},
+    types::Type,
+    Microcontroller,
+};
+
+#[test]
+fn test_mul_const_sample() {
+    let src = std::fs::read_to_string("samples/microcontroller/mul_const.xml").unwrap();
+    let mc = Microcontroller::from_xml_str(&src).unwrap();
+
+    let mut sim = Simulator::new(&mc);
+    sim.set_input_by_label("Input", Value::Number(3.0)).unwrap();
+
+    // input bridge -> multiply -> output bridge
+    sim.run(2);
+    assert_eq!(sim.output_by_label("Output").unwrap(), Value::Number(0.0));
+    sim.tick();
+    assert_eq!(sim.output_by_label("Output").unwrap(), Value::Number(3.0));
+}
+
+#[test]
+fn test_memory_register() {
+    let mut mc = Microcontroller::default();
+    let set = mc
+        .add_io(Some("Set".into()), None, Type::OnOff, IONodeType::Input)
+        .logic
+        .id();
+    let val = mc
+        .add_io(Some("Value".into()), None, Type::Number, IONodeType::Input)
+        .logic
+        .id();
+    let out = mc
+        .add_io(Some("Out".into()), None, Type::Number, IONodeType::Output)
+        .logic
+        .id();
+
+    let reg = mc
+        .add_component(ComponentType::MemoryRegister {
+            set: TypedInputConnection::new(ComponentConnection {
+                component_id: set,
+                node_index: 0,
+            }),
+            reset: TypedInputConnection::empty(),
+            number: TypedInputConnection::new(ComponentConnection {
+                component_id: val,
+                node_index: 0,
+            }),
+            out: Default::default(),
+            reset_value: TextValue::from_value(0),
+            __memory: None,
+        })
+        .id();
+    mc.connect(
+        &ComponentConnection { component_id: reg, node_index: 0 },
+        &ComponentConnection { component_id: out, node_index: 0 },
+    )
+    .unwrap();
+
+    let mut sim = Simulator::new(&mc);
+    sim.set_input_by_label("Value", Value::Number(5.0)).unwrap();
+    sim.set_input_by_label("Set", Value::OnOff(true)).unwrap();
+    sim.run(3);
+    assert_eq!(sim.output_by_label("Out").unwrap(), Value::Number(5.0));
+
+    // value is held once set turns off
+    sim.set_input_by_label("Set", Value::OnOff(false)).unwrap();
+    sim.set_input_by_label("Value", Value::Number(7.0)).unwrap();
+    sim.run(5);
+    assert_eq!(sim.output_by_label("Out").unwrap(), Value::Number(5.0));
+
+    assert!(sim.set_input_by_label("Set", Value::Number(1.0)).is_err());
+    assert!(sim.set_input_by_label("Out", Value::Number(1.0)).is_err());
+}
+
+#[test]
+fn test_timer_tof_startup() {
+    let mut mc = Microcontroller::default();
+    let enable = mc
+        .add_io(Some("Enable".into()), None, Type::OnOff, IONodeType::Input)
+        .logic
+        .id();
+    let out = mc
+        .add_io(Some("Out".into()), None, Type::OnOff, IONodeType::Output)
+        .logic
+        .id();
+    let duration = mc
+        .add_component(ComponentType::ConstantNum {
+            out: Default::default(),
+            n: TextValue::from_value(3),
+        })
+        .id();
+    let tof = mc
+        .add_component(ComponentType::TimerTOF {
+            enable: TypedInputConnection::new(ComponentConnection {
+                component_id: enable,
+                node_index: 0,
+            }),
+            duration: TypedInputConnection::new(ComponentConnection {
+                component_id: duration,
+                node_index: 0,
+            }),
+            timing: Default::default(),
+            units: TimerUnits::Ticks,
+            __t: None,
+        })
+        .id();
+    mc.connect(
+        &ComponentConnection { component_id: tof, node_index: 0 },
+        &ComponentConnection { component_id: out, node_index: 0 },
+    )
+    .unwrap();
+
+    fn run(sim: &mut Simulator, ticks: usize) -> Vec<bool> {
+        (0..ticks)
+            .map(|_| {
+                sim.tick();
+                sim.output_by_label("Out").unwrap().as_bool()
+            })
+            .collect()
+    }
+
+    // enable has never been on, so there's nothing to time
+    let mut sim = Simulator::new(&mc);
+    assert_eq!(run(&mut sim, 5), [false; 5]);
+
+    // once it turns off again, the output stays on for the duration
+    sim.set_input_by_label("Enable", Value::OnOff(true))
+        .unwrap();
+    assert_eq!(run(&mut sim, 3), [false; 3]);
+    sim.set_input_by_label("Enable", Value::OnOff(false))
+        .unwrap();
+    assert_eq!(run(&mut sim, 6), [false, false, true, true, true, false]);
+}
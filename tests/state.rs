@@ -0,0 +1,120 @@
+use sw_rs::{
+    microcontroller::{
+        builder::Builder,
+        components::{
+            state::{ComponentState, MemoryRegisterState, PIDState, StateError},
+            ComponentType,
+        },
+        Microcontroller,
+    },
+    util::AnyComponentMut,
+};
+
+fn quadcopter() -> (String, Microcontroller) {
+    let src =
+        std::fs::read_to_string("samples/microcontroller/Quadcopter Controller 2.xml").unwrap();
+    let mc = Microcontroller::from_xml_str(&src).unwrap();
+    (src, mc)
+}
+
+fn component_mut(mc: &mut Microcontroller, id: u32) -> &mut ComponentType {
+    mc.components_mut()
+        .find_map(|c| match c {
+            AnyComponentMut::Component(c) if c.id() == id => Some(&mut c.component),
+            _ => None,
+        })
+        .unwrap()
+}
+
+#[test]
+fn test_read_state() {
+    let (src, mut mc) = quadcopter();
+
+    let pid = component_mut(&mut mc, 54);
+    let state = pid.state().unwrap();
+    assert_eq!(
+        state,
+        Some(ComponentState::PID(PIDState {
+            total_error: 9420.751,
+            prev_error: 0.28322,
+            prev_error_set: true,
+            prev_process_var: 0.0,
+        }))
+    );
+
+    // writing back the same state doesn't change anything
+    pid.set_state(&state.unwrap()).unwrap();
+    assert_eq!(mc.to_xml_string().unwrap(), src);
+}
+
+#[test]
+fn test_write_state() {
+    let (src, mut mc) = quadcopter();
+
+    let pid = component_mut(&mut mc, 54);
+    pid.set_state(&ComponentState::PID(PIDState {
+        total_error: 12.5,
+        prev_error: -0.25,
+        prev_error_set: true,
+        prev_process_var: 0.0,
+    }))
+    .unwrap();
+    let out = mc.to_xml_string().unwrap();
+    assert!(out.contains(r#"<object id="54" te="12.5" pe="-0.25" pes="true">"#));
+
+    // state has to match the component
+    assert_eq!(
+        component_mut(&mut mc, 54).set_state(&ComponentState::MemoryRegister(
+            MemoryRegisterState { value: 1.0 }
+        )),
+        Err(StateError::WrongComponent {
+            component: "PIDControllerAdvanced",
+            state: "MemoryRegister"
+        })
+    );
+
+    // resetting removes all the saved state
+    let reset = mc.reset_states();
+    assert!(reset.contains(&54) && reset.contains(&55));
+    let out = mc.to_xml_string().unwrap();
+    assert!(!out.contains(" te=") && !out.contains(" pes="));
+    assert_ne!(out, src);
+}
+
+#[test]
+fn test_memory_register_state() {
+    let mut mc = Microcontroller::default();
+    let mut b = Builder::new(&mut mc);
+    let on = b.constant_on();
+    let n = b.constant(3);
+    let reg = b.memory_register(on, None, n, 0).component_id();
+
+    let c = component_mut(&mut mc, reg);
+    assert_eq!(
+        c.state().unwrap(),
+        Some(ComponentState::MemoryRegister(MemoryRegisterState {
+            value: 0.0
+        }))
+    );
+    c.set_state(&ComponentState::MemoryRegister(MemoryRegisterState {
+        value: 42.0,
+    }))
+    .unwrap();
+    assert!(mc.to_xml_string().unwrap().contains(r#" memory="42""#));
+
+    let xml = mc
+        .to_xml_string()
+        .unwrap()
+        .replace(r#"memory="42""#, r#"memory="lots""#);
+    let mut mc = Microcontroller::from_xml_str(&xml).unwrap();
+    assert_eq!(
+        component_mut(&mut mc, reg).state(),
+        Err(StateError::InvalidValue { attribute: "memory", value: "lots".into() })
+    );
+    assert_eq!(mc.reset_states(), vec![reg]);
+    assert!(!mc.to_xml_string().unwrap().contains("memory="));
+    assert!(component_mut(&mut mc, on.component_id())
+        .state()
+        .unwrap()
+        .is_none());
+}
@@ -0,0 +1,106 @@
+use sw_rs::microcontroller::{
+    components::{ComponentType, CompositeChannel, TypedInputConnection, TypedOutputConnection},
+    types::Type,
+    MCSerDeError, MCValidationError, Microcontroller,
+};
+
+fn mul_const() -> String {
+    std::fs::read_to_string("samples/microcontroller/mul_const.xml").unwrap()
+}
+
+fn validation_error(xml: &str) -> MCValidationError {
+    match Microcontroller::from_xml_str(xml) {
+        Err(MCSerDeError::ValidationError(e)) => e,
+        other => panic!("expected a validation error, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_component_id_too_high() {
+    let xml = mul_const().replace(r#"id_counter="7""#, r#"id_counter="6""#);
+    assert!(matches!(
+        validation_error(&xml),
+        MCValidationError::ComponentIdTooHigh { found_id: 7, max: 6 }
+    ));
+}
+
+#[test]
+fn test_bad_connections() {
+    let xml = mul_const().replace(r#"<in1 component_id="6"/>"#, r#"<in1 component_id="9"/>"#);
+    assert!(matches!(
+        validation_error(&xml),
+        MCValidationError::MissingComponent { component_id: 7, input_index: 0, missing_id: 9 }
+    ));
+
+    let xml = mul_const().replace(
+        r#"<in2 component_id="3"/>"#,
+        r#"<in2 component_id="3" node_index="1"/>"#,
+    );
+    assert!(matches!(
+        validation_error(&xml),
+        MCValidationError::InvalidOutputIndex {
+            component_id: 7,
+            source_id: 3,
+            output_index: 1,
+            ..
+        }
+    ));
+
+    // turn the input into an on/off
+    let xml = mul_const()
+        .replace(r#"mode="1" type="1""#, r#"mode="1" type="0""#)
+        .replace(r#"<c type="2">"#, r#"<c>"#);
+    assert!(matches!(
+        validation_error(&xml),
+        MCValidationError::TypeMismatch {
+            component_id: 7,
+            input_index: 1,
+            expected: Type::Number,
+            found: Type::OnOff
+        }
+    ));
+}
+
+#[test]
+fn test_io_positions() {
+    let xml = mul_const().replace(r#"<position x="1"/>"#, r#"<position x="2"/>"#);
+    assert!(matches!(
+        validation_error(&xml),
+        MCValidationError::IONodeOutOfBounds { node_id: 2, .. }
+    ));
+
+    let xml = mul_const().replace(r#"<position x="1"/>"#, "");
+    assert!(matches!(
+        validation_error(&xml),
+        MCValidationError::DuplicateIONodePosition { node_id: 2, .. }
+    ));
+}
+
+#[test]
+fn test_validate_all() {
+    let mut mc = Microcontroller::from_xml_str(&mul_const()).unwrap();
+    assert!(mc.validate_all().is_empty());
+
+    mc.width = 1;
+    mc.add_component(ComponentType::CompositeReadNum {
+        composite: TypedInputConnection::empty(),
+        variable_channel: TypedInputConnection::empty(),
+        out: TypedOutputConnection::default(),
+        channel: CompositeChannel::Fixed(32),
+    });
+
+    let errors = mc.validate_all();
+    assert_eq!(errors.len(), 2, "{errors:?}");
+    assert!(matches!(
+        errors[0],
+        MCValidationError::IONodeOutOfBounds { node_id: 2, .. }
+    ));
+    assert!(matches!(
+        errors[1],
+        MCValidationError::InvalidCompositeChannel { channel: 33, .. }
+    ));
+    assert!(matches!(
+        mc.validate(),
+        Err(MCValidationError::IONodeOutOfBounds { .. })
+    ));
+}